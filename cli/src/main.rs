@@ -5,12 +5,17 @@
 //! CLI are automatically picked up by the other tools.
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::io::{self, Write};
+use std::path::PathBuf;
 
 use ddk_core::commands;
-use ddk_core::commands::CompileFilterOptions;
-use ddk_core::projects::{CompilerConfigurations, ProjectsData};
+use ddk_core::commands::{CompileFilterOptions, CompileTarget, EncodingConvertOptions, PolicyTarget, SourceTarget};
+use ddk_core::encoding::LineEnding;
+use ddk_core::reports;
+use ddk_core::projects::history::HistoryQuery;
+use ddk_core::projects::last_diagnostics::DiagnosticsQuery;
+use ddk_core::projects::{CompilerConfigurations, DiagnosticKind, OutputLanguage, PartialCompilerConfiguration, ProjectsData, WarningPolicy};
 use ddk_core::state::Stateful;
 
 /// DDK – Delphi Development Kit CLI
@@ -45,6 +50,14 @@ enum Commands {
         #[arg(long, short)]
         project: Option<usize>,
 
+        /// Compile every project of this workspace ID.
+        #[arg(long, short, conflicts_with_all = ["project", "group_project"])]
+        workspace: Option<usize>,
+
+        /// Compile every project of the group project.
+        #[arg(long, short, conflicts_with = "project")]
+        group_project: bool,
+
         /// Show warning lines verbatim instead of suppressing them.
         #[arg(long)]
         show_warnings: bool,
@@ -57,8 +70,94 @@ enum Commands {
         /// warnings/hints that were not shown verbatim.
         #[arg(long)]
         summarize_diagnostics: bool,
+
+        /// Write the build's diagnostics as a SARIF 2.1.0 report to this file.
+        /// Locations are relative to the git repository containing the
+        /// current directory (or the current directory itself).
+        #[arg(long, value_name = "FILE")]
+        sarif: Option<PathBuf>,
+
+        /// Write a JUnit XML report with one test case per project to this file.
+        #[arg(long, value_name = "FILE")]
+        junit: Option<PathBuf>,
+
+        /// Fail when a warning or hint is not in the project's baseline
+        /// (`<project>.ddk-baseline.json` next to the project file).
+        #[arg(long)]
+        fail_on_new_warnings: bool,
+
+        /// Record this build's warnings and hints as the projects' baselines.
+        #[arg(long, conflicts_with = "fail_on_new_warnings")]
+        update_baseline: bool,
+
+        /// With -w / -g / --matrix: keep compiling the remaining projects (or
+        /// combinations) after one fails instead of stopping at the first
+        /// failure.
+        #[arg(long, short = 'k')]
+        keep_going: bool,
+
+        /// With -w / -g: build up to N projects at the same time, respecting
+        /// the dependencies between them (0: one per CPU core).
+        #[arg(long, short = 'j', value_name = "N")]
+        jobs: Option<usize>,
+
+        /// When compiling a single project: build the projects it depends on
+        /// in its workspace first (see `ddk project depend`).
+        #[arg(long, short = 'd', conflicts_with_all = ["workspace", "group_project"])]
+        with_dependencies: bool,
+
+        /// Fail instead of waiting when another DDK process (VS Code, the MCP
+        /// server, another `ddk`) is building.
+        #[arg(long)]
+        no_wait: bool,
+
+        /// Build the project (-p, or the active one) or the workspace (-w)
+        /// once for every combination of --config, --platform and
+        /// --compiler, and summarize the results in a grid.
+        #[arg(long, conflicts_with_all = ["group_project", "with_dependencies", "fail_on_new_warnings", "update_baseline"])]
+        matrix: bool,
+
+        /// With --matrix: build configurations, repeatable or comma
+        /// separated (default: every configuration of the project files).
+        #[arg(long = "config", value_name = "CONFIG", value_delimiter = ',', requires = "matrix")]
+        configs: Vec<String>,
+
+        /// With --matrix: platforms, repeatable or comma separated (default:
+        /// every enabled platform of the project files).
+        #[arg(long = "platform", value_name = "PLATFORM", value_delimiter = ',', requires = "matrix")]
+        platforms: Vec<String>,
+
+        /// With --matrix: compiler keys, repeatable or comma separated
+        /// (default: the compiler of the workspace).
+        #[arg(long = "compiler", value_name = "KEY", value_delimiter = ',', requires = "matrix")]
+        compilers: Vec<String>,
     },
 
+    /// List queued, running and recently finished builds of every DDK process.
+    Queue,
+
+    /// Cancel the running build, also when another DDK process runs it.
+    Cancel,
+
+    /// Inspect the builds recorded by every DDK process.
+    #[command(subcommand)]
+    History(HistoryCmd),
+
+    /// Show the diagnostics of the last build of each project, without
+    /// compiling, or parse a saved build log.
+    Diagnostics(DiagnosticsArgs),
+
+    /// Explain a compiler message code: what it means, common causes and fixes.
+    Explain {
+        /// The message code, e.g. W1035.
+        code: String,
+    },
+
+    /// Show or change the warning policy of a workspace or project: warnings
+    /// and hints that fail the build, ones that are ignored, and how many
+    /// warnings a build may report.  Options add to the current policy.
+    Policy(PolicyArgs),
+
     /// Show environment info for the active project.
     Env,
 
@@ -70,10 +169,18 @@ enum Commands {
         /// Path to the file to format.
         file: String,
         /// Encoding of the source file, e.g. "utf-8", "windows-1252", "oem".
-        /// Defaults to "utf-8" when not specified.
+        /// Auto-detected (BOM, UTF-8 validity, ANSI fallback) when not specified.
         #[arg(long, short = 'e')]
         encoding: Option<String>,
+        /// Encoding assumed for files without a BOM that are not valid UTF-8,
+        /// e.g. "windows-1252". Defaults to the system ANSI codepage.
+        #[arg(long)]
+        ansi_fallback: Option<String>,
     },
+
+    /// Inspect or convert the encoding of Delphi source files.
+    #[command(subcommand)]
+    Encoding(EncodingCmd),
 }
 
 #[derive(Subcommand)]
@@ -85,6 +192,162 @@ enum ProjectCmd {
         /// The project ID to select.
         id: usize,
     },
+    /// Declare that a project is built after another project of its workspace.
+    Depend {
+        /// The project ID that depends on the other one.
+        id: usize,
+        /// The project ID it depends on.
+        #[arg(long)]
+        on: usize,
+        /// Workspace ID; only needed when both projects share several workspaces.
+        #[arg(long, short)]
+        workspace: Option<usize>,
+    },
+    /// Remove a dependency declared with `ddk project depend`.
+    Undepend {
+        /// The project ID that depends on the other one.
+        id: usize,
+        /// The project ID it depends on.
+        #[arg(long)]
+        on: usize,
+        /// Workspace ID; only needed when both projects share several workspaces.
+        #[arg(long, short)]
+        workspace: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryCmd {
+    /// List recorded builds, most recent first.
+    List {
+        /// Only builds that compiled this project ID; shows its own status,
+        /// build time and diagnostics.
+        #[arg(long, short)]
+        project: Option<usize>,
+        /// Only builds with this compiler configuration key (e.g. "12.0").
+        #[arg(long, short)]
+        compiler: Option<String>,
+        /// Only successful builds.
+        #[arg(long, conflicts_with = "failed")]
+        succeeded: bool,
+        /// Only failed or cancelled builds.
+        #[arg(long)]
+        failed: bool,
+        /// Show at most N builds.
+        #[arg(long, short = 'n', value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show a recorded build with each of its projects.
+    Show {
+        /// The build ID, as listed by `ddk history list`.
+        id: u64,
+    },
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+struct DiagnosticsArgs {
+    #[command(subcommand)]
+    command: Option<DiagnosticsCmd>,
+    /// Only diagnostics of this project ID.
+    #[arg(long, short)]
+    project: Option<usize>,
+    /// Only diagnostics of this severity; "error" includes fatal errors.
+    #[arg(long, short, value_enum)]
+    severity: Option<SeverityArg>,
+    /// Only diagnostics whose file path contains this text (ignoring case).
+    #[arg(long, short)]
+    file: Option<String>,
+    /// Only diagnostics with this code, e.g. W1036.
+    #[arg(long, short)]
+    code: Option<String>,
+}
+
+#[derive(Args)]
+struct PolicyArgs {
+    /// The project ID whose policy to show or change.
+    #[arg(long, short, required_unless_present = "workspace", conflicts_with = "workspace")]
+    project: Option<usize>,
+    /// The workspace ID whose policy to show or change; it applies to every
+    /// project built from the workspace.
+    #[arg(long, short)]
+    workspace: Option<usize>,
+    /// Treat this warning or hint code as an error, e.g. W1036 (repeatable).
+    #[arg(long = "error", value_name = "CODE")]
+    errors: Vec<String>,
+    /// Ignore this warning or hint code, e.g. H2164 (repeatable).
+    #[arg(long = "suppress", value_name = "CODE")]
+    suppress: Vec<String>,
+    /// Fail builds that report more than N warnings.
+    #[arg(long, value_name = "N")]
+    max_warnings: Option<usize>,
+    /// Start from an empty policy instead of the current one.
+    #[arg(long)]
+    clear: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SeverityArg {
+    Fatal,
+    Error,
+    Warning,
+    Hint,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LanguageArg {
+    Auto,
+    English,
+    German,
+    French,
+    Japanese,
+}
+
+impl From<LanguageArg> for OutputLanguage {
+    fn from(arg: LanguageArg) -> Self {
+        match arg {
+            LanguageArg::Auto => OutputLanguage::Auto,
+            LanguageArg::English => OutputLanguage::English,
+            LanguageArg::German => OutputLanguage::German,
+            LanguageArg::French => OutputLanguage::French,
+            LanguageArg::Japanese => OutputLanguage::Japanese,
+        }
+    }
+}
+
+impl From<SeverityArg> for DiagnosticKind {
+    fn from(arg: SeverityArg) -> Self {
+        match arg {
+            SeverityArg::Fatal => DiagnosticKind::FATAL,
+            SeverityArg::Error => DiagnosticKind::ERROR,
+            SeverityArg::Warning => DiagnosticKind::WARN,
+            SeverityArg::Hint => DiagnosticKind::HINT,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum DiagnosticsCmd {
+    /// Parse a saved build log (e.g. from CI, a colleague, or `ddk history
+    /// show`) into diagnostics, the same way a build's output is parsed.
+    Parse {
+        /// The build log file.
+        log_file: PathBuf,
+        /// Project ID the log belongs to: relative file names are resolved
+        /// against its directory.
+        #[arg(long, short, conflicts_with = "project_dir")]
+        project: Option<usize>,
+        /// Directory relative file names are resolved against. Defaults to
+        /// the current directory.
+        #[arg(long, value_name = "DIR")]
+        project_dir: Option<PathBuf>,
+        /// Also write the diagnostics as a SARIF 2.1.0 report to this file.
+        #[arg(long, value_name = "FILE")]
+        sarif: Option<PathBuf>,
+        /// Language of the compiler's labels ("Warnung", "Erreur", …).
+        #[arg(long, value_enum, default_value = "auto")]
+        language: LanguageArg,
+    },
 }
 
 #[derive(Subcommand)]
@@ -96,6 +359,115 @@ enum CompilerCmd {
         /// The compiler configuration key (e.g. "12.0").
         key: String,
     },
+    /// Change where a compiler configuration finds MSBuild and its build environment.
+    Update {
+        /// The compiler configuration key (e.g. "12.0").
+        key: String,
+        /// Path to msbuild.exe. An empty value restores the automatic lookup.
+        #[arg(long, value_name = "FILE")]
+        msbuild_path: Option<String>,
+        /// Script whose SET lines make up the build environment, instead of
+        /// bin\rsvars.bat. An empty value restores rsvars.bat.
+        #[arg(long, value_name = "FILE")]
+        env_script: Option<String>,
+        /// Set an extra environment variable for builds (repeatable).
+        #[arg(long = "env", value_name = "NAME=VALUE", value_parser = parse_env_var)]
+        env: Vec<(String, String)>,
+        /// Remove an extra environment variable (repeatable).
+        #[arg(long, value_name = "NAME")]
+        unset_env: Vec<String>,
+        /// Remove all extra environment variables before applying --env.
+        #[arg(long)]
+        clear_env: bool,
+        /// Language of the compiler's output labels ("Warnung", "Erreur",
+        /// …); auto recognizes every supported language.
+        #[arg(long, value_enum)]
+        output_language: Option<LanguageArg>,
+    },
+}
+
+fn parse_env_var(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, got \"{value}\"")),
+    }
+}
+
+#[derive(Subcommand)]
+enum EncodingCmd {
+    /// List each source file's detected encoding, BOM and line endings.
+    Report {
+        #[command(flatten)]
+        target: SourceTargetArgs,
+        /// Encoding assumed for files without a BOM that are not valid UTF-8,
+        /// e.g. "windows-1252". Defaults to the system ANSI codepage.
+        #[arg(long)]
+        ansi_fallback: Option<String>,
+    },
+    /// Re-encode source files, e.g. windows-1252 → UTF-8 with BOM.
+    /// Refuses (and writes nothing) if any character cannot be represented
+    /// in the target encoding.
+    Convert {
+        #[command(flatten)]
+        target: SourceTargetArgs,
+        /// Encoding assumed for files without a BOM that are not valid UTF-8,
+        /// e.g. "windows-1252". Defaults to the system ANSI codepage.
+        #[arg(long)]
+        ansi_fallback: Option<String>,
+        /// Target encoding, e.g. "utf-8", "windows-1252".
+        #[arg(long)]
+        to: String,
+        /// Write a byte-order mark (UTF encodings only).
+        #[arg(long)]
+        bom: bool,
+        /// Target line endings. Keeps each file's current style by default.
+        #[arg(long, value_enum)]
+        line_endings: Option<LineEndingArg>,
+        /// Show what would change without writing any file.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Selects the source files of an `encoding` subcommand: a path, a project
+/// or a workspace.
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct SourceTargetArgs {
+    /// File or directory (searched recursively for .pas/.dpr/.dpk/.inc).
+    path: Option<String>,
+    /// Project ID whose sources to process.
+    #[arg(long, short)]
+    project: Option<usize>,
+    /// Workspace ID whose projects' sources to process.
+    #[arg(long, short)]
+    workspace: Option<usize>,
+}
+
+impl SourceTargetArgs {
+    fn into_target(self) -> SourceTarget {
+        match (self.path, self.project, self.workspace) {
+            (Some(path), _, _) => SourceTarget::Path(path),
+            (_, Some(id), _) => SourceTarget::Project(id),
+            (_, _, Some(id)) => SourceTarget::Workspace(id),
+            _ => unreachable!("clap enforces exactly one target"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LineEndingArg {
+    Crlf,
+    Lf,
+}
+
+impl From<LineEndingArg> for LineEnding {
+    fn from(arg: LineEndingArg) -> Self {
+        match arg {
+            LineEndingArg::Crlf => LineEnding::Crlf,
+            LineEndingArg::Lf => LineEnding::Lf,
+        }
+    }
 }
 
 #[tokio::main]
@@ -112,6 +484,117 @@ async fn main() -> Result<()> {
             println!("{readme}");
         }
 
+        Commands::Queue => {
+            let result = commands::cmd_build_queue().await?;
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                print!("{result}");
+            }
+        }
+
+        Commands::Cancel => {
+            let result = commands::cmd_cancel_build().await?;
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                println!("{result}");
+            }
+        }
+
+        Commands::History(cmd) => match cmd {
+            HistoryCmd::List { project, compiler, succeeded, failed, limit } => {
+                let query = HistoryQuery {
+                    project_id: project,
+                    compiler_key: compiler,
+                    success: if succeeded { Some(true) } else if failed { Some(false) } else { None },
+                    limit: Some(limit),
+                };
+                let result = commands::cmd_build_history(query).await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                } else {
+                    print!("{result}");
+                }
+            }
+            HistoryCmd::Show { id } => {
+                let result = commands::cmd_build_history_show(id).await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                } else {
+                    print!("{result}");
+                }
+            }
+        },
+
+        Commands::Diagnostics(args) => match args.command {
+            None => {
+                let query = DiagnosticsQuery {
+                    project_id: args.project,
+                    severity: args.severity.map(DiagnosticKind::from),
+                    file: args.file,
+                    code: args.code,
+                };
+                let result = commands::cmd_last_diagnostics(query).await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                } else {
+                    print!("{result}");
+                }
+            }
+            Some(DiagnosticsCmd::Parse { log_file, project, project_dir, sarif, language }) => {
+                let result = commands::cmd_parse_build_log(log_file, project, project_dir, language.into()).await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                } else {
+                    print!("{result}");
+                }
+                if let Some(sarif_path) = sarif {
+                    let cwd = std::env::current_dir()?;
+                    let root = reports::find_repository_root(&cwd).unwrap_or(cwd);
+                    reports::write_sarif(&sarif_path, &result.to_compile_output(), Some(&root))?;
+                    if !cli.json {
+                        println!("SARIF report written to {}", sarif_path.display());
+                    }
+                }
+            }
+        },
+
+        Commands::Explain { code } => {
+            let result = commands::cmd_explain_diagnostic(&code).await?;
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                print!("{result}");
+            }
+        }
+
+        Commands::Policy(args) => {
+            let target = match (args.project, args.workspace) {
+                (Some(id), _) => PolicyTarget::Project(id),
+                (_, Some(id)) => PolicyTarget::Workspace(id),
+                _ => unreachable!("clap requires a project or a workspace"),
+            };
+            let changed = args.clear || !args.errors.is_empty() || !args.suppress.is_empty() || args.max_warnings.is_some();
+            let result = if changed {
+                let mut policy = match args.clear {
+                    true => WarningPolicy::default(),
+                    false => commands::cmd_warning_policy(target).await?.policy,
+                };
+                policy.errors.extend(args.errors);
+                policy.suppress.extend(args.suppress);
+                policy.max_warnings = args.max_warnings.or(policy.max_warnings);
+                commands::cmd_set_warning_policy(target, policy).await?
+            } else {
+                commands::cmd_warning_policy(target).await?
+            };
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                println!("{result}");
+            }
+        }
+
         Commands::Env => {
             let info = commands::cmd_get_environment_info().await?;
             if cli.json {
@@ -138,6 +621,22 @@ async fn main() -> Result<()> {
                     println!("{result}");
                 }
             }
+            ProjectCmd::Depend { id, on, workspace } => {
+                let result = commands::cmd_add_project_dependency(workspace, id, on).await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                } else {
+                    println!("{result}");
+                }
+            }
+            ProjectCmd::Undepend { id, on, workspace } => {
+                let result = commands::cmd_remove_project_dependency(workspace, id, on).await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                } else {
+                    println!("{result}");
+                }
+            }
         },
 
         Commands::Compiler(cmd) => match cmd {
@@ -161,29 +660,102 @@ async fn main() -> Result<()> {
                     println!("{result}");
                 }
             }
+            CompilerCmd::Update { key, msbuild_path, env_script, env, unset_env, clear_env, output_language } => {
+                let extra_env = if clear_env || !env.is_empty() || !unset_env.is_empty() {
+                    let mut extra_env = if clear_env {
+                        Default::default()
+                    } else {
+                        commands::cmd_list_compilers()
+                            .await?
+                            .into_iter()
+                            .find(|c| c.key == key)
+                            .map(|c| c.extra_env)
+                            .unwrap_or_default()
+                    };
+                    for name in &unset_env {
+                        extra_env.remove(name);
+                    }
+                    extra_env.extend(env);
+                    Some(extra_env)
+                } else {
+                    None
+                };
+                let data = PartialCompilerConfiguration {
+                    condition: None,
+                    product_name: None,
+                    product_version: None,
+                    package_version: None,
+                    compiler_version: None,
+                    installation_path: None,
+                    build_arguments: None,
+                    backend: None,
+                    msbuild_path,
+                    env_script,
+                    extra_env,
+                    output_language: output_language.map(OutputLanguage::from),
+                };
+                let result = commands::cmd_update_compiler(key, data).await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                } else {
+                    println!("{result}");
+                }
+            }
         },
 
         Commands::Compile {
             rebuild,
             project,
+            workspace,
+            group_project,
             show_warnings,
             show_hints,
             summarize_diagnostics,
+            sarif,
+            junit,
+            fail_on_new_warnings,
+            update_baseline,
+            keep_going,
+            jobs,
+            with_dependencies,
+            no_wait,
+            matrix,
+            configs,
+            platforms,
+            compilers,
         } => {
+            let target = match (workspace, group_project) {
+                _ if matrix => CompileTarget::Matrix {
+                    project_id: project,
+                    workspace_id: workspace,
+                    configurations: configs,
+                    platforms,
+                    compilers,
+                },
+                (Some(id), _) => CompileTarget::Workspace(id),
+                (_, true) => CompileTarget::GroupProject,
+                _ => CompileTarget::Project(project),
+            };
             let filter = CompileFilterOptions {
                 trim_banners: true,
                 show_warnings,
                 show_hints,
                 summarize_diagnostics,
+                fail_on_new_warnings,
+                keep_going,
+                jobs,
+                with_dependencies,
+                no_wait,
             };
-            if cli.json {
-                let output = commands::cmd_compile(rebuild, project, filter).await?;
+            let output = if cli.json {
+                let output = commands::cmd_compile_target(target, rebuild, filter, None).await?;
                 println!("{}", serde_json::to_string_pretty(&output)?);
+                output
             } else {
                 let stdout = std::sync::Arc::new(std::sync::Mutex::new(io::stdout()));
-                let output = commands::cmd_compile_with_progress(
+                let output = commands::cmd_compile_target(
+                    target,
                     rebuild,
-                    project,
                     filter,
                     Some(std::sync::Arc::new(move |line: String| {
                         let mut handle = stdout.lock().unwrap();
@@ -195,17 +767,89 @@ async fn main() -> Result<()> {
                 if output.lines.is_empty() {
                     print!("{output}");
                 }
+                output
+            };
+            if let Some(sarif_path) = sarif {
+                let cwd = std::env::current_dir()?;
+                let root = reports::find_repository_root(&cwd).unwrap_or(cwd);
+                reports::write_sarif(&sarif_path, &output, Some(&root))?;
+                if !cli.json {
+                    println!("SARIF report written to {}", sarif_path.display());
+                }
+            }
+            if let Some(junit_path) = junit {
+                reports::write_junit(&junit_path, &output)?;
+                if !cli.json {
+                    println!("JUnit report written to {}", junit_path.display());
+                }
+            }
+            if !cli.json
+                && let Some(baseline) = &output.baseline
+            {
+                println!(
+                    "Baseline: {} new, {} known warning(s)/hint(s).",
+                    baseline.new.len(),
+                    baseline.known.len()
+                );
+                for diagnostic in &baseline.new {
+                    println!("  new: {diagnostic}");
+                }
+            }
+            if update_baseline {
+                for path in commands::cmd_update_baseline(&output).await? {
+                    if !cli.json {
+                        println!("Baseline written to {}", path.display());
+                    }
+                }
+            }
+            if !output.success && !output.cancelled {
+                std::process::exit(1);
             }
         }
 
-        Commands::Format { file, encoding } => {
-            let result = commands::cmd_format_file(file, encoding).await?;
+        Commands::Format { file, encoding, ansi_fallback } => {
+            let result = commands::cmd_format_file(file, encoding, ansi_fallback.as_deref()).await?;
             if cli.json {
                 println!("{}", serde_json::to_string_pretty(&result)?);
             } else {
                 println!("{result}");
             }
         }
+
+        Commands::Encoding(cmd) => match cmd {
+            EncodingCmd::Report { target, ansi_fallback } => {
+                if let Some(label) = &ansi_fallback {
+                    ddk_core::encoding::set_ansi_fallback(label)?;
+                }
+                let report = commands::cmd_encoding_report(target.into_target()).await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    println!("{report}");
+                }
+            }
+            EncodingCmd::Convert { target, ansi_fallback, to, bom, line_endings, dry_run } => {
+                if let Some(label) = &ansi_fallback {
+                    ddk_core::encoding::set_ansi_fallback(label)?;
+                }
+                let options = EncodingConvertOptions {
+                    to,
+                    bom,
+                    line_ending: line_endings.map(Into::into),
+                    dry_run,
+                };
+                let result = commands::cmd_encoding_convert(target.into_target(), options).await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                } else {
+                    println!("{result}");
+                }
+                let refused = result.refused().count();
+                if refused > 0 && !dry_run {
+                    anyhow::bail!("Conversion refused for {refused} file(s); no files were written.");
+                }
+            }
+        },
     }
 
     Ok(())
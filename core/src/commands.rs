@@ -6,10 +6,15 @@
 
 use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
+use std::path::{Path, PathBuf};
 
+use crate::encoding::{LineEnding, SourceEncoding};
 use crate::lsp_types::{CompileProjectParams, CompilerProgress, CompilerProgressParams};
 use crate::projects::*;
+use crate::projects::baseline::{self, BaselineComparison, WarningBaseline};
+use crate::projects::matrix::MatrixCell;
 use crate::state::*;
 
 // ---------------------------------------------------------------------------
@@ -25,6 +30,9 @@ pub struct ProjectSummary {
     pub dproj: Option<String>,
     pub exe: Option<String>,
     pub active: bool,
+    /// Projects of the same workspace this one is built after.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<usize>,
 }
 
 /// Summary of a user-defined workspace and its projects.
@@ -70,6 +78,10 @@ impl fmt::Display for ProjectListResult {
                     if let Some(exe) = &p.exe {
                         writeln!(f, "       exe: {exe}")?;
                     }
+                    if !p.depends_on.is_empty() {
+                        let ids: Vec<String> = p.depends_on.iter().map(|id| id.to_string()).collect();
+                        writeln!(f, "       depends on: {}", ids.join(", "))?;
+                    }
                 }
             }
         }
@@ -167,6 +179,33 @@ pub struct CompilerSummary {
     pub product_version: usize,
     pub compiler_version: usize,
     pub installation_path: String,
+    #[serde(default)]
+    pub backend: BuildBackendKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub msbuild_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_script: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra_env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "OutputLanguage::is_auto")]
+    pub output_language: OutputLanguage,
+}
+
+impl CompilerSummary {
+    fn new(key: &str, cfg: &CompilerConfiguration) -> Self {
+        Self {
+            key: key.to_string(),
+            product_name: cfg.product_name.clone(),
+            product_version: cfg.product_version,
+            compiler_version: cfg.compiler_version,
+            installation_path: cfg.installation_path.clone(),
+            backend: cfg.backend.clone(),
+            msbuild_path: cfg.msbuild_path.clone(),
+            env_script: cfg.env_script.clone(),
+            extra_env: cfg.extra_env.clone(),
+            output_language: cfg.output_language,
+        }
+    }
 }
 
 impl fmt::Display for CompilerSummary {
@@ -178,7 +217,22 @@ impl fmt::Display for CompilerSummary {
             name = self.product_name,
             ver = self.product_version,
             path = self.installation_path,
-        )
+        )?;
+        if let Some(msbuild_path) = &self.msbuild_path {
+            write!(f, "\n    MSBuild: {msbuild_path}")?;
+        }
+        if let Some(env_script) = &self.env_script {
+            write!(f, "\n    Environment script: {env_script}")?;
+        }
+        if !self.output_language.is_auto() {
+            write!(f, "\n    Output language: {:?}", self.output_language)?;
+        }
+        let mut extra_env: Vec<_> = self.extra_env.iter().collect();
+        extra_env.sort();
+        for (name, value) in extra_env {
+            write!(f, "\n    {name}={value}")?;
+        }
+        Ok(())
     }
 }
 
@@ -199,6 +253,97 @@ impl fmt::Display for SelectProjectResult {
     }
 }
 
+/// Build jobs of the running DDK processes (see [`cmd_build_queue`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildQueueResult {
+    pub jobs: Vec<queue::BuildJobInfo>,
+    /// The process currently building, which may be another one than the
+    /// owner of the running job listed here.
+    #[serde(default)]
+    pub lock_holder: Option<build_lock::BuildLockHolder>,
+}
+
+impl fmt::Display for BuildQueueResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(holder) = &self.lock_holder {
+            writeln!(f, "Building: {holder}")?;
+        }
+        if self.jobs.is_empty() {
+            return writeln!(f, "No build jobs.");
+        }
+        let time = |millis: i64| {
+            chrono::DateTime::from_timestamp_millis(millis)
+                .map(|t| t.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+                .unwrap_or_default()
+        };
+        for job in &self.jobs {
+            write!(f, "#{:<4} {:<9} {}  {}  ({} {}", job.id, job.state, time(job.queued_at), job.description, job.program, job.pid)?;
+            if let (Some(started), Some(finished)) = (job.started_at, job.finished_at) {
+                write!(f, ", {:.1}s", (finished - started) as f64 / 1000.0)?;
+            }
+            writeln!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Confirmation after requesting a build to be cancelled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelBuildResult {
+    /// The build that was asked to stop.
+    pub holder: Option<build_lock::BuildLockHolder>,
+}
+
+impl fmt::Display for CancelBuildResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.holder {
+            Some(holder) => write!(f, "Cancelling the build {holder}."),
+            _ => write!(f, "Cancelling the running build."),
+        }
+    }
+}
+
+/// Recorded builds, most recent first (see [`cmd_build_history`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildHistoryResult {
+    pub builds: Vec<history::BuildRecord>,
+    /// The project the builds were filtered by; its status, time and
+    /// diagnostics are listed instead of those of the whole build.
+    #[serde(default)]
+    pub project_id: Option<usize>,
+}
+
+impl fmt::Display for BuildHistoryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.builds.is_empty() {
+            return writeln!(f, "No builds recorded.");
+        }
+        for build in &self.builds {
+            let project = self.project_id.and_then(|id| build.project(id));
+            let (status, duration_ms, diagnostics) = match project {
+                Some(project) => (project.status_str(), project.duration_ms, project.diagnostics),
+                _ => (build.status_str(), build.duration_ms(), build.diagnostics()),
+            };
+            write!(
+                f,
+                "#{:<5} {}  {:<9} {:>7.1}s  {} ({})  {}",
+                build.id,
+                history::format_time(build.started_at),
+                status,
+                duration_ms as f64 / 1000.0,
+                build.compiler_name,
+                build.compiler_key,
+                build.description,
+            )?;
+            if let Some(project) = project {
+                write!(f, "  {}/{}", project.config, project.platform)?;
+            }
+            writeln!(f, "  [{}E {}W {}H]", diagnostics.errors, diagnostics.warnings, diagnostics.hints)?;
+        }
+        Ok(())
+    }
+}
+
 /// Confirmation after setting the group project compiler.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SetCompilerResult {
@@ -224,6 +369,163 @@ pub struct CompileOutput {
     pub cancelled: bool,
     pub code: i32,
     pub lines: Vec<String>,
+    /// Product name of the compiler configuration used, e.g. "Delphi 12 Athens".
+    #[serde(default)]
+    pub compiler_name: String,
+    /// Every diagnostic parsed from the compiler output, regardless of the
+    /// filter options (which only affect `lines`).
+    #[serde(default)]
+    pub diagnostics: Vec<CompileDiagnostic>,
+    /// One entry per compiled project, in compilation order.
+    #[serde(default)]
+    pub projects: Vec<ProjectCompileResult>,
+    /// Warnings and hints split into new and known ones, when a compiled
+    /// project has a baseline or `fail_on_new_warnings` was requested.
+    #[serde(default)]
+    pub baseline: Option<BaselineComparison>,
+    /// One entry per combination of a build matrix, in build order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matrix: Vec<MatrixCell>,
+}
+
+/// Outcome of a single project within a compilation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectCompileResult {
+    pub project_id: usize,
+    pub project_name: String,
+    pub success: bool,
+    pub cancelled: bool,
+    pub code: i32,
+    pub errors: usize,
+    pub warnings: usize,
+    pub hints: usize,
+    /// Wall-clock build time of this project.
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// Not built because the compilation stopped before reaching it.
+    #[serde(default)]
+    pub skipped: bool,
+    /// Not built because its output is newer than its sources.
+    #[serde(default)]
+    pub up_to_date: bool,
+    /// How the build broke the project's warning policy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy_violations: Vec<String>,
+}
+
+impl ProjectCompileResult {
+    /// Build a result for `project_id`, counting its entries in `diagnostics`.
+    pub fn new(
+        project_id: usize,
+        project_name: String,
+        success: bool,
+        cancelled: bool,
+        code: i32,
+        diagnostics: &[CompileDiagnostic],
+    ) -> Self {
+        let count = |kind: DiagnosticKind| {
+            diagnostics
+                .iter()
+                .filter(|d| d.project_id == project_id && d.severity == kind)
+                .count()
+        };
+        ProjectCompileResult {
+            project_id,
+            project_name,
+            success,
+            cancelled,
+            code,
+            errors: count(DiagnosticKind::ERROR) + count(DiagnosticKind::FATAL),
+            warnings: count(DiagnosticKind::WARN),
+            hints: count(DiagnosticKind::HINT),
+            duration_ms: 0,
+            skipped: false,
+            up_to_date: false,
+            policy_violations: Vec::new(),
+        }
+    }
+
+    /// Build the result for a project outcome reported by the compiler.
+    pub fn from_outcome(outcome: &ProjectOutcome, diagnostics: &[CompileDiagnostic]) -> Self {
+        let mut result = Self::new(
+            outcome.project_id,
+            outcome.project_name.clone(),
+            outcome.status.is_success(),
+            outcome.status == ProjectStatus::Cancelled,
+            outcome.code,
+            diagnostics,
+        );
+        result.duration_ms = outcome.duration_ms;
+        result.skipped = outcome.status == ProjectStatus::Skipped;
+        result.up_to_date = outcome.status == ProjectStatus::UpToDate;
+        result.policy_violations = outcome.policy_violations.clone();
+        result
+    }
+}
+
+/// Diagnostics parsed from a saved build log (see [`cmd_parse_build_log`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedBuildLog {
+    pub log_file: String,
+    pub diagnostics: Vec<CompileDiagnostic>,
+    pub counts: DiagnosticCounts,
+}
+
+impl ParsedBuildLog {
+    /// The parsed diagnostics as the output of a build, for the report
+    /// writers in [`crate::reports`].
+    pub fn to_compile_output(&self) -> CompileOutput {
+        CompileOutput {
+            project_name: self.log_file.clone(),
+            success: self.counts.errors == 0,
+            cancelled: false,
+            code: if self.counts.errors == 0 { 0 } else { 1 },
+            lines: Vec::new(),
+            compiler_name: String::new(),
+            diagnostics: self.diagnostics.clone(),
+            projects: Vec::new(),
+            baseline: None,
+            matrix: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for ParsedBuildLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{diagnostic}")?;
+        }
+        writeln!(f, "{}: {}.", self.log_file, self.counts)
+    }
+}
+
+/// Diagnostics of the last build of each project (see [`cmd_last_diagnostics`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastDiagnosticsResult {
+    /// Every built project matching the query, with its matching diagnostics.
+    pub projects: Vec<last_diagnostics::ProjectDiagnostics>,
+}
+
+impl fmt::Display for LastDiagnosticsResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.projects.is_empty() {
+            return writeln!(f, "No diagnostics recorded yet; compile a project first.");
+        }
+        for project in &self.projects {
+            writeln!(
+                f,
+                "{} (build #{}, {}): {}",
+                project.project_name,
+                project.build_id,
+                history::format_time(project.finished_at),
+                project.counts(),
+            )?;
+            for diagnostic in &project.diagnostics {
+                writeln!(f, "  {diagnostic}")?;
+            }
+        }
+        Ok(())
+    }
 }
 
 pub type CompileProgressCallback = std::sync::Arc<dyn Fn(String) + Send + Sync>;
@@ -247,12 +549,67 @@ pub struct CompileFilterOptions {
     /// Emit a per-file summary `<file>: X warn, Y hint` after each project's
     /// completion event for any diagnostics that were not shown verbatim.
     pub summarize_diagnostics: bool,
+    /// Fail the compilation when a warning or hint is not covered by the
+    /// project's committed baseline (see [`baseline`]).  Projects without a
+    /// baseline count every warning and hint as new.
+    pub fail_on_new_warnings: bool,
+    /// For workspace and group project compiles: build every project even
+    /// after one fails, instead of stopping at the first failure.
+    pub keep_going: bool,
+    /// For workspace and group project compiles: how many projects are
+    /// built at the same time (`Some(0)`: one per CPU core).  Output lines
+    /// of parallel builds are prefixed with `[<project id>]`.
+    pub jobs: Option<usize>,
+    /// For single project compiles: build the projects it depends on in its
+    /// workspace first (see [`cmd_add_project_dependency`]).
+    pub with_dependencies: bool,
+    /// Fail instead of waiting when another DDK process is building.
+    pub no_wait: bool,
 }
 
 impl fmt::Display for CompileOutput {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let new_diagnostics = self.baseline.as_ref().map_or(0, |b| b.new.len());
+        let policy_violations: Vec<String> = self
+            .projects
+            .iter()
+            .flat_map(|p| p.policy_violations.iter().map(move |v| format!("{}: {v}", p.project_name)))
+            .collect();
+        let failed_cells: Vec<String> = self
+            .matrix
+            .iter()
+            .filter(|cell| matches!(cell.status, ProjectStatus::Failed | ProjectStatus::Cancelled))
+            .map(|cell| format!("{cell} (exit code {})", cell.code))
+            .collect();
         let summary = if self.cancelled {
             format!("Compilation of \"{}\" was cancelled.", self.project_name)
+        } else if !self.matrix.is_empty() && self.success {
+            format!(
+                "Build matrix of \"{}\" succeeded for all {} combination(s).",
+                self.project_name,
+                self.matrix.len()
+            )
+        } else if !self.matrix.is_empty() {
+            let skipped = self.matrix.iter().filter(|cell| cell.status == ProjectStatus::Skipped).count();
+            format!(
+                "Build matrix of \"{}\" failed for {} of {} combination(s){}:\n{}",
+                self.project_name,
+                failed_cells.len(),
+                self.matrix.len(),
+                if skipped > 0 { format!(", {skipped} skipped") } else { String::new() },
+                failed_cells.join("\n")
+            )
+        } else if !self.success && self.code == 0 && !policy_violations.is_empty() {
+            format!(
+                "Compilation of \"{}\" broke the warning policy:\n{}",
+                self.project_name,
+                policy_violations.join("\n")
+            )
+        } else if !self.success && self.code == 0 && new_diagnostics > 0 {
+            format!(
+                "Compilation of \"{}\" produced {new_diagnostics} warning(s)/hint(s) not in the baseline.",
+                self.project_name
+            )
         } else if self.success {
             format!(
                 "Project \"{}\" compiled successfully.",
@@ -265,6 +622,14 @@ impl fmt::Display for CompileOutput {
             )
         };
         write!(f, "{summary}")?;
+        if let Some(baseline) = &self.baseline
+            && !baseline.new.is_empty()
+        {
+            write!(f, "\n\nNot in baseline:")?;
+            for diagnostic in &baseline.new {
+                write!(f, "\n{diagnostic}")?;
+            }
+        }
         if !self.lines.is_empty() {
             write!(f, "\n\nCompiler output:\n{}", self.lines.join("\n"))?;
         }
@@ -310,9 +675,9 @@ fn trim_banner_lines(lines: Vec<String>) -> Vec<String> {
 lazy_static::lazy_static! {
     /// Matches the formatted-diagnostic line emitted by
     /// `CompilerLineDiagnostic::Display`:
-    ///   `HH:MM:SS.mmm: [KIND][CODE] file:line[:col] - message`
+    ///   `HH:MM:SS.mmm: [KIND][CODE] file[:line[:col]] - message`
     static ref FORMATTED_DIAG_REGEX: regex::Regex = regex::Regex::new(
-        r"^\d{2}:\d{2}:\d{2}\.\d+:\s+\[(?P<kind>WARN|HINT|ERROR)\]\[[A-Z]\d+\]\s+(?P<file>.+?):\d+(?::\d+)?\s+-\s"
+        r"^\d{2}:\d{2}:\d{2}\.\d+:\s+\[(?P<kind>WARN|HINT|ERROR|FATAL)\]\[\w+\]\s+(?P<file>.+?)(?::\d+(?::\d+)?)?\s+-\s"
     ).unwrap();
 }
 
@@ -330,7 +695,7 @@ fn classify_diagnostic_line(line: &str) -> Option<(DiagKind, String)> {
     let kind = match caps.name("kind")?.as_str() {
         "WARN" => DiagKind::Warn,
         "HINT" => DiagKind::Hint,
-        "ERROR" => DiagKind::Error,
+        "ERROR" | "FATAL" => DiagKind::Error,
         _ => return None,
     };
     let file = caps.name("file")?.as_str();
@@ -468,13 +833,7 @@ pub async fn cmd_get_environment_info() -> Result<EnvironmentInfo> {
 
     let group_project_compiler = compilers
         .get(&projects_data.group_project_compiler_id)
-        .map(|c| CompilerSummary {
-            key: projects_data.group_project_compiler_id.clone(),
-            product_name: c.product_name.clone(),
-            product_version: c.product_version,
-            compiler_version: c.compiler_version,
-            installation_path: c.installation_path.clone(),
-        });
+        .map(|c| CompilerSummary::new(&projects_data.group_project_compiler_id, c));
 
     Ok(EnvironmentInfo {
         project: env_project,
@@ -494,6 +853,7 @@ pub async fn cmd_list_projects() -> Result<ProjectListResult> {
         dproj: p.dproj.clone(),
         exe: p.exe.clone(),
         active: Some(p.id) == active_id,
+        depends_on: Vec::new(),
     };
 
     let workspaces = projects_data
@@ -507,6 +867,15 @@ pub async fn cmd_list_projects() -> Result<ProjectListResult> {
                     projects_data
                         .get_project(link.project_id)
                         .map(&make_summary)
+                        .map(|summary| ProjectSummary {
+                            depends_on: ws
+                                .dependencies
+                                .iter()
+                                .filter(|d| d.project_id == summary.id)
+                                .map(|d| d.depends_on)
+                                .collect(),
+                            ..summary
+                        })
                 })
                 .collect();
             WorkspaceSummary {
@@ -567,21 +936,277 @@ pub async fn cmd_select_project(project_id: usize) -> Result<SelectProjectResult
     })
 }
 
+/// Result of declaring or removing a project dependency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectDependencyResult {
+    pub workspace_id: usize,
+    pub workspace_name: String,
+    pub project_id: usize,
+    pub depends_on: usize,
+    /// Whether the dependency exists after the command.
+    pub declared: bool,
+}
+
+impl fmt::Display for ProjectDependencyResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.declared { "depends on" } else { "no longer depends on" };
+        write!(
+            f,
+            "Project {} {verb} project {} in workspace '{}'.",
+            self.project_id, self.depends_on, self.workspace_name
+        )
+    }
+}
+
+/// The workspace holding both projects: `workspace_id` when given, otherwise
+/// the only workspace linking both.
+fn dependency_workspace(
+    data: &crate::projects::ProjectsData,
+    workspace_id: Option<usize>,
+    project_id: usize,
+    depends_on: usize,
+) -> Result<usize> {
+    if let Some(workspace_id) = workspace_id {
+        return Ok(workspace_id);
+    }
+    let links = |ws: &crate::projects::Workspace, id: usize| ws.project_links.iter().any(|link| link.project_id == id);
+    let candidates: Vec<&crate::projects::Workspace> = data
+        .workspaces
+        .iter()
+        .filter(|ws| links(ws, project_id) && links(ws, depends_on))
+        .collect();
+    match candidates.as_slice() {
+        [workspace] => Ok(workspace.id),
+        [] => bail!("No workspace contains both project {project_id} and project {depends_on}."),
+        _ => bail!(
+            "Projects {project_id} and {depends_on} share several workspaces; choose one with a workspace ID."
+        ),
+    }
+}
+
+async fn update_project_dependency(
+    workspace_id: Option<usize>,
+    project_id: usize,
+    depends_on: usize,
+    declared: bool,
+) -> Result<ProjectDependencyResult> {
+    let workspace_id = {
+        let data = PROJECTS_DATA.read().await;
+        dependency_workspace(&data, workspace_id, project_id, depends_on)?
+    };
+    let change = if declared {
+        Change::AddProjectDependency { workspace_id, project_id, depends_on }
+    } else {
+        Change::RemoveProjectDependency { workspace_id, project_id, depends_on }
+    };
+    change.execute().await?;
+
+    let data = PROJECTS_DATA.read().await;
+    let workspace_name = data
+        .get_workspace(workspace_id)
+        .map(|ws| ws.name.clone())
+        .unwrap_or_else(|| format!("ID {workspace_id}"));
+    Ok(ProjectDependencyResult { workspace_id, workspace_name, project_id, depends_on, declared })
+}
+
+/// Declares that `project_id` is built after `depends_on`.  Without a
+/// `workspace_id`, the only workspace linking both projects is used.
+pub async fn cmd_add_project_dependency(
+    workspace_id: Option<usize>,
+    project_id: usize,
+    depends_on: usize,
+) -> Result<ProjectDependencyResult> {
+    update_project_dependency(workspace_id, project_id, depends_on, true).await
+}
+
+/// Removes a dependency declared with [`cmd_add_project_dependency`].
+pub async fn cmd_remove_project_dependency(
+    workspace_id: Option<usize>,
+    project_id: usize,
+    depends_on: usize,
+) -> Result<ProjectDependencyResult> {
+    update_project_dependency(workspace_id, project_id, depends_on, false).await
+}
+
+/// Whose warning policy a command reads or changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolicyTarget {
+    Workspace(usize),
+    Project(usize),
+}
+
+/// Warning policy of a workspace or project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarningPolicyResult {
+    pub target: PolicyTarget,
+    pub name: String,
+    pub policy: WarningPolicy,
+}
+
+impl fmt::Display for WarningPolicyResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.target {
+            PolicyTarget::Workspace(_) => "workspace",
+            PolicyTarget::Project(_) => "project",
+        };
+        write!(f, "Warning policy of {kind} '{}': {}.", self.name, self.policy)
+    }
+}
+
+/// Returns the warning policy of a workspace or project.
+pub async fn cmd_warning_policy(target: PolicyTarget) -> Result<WarningPolicyResult> {
+    let data = PROJECTS_DATA.read().await;
+    let (name, policy) = match target {
+        PolicyTarget::Workspace(id) => data
+            .get_workspace(id)
+            .map(|ws| (ws.name.clone(), ws.warning_policy.clone()))
+            .ok_or_else(|| anyhow::anyhow!("Workspace with id {id} not found"))?,
+        PolicyTarget::Project(id) => data
+            .get_project(id)
+            .map(|project| (project.name.clone(), project.warning_policy.clone()))
+            .ok_or_else(|| anyhow::anyhow!("Project with id {id} not found"))?,
+    };
+    Ok(WarningPolicyResult { target, name, policy })
+}
+
+/// Replaces the warning policy of a workspace or project.  Codes are
+/// stored upper case, each once.
+pub async fn cmd_set_warning_policy(target: PolicyTarget, mut policy: WarningPolicy) -> Result<WarningPolicyResult> {
+    for codes in [&mut policy.errors, &mut policy.suppress] {
+        let mut normalized: Vec<String> = Vec::new();
+        for code in codes.iter().map(|code| code.trim().to_uppercase()) {
+            if code.is_empty() {
+                bail!("Empty diagnostic code in the warning policy.");
+            }
+            if !normalized.contains(&code) {
+                normalized.push(code);
+            }
+        }
+        *codes = normalized;
+    }
+    let change = match target {
+        PolicyTarget::Workspace(workspace_id) => Change::SetWorkspaceWarningPolicy { workspace_id, policy },
+        PolicyTarget::Project(project_id) => Change::SetProjectWarningPolicy { project_id, policy },
+    };
+    change.execute().await?;
+    cmd_warning_policy(target).await
+}
+
+/// Lists the build jobs of every running DDK process: the language server,
+/// the MCP server and other `ddk` invocations.
+pub async fn cmd_build_queue() -> Result<BuildQueueResult> {
+    Ok(BuildQueueResult { jobs: queue::all_jobs(), lock_holder: build_lock::current_holder() })
+}
+
+/// Cancels the running build, also when another DDK process runs it.
+pub async fn cmd_cancel_build() -> Result<CancelBuildResult> {
+    if queue::cancel_running().is_some() {
+        return Ok(CancelBuildResult { holder: build_lock::current_holder() });
+    }
+    let holder = build_lock::request_cancel()?;
+    Ok(CancelBuildResult { holder: Some(holder) })
+}
+
+/// Lists recorded builds matching `query`, most recent first.
+pub async fn cmd_build_history(query: history::HistoryQuery) -> Result<BuildHistoryResult> {
+    Ok(BuildHistoryResult { project_id: query.project_id, builds: history::query(&query) })
+}
+
+/// Shows a single recorded build with its projects.
+pub async fn cmd_build_history_show(id: history::BuildId) -> Result<history::BuildRecord> {
+    history::get(id).ok_or_else(|| anyhow::anyhow!("Build #{} not found in the build history", id))
+}
+
+/// Parses a saved build log offline, e.g. one from a colleague or a CI run,
+/// into the same diagnostics a build produces.  With `project_id`, relative
+/// file names are resolved against that project's directory and the
+/// diagnostics are attributed to it and filtered by its warning policy, as
+/// when it is compiled; otherwise against `project_dir` or the current
+/// directory.  `language` is the one of the compiler's labels.
+pub async fn cmd_parse_build_log(
+    log_file: PathBuf,
+    project_id: Option<usize>,
+    project_dir: Option<PathBuf>,
+    language: OutputLanguage,
+) -> Result<ParsedBuildLog> {
+    let (project_dir, project_file, policy) = match project_id {
+        Some(id) => {
+            let data = PROJECTS_DATA.read().await;
+            let project = data.get_project(id).with_context(|| format!("Project with id {} not found", id))?;
+            let project_file = project.get_project_file()?;
+            let project_dir = project_file
+                .parent()
+                .map(|dir| dir.to_path_buf())
+                .unwrap_or_else(|| PathBuf::from(&project.directory));
+            let workspace_id = find_project_link_id(&data, id)
+                .and_then(|link_id| data.get_workspace_id_containing_project_link(link_id));
+            (project_dir, Some(project_file), data.warning_policy(workspace_id, project))
+        }
+        _ => match project_dir {
+            Some(dir) => (dir, None, WarningPolicy::default()),
+            _ => (std::env::current_dir()?, None, WarningPolicy::default()),
+        },
+    };
+    let diagnostics = parse_build_log(
+        &log_file,
+        project_id.unwrap_or_default(),
+        &project_dir,
+        project_file.as_deref(),
+        policy,
+        language,
+    ).await?;
+    let mut counts = DiagnosticCounts::default();
+    for diagnostic in &diagnostics {
+        counts.add(diagnostic.severity);
+    }
+    Ok(ParsedBuildLog { log_file: log_file.to_string_lossy().to_string(), diagnostics, counts })
+}
+
+/// Returns the diagnostics of the last build of each project matching
+/// `query`, without compiling.
+pub async fn cmd_last_diagnostics(query: last_diagnostics::DiagnosticsQuery) -> Result<LastDiagnosticsResult> {
+    if let Some(project_id) = query.project_id
+        && last_diagnostics::get(project_id).is_none()
+    {
+        bail!("No diagnostics recorded for project {}; compile it first.", project_id);
+    }
+    Ok(LastDiagnosticsResult { projects: last_diagnostics::query(&query) })
+}
+
+/// Explains a compiler message code, e.g. "W1035".
+pub async fn cmd_explain_diagnostic(code: &str) -> Result<DiagnosticExplanation> {
+    match DiagnosticExplanation::find(code) {
+        Some(explanation) => Ok(explanation.clone()),
+        _ => bail!(
+            "No explanation for '{}'; known codes: {}",
+            code.trim(),
+            DiagnosticExplanation::all().iter().map(|explanation| explanation.code.as_str()).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
 /// Lists all available compiler configurations.
 pub async fn cmd_list_compilers() -> Result<Vec<CompilerSummary>> {
     let configs = COMPILER_CONFIGURATIONS.read().await;
     Ok(configs
         .iter()
-        .map(|(key, cfg)| CompilerSummary {
-            key: key.clone(),
-            product_name: cfg.product_name.clone(),
-            product_version: cfg.product_version,
-            compiler_version: cfg.compiler_version,
-            installation_path: cfg.installation_path.clone(),
-        })
+        .map(|(key, cfg)| CompilerSummary::new(key, cfg))
         .collect())
 }
 
+/// Updates the given fields of a compiler configuration and returns the
+/// result.  The updated configuration must pass validation.
+pub async fn cmd_update_compiler(key: String, data: PartialCompilerConfiguration) -> Result<CompilerSummary> {
+    let change = Change::UpdateCompiler { key: key.clone(), data };
+    change.execute().await?;
+
+    let configs = COMPILER_CONFIGURATIONS.read().await;
+    let cfg = configs
+        .get(&key)
+        .ok_or_else(|| anyhow::anyhow!("Unknown compiler key: \"{key}\""))?;
+    Ok(CompilerSummary::new(&key, cfg))
+}
+
 /// Sets the group project compiler by key.
 pub async fn cmd_set_group_compiler(compiler_key: String) -> Result<SetCompilerResult> {
     {
@@ -616,14 +1241,39 @@ pub async fn cmd_set_group_compiler(compiler_key: String) -> Result<SetCompilerR
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FormatFileResult {
     pub file_path: String,
+    /// Encoding, BOM and line endings the file was read and written with.
+    pub encoding: crate::encoding::SourceEncoding,
 }
 
 impl fmt::Display for FormatFileResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Formatted: {}", self.file_path)
+        write!(f, "Formatted: {} ({})", self.file_path, self.encoding)
     }
 }
 
+/// What a compile command builds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CompileTarget {
+    /// A single project; `None` means the active project.
+    Project(Option<usize>),
+    /// Every project of a workspace, in workspace order.
+    Workspace(usize),
+    /// Every project of the group project, in group project order.
+    GroupProject,
+    /// A project (the active one when both ids are `None`) or every project
+    /// of a workspace, once for every combination of the selected
+    /// configurations, platforms and compiler keys.  An empty selection
+    /// takes every configuration / enabled platform of the `.dproj` files,
+    /// or the workspace's compiler.
+    Matrix {
+        project_id: Option<usize>,
+        workspace_id: Option<usize>,
+        configurations: Vec<String>,
+        platforms: Vec<String>,
+        compilers: Vec<String>,
+    },
+}
+
 /// Compiles a project. If `project_id` is `Some`, that project is compiled
 /// directly **without** changing the active project in state; otherwise the
 /// currently active project is compiled.
@@ -644,33 +1294,110 @@ pub async fn cmd_compile_with_progress(
     filter: CompileFilterOptions,
     on_progress: Option<CompileProgressCallback>,
 ) -> Result<CompileOutput> {
-    let (project_name, resolved_id, link_id) = {
-        let data = PROJECTS_DATA.read().await;
-        // Use the explicitly requested project_id, falling back to the active project.
-        // We intentionally do NOT call cmd_select_project here so that the active
-        // project in state is never changed as a side-effect of a compile call.
-        let target_id = match project_id.or(data.active_project_id) {
-            Some(id) => id,
-            _ => bail!("No active project selected."),
-        };
-        let project = match data.get_project(target_id) {
-            Some(p) => p,
-            _ => bail!("Project with ID {target_id} not found."),
-        };
-        let name = project.name.clone();
-        let lid = find_project_link_id(&data, target_id);
-        match lid {
-            Some(lid) => (name, target_id, lid),
-            _ => bail!("Project \"{name}\" has no compiled links."),
+    cmd_compile_target(CompileTarget::Project(project_id), rebuild, filter, on_progress).await
+}
+
+/// Resolve a [`CompileTarget`] to the display name and the compiler parameters.
+async fn resolve_compile_target(
+    target: &CompileTarget,
+    rebuild: bool,
+    filter: &CompileFilterOptions,
+) -> Result<(String, CompileProjectParams)> {
+    let (keep_going, jobs) = (filter.keep_going, filter.jobs);
+    let data = PROJECTS_DATA.read().await;
+    let event_id = "cmd-compile".to_string();
+    match target {
+        CompileTarget::Project(project_id) => {
+            // Use the explicitly requested project_id, falling back to the active project.
+            // We intentionally do NOT call cmd_select_project here so that the active
+            // project in state is never changed as a side-effect of a compile call.
+            let target_id = match project_id.or(data.active_project_id) {
+                Some(id) => id,
+                _ => bail!("No active project selected."),
+            };
+            let project = match data.get_project(target_id) {
+                Some(p) => p,
+                _ => bail!("Project with ID {target_id} not found."),
+            };
+            let name = project.name.clone();
+            let link_id = match find_project_link_id(&data, target_id) {
+                Some(lid) => lid,
+                _ => bail!("Project \"{name}\" has no compiled links."),
+            };
+            let params = CompileProjectParams::Project {
+                project_id: target_id,
+                project_link_id: Some(link_id),
+                rebuild,
+                event_id,
+                with_dependencies: filter.with_dependencies,
+            };
+            Ok((name, params))
         }
-    };
+        CompileTarget::Workspace(workspace_id) => {
+            let workspace = match data.get_workspace(*workspace_id) {
+                Some(ws) => ws,
+                _ => bail!("Workspace with ID {workspace_id} not found."),
+            };
+            let params = CompileProjectParams::AllInWorkspace {
+                workspace_id: *workspace_id,
+                rebuild,
+                event_id,
+                keep_going,
+                jobs,
+            };
+            Ok((workspace.name.clone(), params))
+        }
+        CompileTarget::GroupProject => {
+            let group_project = match &data.group_project {
+                Some(gp) => gp,
+                _ => bail!("No group project loaded."),
+            };
+            let params = CompileProjectParams::AllInGroupProject { rebuild, event_id, keep_going, jobs };
+            Ok((group_project.name.clone(), params))
+        }
+        CompileTarget::Matrix { project_id, workspace_id, configurations, platforms, compilers } => {
+            let (name, project_id, workspace_id) = match (project_id, workspace_id) {
+                (None, Some(workspace_id)) => match data.get_workspace(*workspace_id) {
+                    Some(ws) => (ws.name.clone(), None, Some(*workspace_id)),
+                    _ => bail!("Workspace with ID {workspace_id} not found."),
+                },
+                _ => {
+                    let target_id = match project_id.or(data.active_project_id) {
+                        Some(id) => id,
+                        _ => bail!("No active project selected."),
+                    };
+                    match data.get_project(target_id) {
+                        Some(p) => (p.name.clone(), Some(target_id), None),
+                        _ => bail!("Project with ID {target_id} not found."),
+                    }
+                }
+            };
+            let params = CompileProjectParams::Matrix {
+                project_id,
+                workspace_id,
+                configurations: configurations.clone(),
+                platforms: platforms.clone(),
+                compilers: compilers.clone(),
+                rebuild,
+                event_id,
+                keep_going,
+                jobs,
+            };
+            Ok((name, params))
+        }
+    }
+}
 
-    let params = CompileProjectParams::Project {
-        project_id: resolved_id,
-        project_link_id: Some(link_id),
-        rebuild,
-        event_id: "cmd-compile".to_string(),
-    };
+/// Compiles a [`CompileTarget`] and optionally invokes `on_progress` for each
+/// emitted compiler output line as it arrives.
+pub async fn cmd_compile_target(
+    target: CompileTarget,
+    rebuild: bool,
+    filter: CompileFilterOptions,
+    on_progress: Option<CompileProgressCallback>,
+) -> Result<CompileOutput> {
+    let (project_name, params) = resolve_compile_target(&target, rebuild, &filter).await?;
+    let parallel = params.jobs() > 1;
 
     // Collect broadcast messages concurrently with compilation.
     let collected: std::sync::Arc<std::sync::Mutex<Vec<String>>> =
@@ -679,9 +1406,13 @@ pub async fn cmd_compile_with_progress(
     let progress_callback = on_progress.clone();
     let filter_opts = filter.clone();
     let mut receiver = CompilerProgress::subscribe();
+    // Other compilations of this process may be queued or running; only the
+    // events between this job's Start and Completed belong to it.
+    let job_id = queue::next_job_id();
 
     let collect_handle = tokio::spawn(async move {
         let mut counts = DiagCounts::default();
+        let mut own_job = false;
         let emit = |callback: &Option<CompileProgressCallback>,
                     lines: &mut Vec<String>,
                     out: Vec<String>| {
@@ -695,16 +1426,31 @@ pub async fn cmd_compile_with_progress(
         loop {
             match receiver.recv().await {
                 Ok(event) => {
-                    let mut lines = collected_clone.lock().unwrap();
+                    let mut collected = collected_clone.lock().unwrap();
+                    let lines = &mut *collected;
+                    match &event {
+                        CompilerProgressParams::Queued { job_id: queued, jobs_ahead } => {
+                            if *queued == job_id {
+                                let line = format!("Waiting for {jobs_ahead} build job(s) to finish...");
+                                emit(&progress_callback, lines, vec![line]);
+                            }
+                            continue;
+                        }
+                        CompilerProgressParams::Start { job_id: started, .. } => own_job = *started == Some(job_id),
+                        CompilerProgressParams::Completed { job_id: completed, .. } if *completed == Some(job_id) => {}
+                        _ if !own_job => continue,
+                        _ => {}
+                    }
                     match event {
-                        CompilerProgressParams::Start { lines: ls }
+                        CompilerProgressParams::Queued { .. } => {}
+                        CompilerProgressParams::Start { lines: ls, .. }
                         | CompilerProgressParams::SingleProjectStarted { lines: ls, .. } => {
                             let out = if filter_opts.trim_banners {
                                 trim_banner_lines(ls)
                             } else {
                                 ls
                             };
-                            emit(&progress_callback, &mut lines, out);
+                            emit(&progress_callback, lines, out);
                         }
                         CompilerProgressParams::Completed { lines: ls, .. }
                         | CompilerProgressParams::SingleProjectCompleted { lines: ls, .. } => {
@@ -713,7 +1459,7 @@ pub async fn cmd_compile_with_progress(
                             if filter_opts.summarize_diagnostics {
                                 let summary = counts.drain_summary_lines();
                                 if !summary.is_empty() {
-                                    emit(&progress_callback, &mut lines, summary);
+                                    emit(&progress_callback, lines, summary);
                                 }
                             } else {
                                 counts.drain_summary_lines();
@@ -723,10 +1469,10 @@ pub async fn cmd_compile_with_progress(
                             } else {
                                 ls
                             };
-                            emit(&progress_callback, &mut lines, out);
+                            emit(&progress_callback, lines, out);
                         }
-                        CompilerProgressParams::Stdout { line }
-                        | CompilerProgressParams::Stderr { line } => {
+                        CompilerProgressParams::Stdout { line, project_id }
+                        | CompilerProgressParams::Stderr { line, project_id } => {
                             if let Some((kind, file)) = classify_diagnostic_line(&line) {
                                 let suppress = match kind {
                                     DiagKind::Warn => !filter_opts.show_warnings,
@@ -740,7 +1486,11 @@ pub async fn cmd_compile_with_progress(
                                     continue;
                                 }
                             }
-                            emit(&progress_callback, &mut lines, vec![line]);
+                            let line = match project_id {
+                                Some(project_id) if parallel => format!("[{project_id}] {line}"),
+                                _ => line,
+                            };
+                            emit(&progress_callback, lines, vec![line]);
                         }
                     }
                 }
@@ -750,7 +1500,8 @@ pub async fn cmd_compile_with_progress(
         }
     });
 
-    let compiler = Compiler::new_standalone(&params).await;
+    let lock_policy = if filter.no_wait { build_lock::LockPolicy::Fail } else { build_lock::LockPolicy::Wait };
+    let compiler = Compiler::new_standalone(&params).await.with_job_id(job_id).with_lock_policy(lock_policy);
     let compile_result = compiler.compile().await;
 
     // Brief settling window for in-flight broadcasts, then stop collector.
@@ -764,13 +1515,43 @@ pub async fn cmd_compile_with_progress(
     };
 
     match compile_result {
-        Ok(result) => Ok(CompileOutput {
-            project_name,
-            success: result.success,
-            cancelled: result.cancelled,
-            code: result.code,
-            lines: output_lines,
-        }),
+        Ok(result) => {
+            let diagnostics = result.diagnostics;
+            let mut projects: Vec<ProjectCompileResult> = result
+                .projects
+                .iter()
+                .map(|outcome| ProjectCompileResult::from_outcome(outcome, &diagnostics))
+                .collect();
+            let (mut success, code) = (result.success, result.code);
+            // A project appears once per combination of a build matrix, so
+            // its diagnostics are counted per build and not compared with
+            // its baseline.
+            let baseline = if result.matrix.is_empty() {
+                compare_with_baselines(&projects, &diagnostics, filter.fail_on_new_warnings).await?
+            } else {
+                for (project, outcome) in projects.iter_mut().zip(&result.projects) {
+                    project.errors = outcome.diagnostics.errors;
+                    project.warnings = outcome.diagnostics.warnings;
+                    project.hints = outcome.diagnostics.hints;
+                }
+                None
+            };
+            if filter.fail_on_new_warnings && baseline.as_ref().is_some_and(|b| !b.new.is_empty()) {
+                success = false;
+            }
+            Ok(CompileOutput {
+                project_name,
+                success,
+                cancelled: result.cancelled,
+                code,
+                lines: output_lines,
+                compiler_name: result.compiler_name,
+                diagnostics,
+                projects,
+                baseline,
+                matrix: result.matrix,
+            })
+        }
         Err(e) => {
             // Still return collected output on failure.
             if on_progress.is_some() {
@@ -788,26 +1569,359 @@ pub async fn cmd_compile_with_progress(
     }
 }
 
+/// Compare each compiled project's warnings and hints with its baseline.
+/// Returns `None` when no project has a baseline, unless `require` is set, in
+/// which case projects without one compare against an empty baseline.
+async fn compare_with_baselines(
+    projects: &[ProjectCompileResult],
+    diagnostics: &[CompileDiagnostic],
+    require: bool,
+) -> Result<Option<BaselineComparison>> {
+    let data = PROJECTS_DATA.read().await;
+    let mut comparison: Option<BaselineComparison> = None;
+    for result in projects.iter().filter(|p| !p.cancelled) {
+        let Some(project) = data.get_project(result.project_id) else { continue };
+        let Ok(path) = baseline::baseline_path(project) else { continue };
+        let known = match WarningBaseline::load(&path)? {
+            Some(known) => known,
+            _ if require => WarningBaseline::from_diagnostics(&[], Path::new("")),
+            _ => continue,
+        };
+        let own: Vec<CompileDiagnostic> = diagnostics
+            .iter()
+            .filter(|d| d.project_id == result.project_id)
+            .cloned()
+            .collect();
+        let project_dir = path.parent().unwrap_or(Path::new(""));
+        comparison
+            .get_or_insert_default()
+            .extend(known.compare(&own, project_dir));
+    }
+    Ok(comparison)
+}
+
+/// Record the warnings and hints of `output` as the new baseline of every
+/// compiled project and return the written baseline files.  Refuses when a
+/// project did not compile, since its diagnostics would be incomplete.
+pub async fn cmd_update_baseline(output: &CompileOutput) -> Result<Vec<PathBuf>> {
+    if let Some(failed) = output.projects.iter().find(|p| !p.success || p.cancelled) {
+        bail!(
+            "Not updating baselines: compilation of \"{}\" did not succeed.",
+            failed.project_name
+        );
+    }
+    let data = PROJECTS_DATA.read().await;
+    let mut written = Vec::new();
+    for result in &output.projects {
+        let project = match data.get_project(result.project_id) {
+            Some(p) => p,
+            _ => bail!("Project with ID {} not found.", result.project_id),
+        };
+        let path = baseline::baseline_path(project)?;
+        let own: Vec<CompileDiagnostic> = output
+            .diagnostics
+            .iter()
+            .filter(|d| d.project_id == result.project_id)
+            .cloned()
+            .collect();
+        let project_dir = path.parent().unwrap_or(Path::new(""));
+        WarningBaseline::from_diagnostics(&own, project_dir).save(&path)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
 /// Formats a Delphi source file in-place.
 ///
-/// Reads the file at `file_path`, decodes it with `encoding` (e.g. `"utf-8"`,
-/// `"windows-1252"`, `"oem"`), runs it through the DDK formatter, then
-/// encodes the result back to the same encoding before writing.
-/// Defaults to `"utf-8"` when `encoding` is `None`.
-pub async fn cmd_format_file(file_path: String, encoding: Option<String>) -> Result<FormatFileResult> {
+/// Reads the file at `file_path` and decodes it with `encoding` (e.g.
+/// `"utf-8"`, `"windows-1252"`, `"oem"`).  When `encoding` is `None` the
+/// encoding is auto-detected: BOM first, then UTF-8 validity, then
+/// `fallback` or, without one, the configured ANSI fallback (see
+/// [`crate::encoding::set_ansi_fallback`]).  The result is written back in
+/// the original encoding with the original BOM and line endings.
+pub async fn cmd_format_file(
+    file_path: String,
+    encoding: Option<String>,
+    fallback: Option<&str>,
+) -> Result<FormatFileResult> {
     use crate::format::Formatter;
-    use crate::encoding::{decode_bytes, encode_string};
-
-    let encoding_label = encoding.as_deref().unwrap_or("utf-8");
+    use crate::encoding::{
+        ansi_fallback, ansi_fallback_label, decode_source_as, detect_source_encoding, encode_source,
+        source_encoding_for_label,
+    };
 
+    let fallback = match fallback {
+        Some(label) => ansi_fallback_label(label)?,
+        _ => ansi_fallback().to_string(),
+    };
     let raw = std::fs::read(&file_path)
         .with_context(|| format!("Failed to read file: {file_path}"))?;
-    let content = decode_bytes(&raw, encoding_label);
+    let source_encoding = match encoding.as_deref() {
+        Some(label) => source_encoding_for_label(&raw, label),
+        _ => detect_source_encoding(&raw, &fallback),
+    };
+    let content = decode_source_as(&raw, &source_encoding);
 
     let formatted = Formatter::new(content)?.execute().await?;
 
-    let out_bytes = encode_string(&formatted, encoding_label);
+    let out_bytes = encode_source(&formatted, &source_encoding);
     std::fs::write(&file_path, &out_bytes)
         .with_context(|| format!("Failed to write file: {file_path}"))?;
-    Ok(FormatFileResult { file_path })
+    Ok(FormatFileResult { file_path, encoding: source_encoding })
+}
+
+// ---------------------------------------------------------------------------
+// Source encoding report / conversion
+// ---------------------------------------------------------------------------
+
+/// The set of source files an encoding command operates on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SourceTarget {
+    /// A single file, or every source file below a directory.
+    Path(String),
+    /// The sources of a project: its directory plus units referenced by
+    /// path from the `.dpr` / `.dpk`.
+    Project(usize),
+    /// The sources of every project in a workspace.
+    Workspace(usize),
+}
+
+/// Detected encoding of a single source file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodingReportEntry {
+    pub file: String,
+    pub encoding: SourceEncoding,
+}
+
+/// Result of `cmd_encoding_report`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodingReport {
+    pub files: Vec<EncodingReportEntry>,
+}
+
+impl fmt::Display for EncodingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.files.is_empty() {
+            return write!(f, "No source files found.");
+        }
+        let mut totals: std::collections::BTreeMap<String, usize> = std::collections::BTreeMap::new();
+        for entry in &self.files {
+            writeln!(f, "{:<28} {}", entry.encoding.to_string(), entry.file)?;
+            *totals.entry(entry.encoding.to_string()).or_default() += 1;
+        }
+        writeln!(f)?;
+        write!(f, "{} file(s):", self.files.len())?;
+        for (encoding, count) in totals {
+            write!(f, "\n  {count:>5}  {encoding}")?;
+        }
+        Ok(())
+    }
+}
+
+/// What `cmd_encoding_convert` did (or would do) with a single file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ConvertStatus {
+    /// The file was re-encoded and written.
+    Converted,
+    /// Dry run: the file would be re-encoded.
+    WouldConvert,
+    /// The file is already in the target encoding.
+    Unchanged,
+    /// The file cannot be converted without losing characters.
+    Refused { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodingConvertEntry {
+    pub file: String,
+    pub from: SourceEncoding,
+    pub to: SourceEncoding,
+    #[serde(flatten)]
+    pub status: ConvertStatus,
+}
+
+/// Result of `cmd_encoding_convert`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodingConvertResult {
+    pub dry_run: bool,
+    pub files: Vec<EncodingConvertEntry>,
+}
+
+impl EncodingConvertResult {
+    pub fn refused(&self) -> impl Iterator<Item = &EncodingConvertEntry> {
+        self.files.iter().filter(|e| matches!(e.status, ConvertStatus::Refused { .. }))
+    }
+}
+
+impl fmt::Display for EncodingConvertResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.files.is_empty() {
+            return write!(f, "No source files found.");
+        }
+        let (mut changed, mut unchanged, mut refused) = (0, 0, 0);
+        for entry in &self.files {
+            match &entry.status {
+                ConvertStatus::Converted | ConvertStatus::WouldConvert => {
+                    changed += 1;
+                    writeln!(f, "{} → {}  {}", entry.from, entry.to, entry.file)?;
+                }
+                ConvertStatus::Unchanged => unchanged += 1,
+                ConvertStatus::Refused { reason } => {
+                    refused += 1;
+                    writeln!(f, "REFUSED  {}: {reason}", entry.file)?;
+                }
+            }
+        }
+        let verb = if self.dry_run { "would be converted" } else { "converted" };
+        write!(f, "{changed} file(s) {verb}, {unchanged} unchanged, {refused} refused.")?;
+        if refused > 0 && !self.dry_run {
+            write!(f, " No files were written.")?;
+        }
+        Ok(())
+    }
+}
+
+/// Target format for `cmd_encoding_convert`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodingConvertOptions {
+    /// Target encoding label, e.g. `"utf-8"` or `"windows-1252"`.
+    pub to: String,
+    /// Write a BOM (only meaningful for UTF encodings).
+    pub bom: bool,
+    /// Target line endings; `None` keeps each file's current style.
+    pub line_ending: Option<LineEnding>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+}
+
+/// Resolve a [`SourceTarget`] to the list of source files it covers.
+pub async fn resolve_source_files(target: &SourceTarget) -> Result<Vec<std::path::PathBuf>> {
+    use crate::files::sources::{collect_sources, main_source_unit_paths};
+    use std::path::{Path, PathBuf};
+
+    fn project_sources(project: &Project) -> Result<Vec<PathBuf>> {
+        let mut files = collect_sources(Path::new(&project.directory))?;
+        if let Some(main) = project.dpr.as_ref().or(project.dpk.as_ref()) {
+            files.extend(main_source_unit_paths(Path::new(main)));
+        }
+        Ok(files)
+    }
+
+    let mut files = match target {
+        SourceTarget::Path(path) => collect_sources(Path::new(path))?,
+        SourceTarget::Project(project_id) => {
+            let data = PROJECTS_DATA.read().await;
+            let project = data
+                .get_project(*project_id)
+                .ok_or_else(|| anyhow::anyhow!("No project found with ID {project_id}."))?;
+            project_sources(project)?
+        }
+        SourceTarget::Workspace(workspace_id) => {
+            let data = PROJECTS_DATA.read().await;
+            let workspace = data
+                .get_workspace(*workspace_id)
+                .ok_or_else(|| anyhow::anyhow!("No workspace found with ID {workspace_id}."))?;
+            let mut files = Vec::new();
+            for project in data.projects_of_workspace(workspace) {
+                files.extend(project_sources(project)?);
+            }
+            files
+        }
+    };
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Reports the detected encoding, BOM and line endings of every source file
+/// covered by `target`.
+pub async fn cmd_encoding_report(target: SourceTarget) -> Result<EncodingReport> {
+    use crate::encoding::{ansi_fallback, detect_source_encoding};
+
+    let mut files = Vec::new();
+    for path in resolve_source_files(&target).await? {
+        let raw = std::fs::read(&path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        files.push(EncodingReportEntry {
+            file: path.to_string_lossy().to_string(),
+            encoding: detect_source_encoding(&raw, ansi_fallback()),
+        });
+    }
+    Ok(EncodingReport { files })
+}
+
+/// Re-encodes every source file covered by `target` to `options.to`.
+///
+/// Conversion is all-or-nothing: every file is checked first, and if any
+/// file contains characters that cannot be represented in the target
+/// encoding (or could not be decoded losslessly), nothing is written and
+/// those files are reported as [`ConvertStatus::Refused`].
+pub async fn cmd_encoding_convert(
+    target: SourceTarget,
+    options: EncodingConvertOptions,
+) -> Result<EncodingConvertResult> {
+    use crate::encoding::{
+        ansi_fallback, decode_source_as, detect_source_encoding, encode_source,
+        first_unmappable_char, is_known_label, output_label,
+    };
+
+    if !is_known_label(&options.to) {
+        bail!("Unknown target encoding: \"{}\"", options.to);
+    }
+    let Some(to_label) = output_label(&options.to) else {
+        bail!("Files cannot be written in encoding \"{}\"", options.to);
+    };
+
+    let mut planned = Vec::new();
+    for path in resolve_source_files(&target).await? {
+        let raw = std::fs::read(&path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        let from = detect_source_encoding(&raw, ansi_fallback());
+        let to = SourceEncoding {
+            label: to_label.clone(),
+            bom: options.bom && to_label.starts_with("utf-"),
+            line_ending: options.line_ending.unwrap_or(from.line_ending),
+        };
+        let text = decode_source_as(&raw, &from);
+        let status = if from == to {
+            ConvertStatus::Unchanged
+        } else if let Some(line) = text.lines().position(|l| l.contains('\u{FFFD}')) {
+            ConvertStatus::Refused {
+                reason: format!("line {}: contains bytes that are not valid {}", line + 1, from.label),
+            }
+        } else if let Some((line, c)) = first_unmappable_char(&text, &to.label) {
+            ConvertStatus::Refused {
+                reason: format!(
+                    "line {line}: '{c}' (U+{:04X}) cannot be represented in {}",
+                    c as u32, to.label
+                ),
+            }
+        } else if options.dry_run {
+            ConvertStatus::WouldConvert
+        } else {
+            ConvertStatus::Converted
+        };
+        planned.push((path, text, from, to, status));
+    }
+
+    let any_refused = planned.iter().any(|(.., status)| matches!(status, ConvertStatus::Refused { .. }));
+    let mut files = Vec::with_capacity(planned.len());
+    for (path, text, from, to, mut status) in planned {
+        if status == ConvertStatus::Converted {
+            if any_refused {
+                status = ConvertStatus::WouldConvert;
+            } else {
+                std::fs::write(&path, encode_source(&text, &to))
+                    .with_context(|| format!("Failed to write file: {}", path.display()))?;
+            }
+        }
+        files.push(EncodingConvertEntry {
+            file: path.to_string_lossy().to_string(),
+            from,
+            to,
+            status,
+        });
+    }
+    Ok(EncodingConvertResult { dry_run: options.dry_run, files })
 }
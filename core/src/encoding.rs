@@ -1,18 +1,25 @@
 use std::sync::RwLock;
 use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
 
 /// The special value "oem" means: auto-detect using the Windows OEM codepage.
 static COMPILER_ENCODING: RwLock<&str> = RwLock::new("oem");
 
+/// Encoding assumed for source files that have no BOM and are not valid UTF-8.
+/// The special value "ansi" means: the Windows ANSI codepage (`GetACP`).
+static ANSI_FALLBACK_ENCODING: RwLock<&str> = RwLock::new("ansi");
+
 /// Decode raw bytes to a UTF-8 `String` using the given encoding label.
 ///
 /// Recognised special values: `"oem"` (Windows system OEM codepage auto-detected
-/// at runtime), `"utf-8"`, `"utf-32le"`, `"utf-32be"`.
+/// at runtime), `"ansi"` (Windows system ANSI codepage), `"utf-8"`,
+/// `"utf-32le"`, `"utf-32be"`.
 /// Every other value is passed to `encoding_rs` by label.
 pub fn decode_bytes(bytes: &[u8], label: &str) -> String {
     let lower = label.to_lowercase();
     match lower.as_str() {
         "oem" => decode_oem(bytes),
+        "ansi" => decode_ansi(bytes),
         "utf-8" | "utf8" => String::from_utf8_lossy(bytes).to_string(),
         "utf-32le" => decode_utf32(bytes, true),
         "utf-32be" => decode_utf32(bytes, false),
@@ -29,13 +36,22 @@ pub fn decode_bytes(bytes: &[u8], label: &str) -> String {
 /// Encode a UTF-8 `str` to raw bytes using the given encoding label.
 ///
 /// `"oem"` maps to the Windows system OEM codepage (via `WideCharToMultiByte`
-/// on Windows, falls back to UTF-8 on other platforms).
+/// on Windows, falls back to UTF-8 on other platforms), `"ansi"` to the
+/// Windows system ANSI codepage (windows-1252 on other platforms).
 /// Every other value is passed to `encoding_rs` by label.
 pub fn encode_string(s: &str, label: &str) -> Vec<u8> {
     let lower = label.to_lowercase();
     match lower.as_str() {
         "oem" => encode_oem(s),
+        "ansi" => {
+            let (bytes, _, _) = ansi_encoding().encode(s);
+            bytes.into_owned()
+        }
         "utf-8" | "utf8" => s.as_bytes().to_vec(),
+        // encoding_rs never encodes *to* UTF-16 (it substitutes UTF-8),
+        // so both byte orders are handled here.
+        "utf-16le" | "utf-16" => s.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect(),
+        "utf-16be" => s.encode_utf16().flat_map(|unit| unit.to_be_bytes()).collect(),
         "utf-32le" => {
             let mut out = Vec::with_capacity(s.chars().count() * 4);
             for c in s.chars() {
@@ -141,6 +157,267 @@ pub fn decode_line(bytes: &[u8]) -> String {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Source file encoding detection (BOM, UTF-8 validity, ANSI fallback)
+// ---------------------------------------------------------------------------
+
+/// Line-ending style of a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineEnding {
+    Crlf,
+    Lf,
+    /// Both `\r\n` and bare `\n` occur; the text is written back untouched.
+    Mixed,
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineEnding::Crlf => write!(f, "CRLF"),
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::Mixed => write!(f, "mixed"),
+        }
+    }
+}
+
+/// The on-disk shape of a source file: encoding label, whether it started
+/// with a byte-order mark, and its line endings.  Produced by
+/// [`detect_source_encoding`] and consumed by [`encode_source`] so that a
+/// decode → edit → encode round trip preserves the original file format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceEncoding {
+    /// Encoding label understood by [`decode_bytes`] / [`encode_string`].
+    pub label: String,
+    pub bom: bool,
+    pub line_ending: LineEnding,
+}
+
+impl std::fmt::Display for SourceEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bom = if self.bom { " with BOM" } else { "" };
+        write!(f, "{}{}, {}", self.label, bom, self.line_ending)
+    }
+}
+
+/// Byte-order marks, longest first so UTF-32LE wins over UTF-16LE.
+const BOMS: [(&[u8], &str); 5] = [
+    (&[0xFF, 0xFE, 0x00, 0x00], "utf-32le"),
+    (&[0x00, 0x00, 0xFE, 0xFF], "utf-32be"),
+    (&[0xEF, 0xBB, 0xBF], "utf-8"),
+    (&[0xFF, 0xFE], "utf-16le"),
+    (&[0xFE, 0xFF], "utf-16be"),
+];
+
+/// Return the BOM bytes for an encoding label, if that encoding has one.
+fn bom_for_label(label: &str) -> Option<&'static [u8]> {
+    let lower = label.to_lowercase();
+    let lower = if lower == "utf8" { "utf-8".to_string() } else { lower };
+    BOMS.iter()
+        .find(|(_, bom_label)| *bom_label == lower)
+        .map(|(bom, _)| *bom)
+}
+
+/// Set the fallback encoding used for source files that carry no BOM and
+/// are not valid UTF-8 (e.g. "windows-1252", "windows-1250", "ansi").
+/// An unknown label is an error and keeps the current fallback.
+pub fn set_ansi_fallback(label: &str) -> anyhow::Result<()> {
+    let lower = ansi_fallback_label(label)?;
+    *ANSI_FALLBACK_ENCODING.write().unwrap() = Box::leak(lower.into_boxed_str());
+    Ok(())
+}
+
+/// `label` in lower case, or an error if it cannot be used as ANSI fallback.
+pub fn ansi_fallback_label(label: &str) -> anyhow::Result<String> {
+    let lower = label.to_lowercase();
+    if lower != "ansi" && lower != "oem" && Encoding::for_label(lower.as_bytes()).is_none() {
+        anyhow::bail!("Unknown ANSI fallback encoding: \"{label}\"");
+    }
+    Ok(lower)
+}
+
+/// The currently configured ANSI fallback label.
+pub fn ansi_fallback() -> &'static str {
+    *ANSI_FALLBACK_ENCODING.read().unwrap()
+}
+
+/// Detect the line-ending style of already decoded text.
+/// Text without any line break is reported as CRLF, the Delphi default.
+pub fn detect_line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    match (crlf, lf) {
+        (_, 0) => LineEnding::Crlf,
+        (0, _) => LineEnding::Lf,
+        _ => LineEnding::Mixed,
+    }
+}
+
+/// Detect the encoding of raw source bytes.
+///
+/// Order of precedence: BOM (UTF-8 / UTF-16 / UTF-32, either byte order),
+/// then strict UTF-8 validity, then `fallback` (typically
+/// [`ansi_fallback`]).
+pub fn detect_source_encoding(bytes: &[u8], fallback: &str) -> SourceEncoding {
+    let (label, bom_len) = match BOMS.iter().find(|(bom, _)| bytes.starts_with(bom)) {
+        Some((bom, label)) => (label.to_string(), bom.len()),
+        _ if std::str::from_utf8(bytes).is_ok() => ("utf-8".to_string(), 0),
+        _ => (fallback.to_lowercase(), 0),
+    };
+    let bom = bom_len > 0;
+    let body = &bytes[bom_len..];
+    let line_ending = detect_line_ending(&decode_bytes(body, &label));
+    SourceEncoding { label, bom, line_ending }
+}
+
+/// Build the [`SourceEncoding`] for bytes whose encoding is already known
+/// (e.g. passed explicitly by the user).  BOM and line endings are still
+/// detected so they survive a round trip.
+pub fn source_encoding_for_label(bytes: &[u8], label: &str) -> SourceEncoding {
+    let lower = label.to_lowercase();
+    let lower = if lower == "utf8" { "utf-8".to_string() } else { lower };
+    let bom = bom_for_label(&lower).is_some_and(|bom| bytes.starts_with(bom));
+    let mut encoding = SourceEncoding { label: lower, bom, line_ending: LineEnding::Crlf };
+    encoding.line_ending = detect_line_ending(&decode_source_as(bytes, &encoding));
+    encoding
+}
+
+/// Decode raw source bytes, auto-detecting the encoding.
+///
+/// The BOM (if any) is not part of the returned text; it is recorded in the
+/// returned [`SourceEncoding`] instead.
+pub fn decode_source(bytes: &[u8]) -> (String, SourceEncoding) {
+    let encoding = detect_source_encoding(bytes, ansi_fallback());
+    let text = decode_source_as(bytes, &encoding);
+    (text, encoding)
+}
+
+/// Decode raw source bytes with a known [`SourceEncoding`], skipping its BOM.
+pub fn decode_source_as(bytes: &[u8], encoding: &SourceEncoding) -> String {
+    let body = match bom_for_label(&encoding.label) {
+        Some(bom) if bytes.starts_with(bom) => &bytes[bom.len()..],
+        _ => bytes,
+    };
+    let text = decode_bytes(body, &encoding.label);
+    // Defensive: a caller-supplied label may not have stripped a UTF-8 BOM.
+    match text.strip_prefix('\u{feff}') {
+        Some(stripped) => stripped.to_string(),
+        _ => text,
+    }
+}
+
+/// Encode text for writing back to disk in the given [`SourceEncoding`]:
+/// line endings are normalised to the recorded style (unless mixed), the
+/// BOM is re-added when the original file had one, and the text is encoded
+/// with the recorded label.
+pub fn encode_source(text: &str, encoding: &SourceEncoding) -> Vec<u8> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let normalized = match encoding.line_ending {
+        LineEnding::Crlf => text.replace("\r\n", "\n").replace('\n', "\r\n"),
+        LineEnding::Lf => text.replace("\r\n", "\n"),
+        LineEnding::Mixed => text.to_string(),
+    };
+    let mut out = Vec::with_capacity(normalized.len() + 4);
+    if encoding.bom && let Some(bom) = bom_for_label(&encoding.label) {
+        out.extend_from_slice(bom);
+    }
+    out.extend(encode_string(&normalized, &encoding.label));
+    out
+}
+
+/// Returns `true` if `label` is understood by [`decode_bytes`] /
+/// [`encode_string`] without falling back to UTF-8.
+pub fn is_known_label(label: &str) -> bool {
+    let lower = label.to_lowercase();
+    matches!(
+        lower.as_str(),
+        "oem" | "ansi" | "utf-8" | "utf8" | "utf-16le" | "utf-16be" | "utf-32le" | "utf-32be"
+    ) || Encoding::for_label(lower.as_bytes()).is_some()
+}
+
+/// The label to write text in when the user asks for `label`, or `None` if
+/// text cannot be written in that encoding.  "utf-16" means little endian;
+/// encoding_rs labels whose encoder writes another encoding (e.g. "unicode"
+/// or "ucs-2", which it encodes as UTF-8) are refused.
+pub fn output_label(label: &str) -> Option<String> {
+    let lower = label.to_lowercase();
+    match lower.as_str() {
+        "utf8" => Some("utf-8".to_string()),
+        "utf-16" => Some("utf-16le".to_string()),
+        "oem" | "ansi" | "utf-8" | "utf-16le" | "utf-16be" | "utf-32le" | "utf-32be" => Some(lower),
+        _ => match Encoding::for_label(lower.as_bytes()) {
+            Some(enc) if enc.output_encoding() == enc => Some(lower),
+            _ => None,
+        },
+    }
+}
+
+/// Find the first character of `s` that cannot be represented in the
+/// encoding `label`.  Returns the 1-based line number and the character,
+/// or `None` when the whole text is representable.
+pub fn first_unmappable_char(s: &str, label: &str) -> Option<(usize, char)> {
+    let lower = label.to_lowercase();
+    let representable: Box<dyn Fn(char) -> bool> = match lower.as_str() {
+        "utf-8" | "utf8" | "utf-16le" | "utf-16be" | "utf-16" | "utf-32le" | "utf-32be" => return None,
+        "ansi" => Box::new(|c| encoding_rs_can_encode(ansi_encoding(), c)),
+        "oem" => match oem_codepage() {
+            65001 => return None,
+            850 => Box::new(|c| c.is_ascii() || CP850_HIGH.contains(&c)),
+            437 => Box::new(|c| c.is_ascii() || CP437_HIGH.contains(&c)),
+            cp => match codepage_to_encoding(cp) {
+                Some(enc) => Box::new(move |c| encoding_rs_can_encode(enc, c)),
+                _ => return None,
+            },
+        },
+        _ => match Encoding::for_label(lower.as_bytes()) {
+            Some(enc) if enc.output_encoding() == enc => {
+                // Fast path: most files encode cleanly.
+                if !enc.encode(s).2 {
+                    return None;
+                }
+                Box::new(move |c| encoding_rs_can_encode(enc, c))
+            }
+            _ => return None,
+        },
+    };
+    s.lines()
+        .enumerate()
+        .find_map(|(index, line)| line.chars().find(|c| !representable(*c)).map(|c| (index + 1, c)))
+}
+
+fn encoding_rs_can_encode(enc: &'static Encoding, c: char) -> bool {
+    let mut buf = [0u8; 4];
+    !enc.encode(c.encode_utf8(&mut buf)).2
+}
+
+// ---------------------------------------------------------------------------
+// ANSI codepage (Windows)
+// ---------------------------------------------------------------------------
+
+/// The `encoding_rs` encoding for the system ANSI codepage.
+fn ansi_encoding() -> &'static Encoding {
+    codepage_to_encoding(ansi_codepage()).unwrap_or(encoding_rs::WINDOWS_1252)
+}
+
+fn decode_ansi(bytes: &[u8]) -> String {
+    let (decoded, _) = ansi_encoding().decode_without_bom_handling(bytes);
+    decoded.into_owned()
+}
+
+/// Get the active ANSI codepage. On non-Windows, falls back to windows-1252.
+#[cfg(windows)]
+fn ansi_codepage() -> u32 {
+    // kernel32!GetACP – always available, no additional crate needed.
+    unsafe extern "system" {
+        fn GetACP() -> u32;
+    }
+    unsafe { GetACP() }
+}
+
+#[cfg(not(windows))]
+fn ansi_codepage() -> u32 {
+    1252
+}
+
 // ---------------------------------------------------------------------------
 // OEM codepage auto-detection (Windows)
 // ---------------------------------------------------------------------------
@@ -22,4 +22,35 @@ pub fn parse_groupproj(file_path: PathBuf) -> Result<Vec<PathBuf>> {
         }
     }
     Ok(project_paths)
-}
\ No newline at end of file
+}
+/// Dependencies declared in a group project: every project file paired with
+/// the project files listed in its `<Dependencies>` element.  Projects
+/// without dependencies are left out.
+pub fn parse_groupproj_dependencies(file_path: PathBuf) -> Result<Vec<(PathBuf, Vec<PathBuf>)>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let parent_directory = file_path.parent().ok_or_else(|| anyhow::anyhow!("Failed to get parent directory"))?;
+    let xml_content = Document::parse(&content)?;
+    let mut dependencies = Vec::new();
+    for project in xml_content.descendants().filter(|n| n.has_tag_name("Projects")) {
+        let Some(include_attr) = project.attribute("Include") else {
+            continue;
+        };
+        let Some(text) = project
+            .children()
+            .find(|n| n.has_tag_name("Dependencies"))
+            .and_then(|n| n.text())
+        else {
+            continue;
+        };
+        let depends_on: Vec<PathBuf> = text
+            .split(';')
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(|path| parent_directory.join(path))
+            .collect();
+        if !depends_on.is_empty() {
+            dependencies.push((parent_directory.join(include_attr), depends_on));
+        }
+    }
+    Ok(dependencies)
+}
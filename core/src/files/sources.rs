@@ -0,0 +1,74 @@
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::utils::normalize_path;
+
+/// File extensions treated as Delphi source text.  Form files (`.dfm`) are
+/// deliberately excluded: they may be stored in binary format.
+pub const SOURCE_EXTENSIONS: [&str; 4] = ["pas", "dpr", "dpk", "inc"];
+
+/// Directories that the IDE creates for backups and never holds real sources.
+const SKIPPED_DIRECTORIES: [&str; 2] = ["__history", "__recovery"];
+
+lazy_static::lazy_static! {
+    /// Matches `Unit in 'relative\path\Unit.pas'` entries of a `.dpr`/`.dpk`
+    /// uses / contains clause.
+    static ref UNIT_IN_PATH_REGEX: regex::Regex =
+        regex::Regex::new(r"(?i)\b[A-Za-z_][\w.]*\s+in\s+'(?P<path>[^']+)'").unwrap();
+}
+
+pub fn is_source_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| SOURCE_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+        .unwrap_or(false)
+}
+
+/// Collect Delphi source files below `path` (recursively), or `path` itself
+/// if it is a file.  The result is sorted and free of duplicates.
+pub fn collect_sources(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut found = BTreeSet::new();
+    collect_sources_into(path, &mut found)?;
+    Ok(found.into_iter().collect())
+}
+
+fn collect_sources_into(path: &Path, found: &mut BTreeSet<PathBuf>) -> Result<()> {
+    if path.is_file() {
+        if is_source_file(path) {
+            found.insert(normalize_path(path));
+        }
+        return Ok(());
+    }
+    if !path.is_dir() {
+        anyhow::bail!("Path does not exist: {}", path.display());
+    }
+    for entry in std::fs::read_dir(path)? {
+        let entry_path = entry?.path();
+        if entry_path.is_dir() {
+            let name = entry_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if name.starts_with('.') || SKIPPED_DIRECTORIES.iter().any(|d| name.eq_ignore_ascii_case(d)) {
+                continue;
+            }
+            collect_sources_into(&entry_path, found)?;
+        } else if is_source_file(&entry_path) {
+            found.insert(normalize_path(&entry_path));
+        }
+    }
+    Ok(())
+}
+
+/// Return the unit files referenced with an explicit `in '...'` path from a
+/// `.dpr` / `.dpk` main source, resolved against its directory.  Units that
+/// do not exist on disk are skipped.
+pub fn main_source_unit_paths(main_source: &Path) -> Vec<PathBuf> {
+    let Ok(raw) = std::fs::read(main_source) else { return Vec::new() };
+    let (content, _) = crate::encoding::decode_source(&raw);
+    let base = main_source.parent().unwrap_or(Path::new("."));
+    UNIT_IN_PATH_REGEX
+        .captures_iter(&content)
+        .filter_map(|caps| caps.name("path").map(|m| m.as_str().replace('\\', std::path::MAIN_SEPARATOR_STR)))
+        .map(|rel| normalize_path(base.join(rel)))
+        .filter(|path| path.is_file())
+        .collect()
+}
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 use anyhow::{Result, Context};
 use scopeguard::defer;
 
-use crate::{projects::CompilerConfigurations};
+use crate::{projects::CompilerConfigurations, utils::ProcessTree};
 
 const DEFAULT_FORMATTER_CONFIG: &str = include_str!("presets/ddk_formatter.config");
 
@@ -26,6 +26,12 @@ impl Formatter {
         Ok(Formatter { config_path, content })
     }
 
+    /// Run the formatter and return the formatted text.
+    ///
+    /// The formatter always writes UTF-8 with a BOM; the BOM is stripped here
+    /// because it is a property of the file, not of the text.  Callers that
+    /// write back to disk re-apply the original file's BOM and line endings
+    /// via [`crate::encoding::encode_source`].
     pub async fn execute(self) -> Result<String> {
         let temp_file = tempfile::NamedTempFile::with_suffix(".pas")?;
         std::fs::write(temp_file.path(), &self.content)?;
@@ -36,11 +42,15 @@ impl Formatter {
         let formatter = CompilerConfigurations::first_available_formatter()
             .await
             .context("No formatters found (all compiler paths have been searched)")?;
-        let status = std::process::Command::new(&formatter)
-            .args(&["-e", "utf-8", "-config"])
+        let mut command = tokio::process::Command::new(&formatter);
+        command
+            .args(["-e", "utf-8", "-config"])
             .arg(&self.config_path)
-            .arg(&temp_file_path)
-            .status()
+            .arg(&temp_file_path);
+        let status = ProcessTree::spawn(&mut command)
+            .context("Failed to execute formatter")?
+            .wait()
+            .await
             .context("Failed to execute formatter")?;
         if !status.success() {
             anyhow::bail!("Formatter failed with exit code: {}", status);
@@ -2,6 +2,9 @@ use tower_lsp::lsp_types::{MessageType, Range, notification::Notification};
 use serde::{Deserialize, Serialize};
 
 use crate::projects::*;
+use crate::projects::build_lock::BuildLockHolder;
+use crate::projects::history::{BuildId, BuildRecord, HistoryQuery};
+use crate::projects::queue::{BuildJobInfo, JobId};
 
 pub enum EventDone {}
 
@@ -124,20 +127,39 @@ pub enum CompilerProgress {}
 #[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
 #[serde(tag = "kind")]
 pub enum CompilerProgressParams {
+    /// The compilation waits in the build queue behind `jobs_ahead` jobs.
+    Queued {
+        job_id: JobId,
+        jobs_ahead: usize,
+    },
     Start {
+        /// Build queue job of the compilation.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        job_id: Option<JobId>,
         lines: Vec<String>,
     },
     Stdout {
         line: String,
+        /// Project whose build printed the line; `None` for lines that
+        /// belong to the compilation as a whole.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        project_id: Option<usize>,
     },
     Stderr {
         line: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        project_id: Option<usize>,
     },
     Completed {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        job_id: Option<JobId>,
         success: bool,
         cancelled: bool,
         code: i32,
         lines: Vec<String>,
+        /// Per-project outcomes, in build order.
+        #[serde(default)]
+        projects: Vec<ProjectOutcome>,
     },
     SingleProjectStarted {
         project_id: usize,
@@ -163,7 +185,7 @@ static COMPILER_BROADCAST: std::sync::OnceLock<broadcast::Sender<CompilerProgres
     std::sync::OnceLock::new();
 
 fn broadcast_channel() -> &'static broadcast::Sender<CompilerProgressParams> {
-    COMPILER_BROADCAST.get_or_init(|| broadcast::channel(512).0)
+    COMPILER_BROADCAST.get_or_init(|| broadcast::channel(2048).0)
 }
 
 impl CompilerProgress {
@@ -177,32 +199,48 @@ impl CompilerProgress {
         let _ = broadcast_channel().send(params.clone());
     }
 
-    pub async fn notify_start(client: Option<&tower_lsp::Client>, lines: Vec<String>) {
-        let params = CompilerProgressParams::Start { lines };
+    pub async fn notify_queued(client: Option<&tower_lsp::Client>, job_id: JobId, jobs_ahead: usize) {
+        let params = CompilerProgressParams::Queued { job_id, jobs_ahead };
         Self::broadcast(&params);
         if let Some(client) = client {
             client.send_notification::<CompilerProgress>(params).await;
         }
     }
 
-    pub async fn notify_stdout(client: Option<&tower_lsp::Client>, line: String) {
-        let params = CompilerProgressParams::Stdout { line };
+    pub async fn notify_start(client: Option<&tower_lsp::Client>, job_id: JobId, lines: Vec<String>) {
+        let params = CompilerProgressParams::Start { job_id: Some(job_id), lines };
         Self::broadcast(&params);
         if let Some(client) = client {
             client.send_notification::<CompilerProgress>(params).await;
         }
     }
 
-    pub async fn notify_stderr(client: Option<&tower_lsp::Client>, line: String) {
-        let params = CompilerProgressParams::Stderr { line };
+    pub async fn notify_stdout(client: Option<&tower_lsp::Client>, project_id: Option<usize>, line: String) {
+        let params = CompilerProgressParams::Stdout { line, project_id };
         Self::broadcast(&params);
         if let Some(client) = client {
             client.send_notification::<CompilerProgress>(params).await;
         }
     }
 
-    pub async fn notify_completed(client: Option<&tower_lsp::Client>, success: bool, cancelled: bool, code: i32, lines: Vec<String>) {
-        let params = CompilerProgressParams::Completed { success, cancelled, code, lines };
+    pub async fn notify_stderr(client: Option<&tower_lsp::Client>, project_id: Option<usize>, line: String) {
+        let params = CompilerProgressParams::Stderr { line, project_id };
+        Self::broadcast(&params);
+        if let Some(client) = client {
+            client.send_notification::<CompilerProgress>(params).await;
+        }
+    }
+
+    pub async fn notify_completed(
+        client: Option<&tower_lsp::Client>,
+        job_id: JobId,
+        success: bool,
+        cancelled: bool,
+        code: i32,
+        lines: Vec<String>,
+        projects: Vec<ProjectOutcome>,
+    ) {
+        let params = CompilerProgressParams::Completed { job_id: Some(job_id), success, cancelled, code, lines, projects };
         Self::broadcast(&params);
         if let Some(client) = client {
             client.send_notification::<CompilerProgress>(params).await;
@@ -245,20 +283,93 @@ pub enum CompileProjectParams {
         project_link_id: Option<usize>,
         rebuild: bool,
         event_id: String,
+        /// Build the projects it depends on in its workspace first.
+        #[serde(default)]
+        with_dependencies: bool,
     },
     AllInWorkspace {
         workspace_id: usize,
         rebuild: bool,
         event_id: String,
+        /// Build every project even after one fails.
+        #[serde(default)]
+        keep_going: bool,
+        /// Number of projects built at the same time; `0` uses one per CPU
+        /// core.  Projects are built one after another when unset.
+        #[serde(default)]
+        jobs: Option<usize>,
     },
     AllInGroupProject {
         rebuild: bool,
         event_id: String,
+        #[serde(default)]
+        keep_going: bool,
+        #[serde(default)]
+        jobs: Option<usize>,
     },
     FromLink {
         project_link_id: usize,
         rebuild: bool,
         event_id: String,
+        #[serde(default)]
+        keep_going: bool,
+        #[serde(default)]
+        jobs: Option<usize>,
+    },
+    /// Build a project, or every project of a workspace, once for every
+    /// combination of the selected configurations, platforms and compilers.
+    Matrix {
+        /// Project to build; unset to build every project of `workspace_id`.
+        project_id: Option<usize>,
+        workspace_id: Option<usize>,
+        /// Build configurations, e.g. "Release"; every configuration of the
+        /// `.dproj` files when empty.
+        #[serde(default)]
+        configurations: Vec<String>,
+        /// Target platforms, e.g. "Win64"; every enabled platform of the
+        /// `.dproj` files when empty.
+        #[serde(default)]
+        platforms: Vec<String>,
+        /// Compiler keys, e.g. "12.0"; the workspace's compiler when empty.
+        #[serde(default)]
+        compilers: Vec<String>,
+        rebuild: bool,
+        event_id: String,
+        /// Build the remaining combinations (and projects) after one fails.
+        #[serde(default)]
+        keep_going: bool,
+        #[serde(default)]
+        jobs: Option<usize>,
+    }
+}
+
+impl CompileProjectParams {
+    /// Whether a multi-project compile continues after a failed project.
+    pub fn keep_going(&self) -> bool {
+        match self {
+            CompileProjectParams::AllInWorkspace { keep_going, .. }
+            | CompileProjectParams::AllInGroupProject { keep_going, .. }
+            | CompileProjectParams::FromLink { keep_going, .. }
+            | CompileProjectParams::Matrix { keep_going, .. } => *keep_going,
+            _ => false,
+        }
+    }
+
+    /// How many projects of a multi-project compile are built at the same
+    /// time.  Always at least 1.
+    pub fn jobs(&self) -> usize {
+        let jobs = match self {
+            CompileProjectParams::AllInWorkspace { jobs, .. }
+            | CompileProjectParams::AllInGroupProject { jobs, .. }
+            | CompileProjectParams::FromLink { jobs, .. }
+            | CompileProjectParams::Matrix { jobs, .. } => *jobs,
+            _ => None,
+        };
+        match jobs {
+            Some(0) => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            Some(jobs) => jobs,
+            _ => 1,
+        }
     }
 }
 
@@ -269,7 +380,40 @@ pub struct ConfigurationFetchResponse {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct CancelCompilationParams {}
+pub struct CancelCompilationParams {
+    /// Build queue job to cancel; the running job when unset.
+    #[serde(default)]
+    pub job_id: Option<JobId>,
+}
+
+/// Request params for `projects/compile-queue`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CompileQueueParams {}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompileQueueResponse {
+    /// Jobs of every running DDK process, by queue time.
+    pub jobs: Vec<BuildJobInfo>,
+    /// The process currently building, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_holder: Option<BuildLockHolder>,
+}
+
+/// Request params for `projects/build-history`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BuildHistoryParams {
+    #[serde(flatten)]
+    pub query: HistoryQuery,
+    /// Return only the build with this id.
+    #[serde(default)]
+    pub build_id: Option<BuildId>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BuildHistoryResponse {
+    /// Recorded builds, most recent first.
+    pub builds: Vec<BuildRecord>,
+}
 
 /// Request params for `dproj/metadata` – asks the server for configuration
 /// and platform information about a single project's .dproj file.
@@ -49,6 +49,10 @@ pub enum Change {
     RemoveWorkspace { workspace_id: usize },
     MoveWorkspace { workspace_id: usize, drop_target: usize },
     UpdateWorkspace { workspace_id: usize, data: WorkspaceUpdateData },
+    AddProjectDependency { workspace_id: usize, project_id: usize, depends_on: usize },
+    RemoveProjectDependency { workspace_id: usize, project_id: usize, depends_on: usize },
+    SetWorkspaceWarningPolicy { workspace_id: usize, policy: WarningPolicy },
+    SetProjectWarningPolicy { project_id: usize, policy: WarningPolicy },
     AddCompiler { key: String, config: CompilerConfiguration },
     RemoveCompiler { compiler: String },
     UpdateCompiler { key: String, data: PartialCompilerConfiguration },
@@ -101,6 +105,18 @@ impl Change {
             Change::UpdateWorkspace { workspace_id, data } => {
                 return Self::update_workspace(workspace_id, data).await;
             }
+            Change::AddProjectDependency { workspace_id, project_id, depends_on } => {
+                return Self::add_project_dependency(workspace_id, project_id, depends_on).await;
+            }
+            Change::RemoveProjectDependency { workspace_id, project_id, depends_on } => {
+                return Self::remove_project_dependency(workspace_id, project_id, depends_on).await;
+            }
+            Change::SetWorkspaceWarningPolicy { workspace_id, policy } => {
+                return Self::set_workspace_warning_policy(workspace_id, policy).await;
+            }
+            Change::SetProjectWarningPolicy { project_id, policy } => {
+                return Self::set_project_warning_policy(project_id, policy).await;
+            }
             Change::AddCompiler { key, config } => {
                 return Self::add_compiler(key, config).await;
             }
@@ -209,6 +225,34 @@ impl Change {
         return projects_data.save().await;
     }
 
+    async fn add_project_dependency(workspace_id: usize, project_id: usize, depends_on: usize) -> Result<()> {
+        let mut projects_data = PROJECTS_DATA.write().await;
+        projects_data.add_project_dependency(workspace_id, project_id, depends_on)?;
+        return projects_data.save().await;
+    }
+
+    async fn remove_project_dependency(workspace_id: usize, project_id: usize, depends_on: usize) -> Result<()> {
+        let mut projects_data = PROJECTS_DATA.write().await;
+        projects_data.remove_project_dependency(workspace_id, project_id, depends_on)?;
+        return projects_data.save().await;
+    }
+
+    async fn set_workspace_warning_policy(workspace_id: usize, policy: WarningPolicy) -> Result<()> {
+        let mut projects_data = PROJECTS_DATA.write().await;
+        let workspace = projects_data.get_workspace_mut(workspace_id)
+            .ok_or_else(|| anyhow::anyhow!("Workspace with id {} not found", workspace_id))?;
+        workspace.warning_policy = policy;
+        return projects_data.save().await;
+    }
+
+    async fn set_project_warning_policy(project_id: usize, policy: WarningPolicy) -> Result<()> {
+        let mut projects_data = PROJECTS_DATA.write().await;
+        let project = projects_data.get_project_mut(project_id)
+            .ok_or_else(|| anyhow::anyhow!("Project with id {} not found", project_id))?;
+        project.warning_policy = policy;
+        return projects_data.save().await;
+    }
+
     async fn add_compiler(key: String, config: CompilerConfiguration) -> Result<()> {
         let mut compilers = COMPILER_CONFIGURATIONS.write().await;
         compilers.insert(key, config);
@@ -226,7 +270,10 @@ impl Change {
     async fn update_compiler(key: String, data: PartialCompilerConfiguration) -> Result<()> {
         let mut compilers = COMPILER_CONFIGURATIONS.write().await;
         if let Some(compiler) = compilers.get_mut(&key) {
-            compiler.update(&data);
+            let mut updated = compiler.clone();
+            updated.update(&data);
+            updated.validate(&key)?;
+            *compiler = updated;
             return compilers.save().await;
         } else {
             anyhow::bail!("Unable to update compiler - compiler not found: {}", key);
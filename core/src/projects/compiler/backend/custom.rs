@@ -0,0 +1,54 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use super::{BuildBackend, BuildProcess, BuildRequest, spawn_process};
+
+/// Runs a user-defined build command, e.g. a build wrapper script.
+///
+/// The placeholders `{project_file}`, `{project_dir}`, `{config}`,
+/// `{platform}`, `{target}` (`Build` on rebuild, `Make` otherwise) and
+/// `{installation_path}` are substituted in `program` and every argument.
+/// The command runs in the project directory with the configuration's
+/// `extra_env` set; the environment script is left to the command itself.
+pub struct CustomCommandBackend {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl CustomCommandBackend {
+    pub fn expand(&self, template: &str, request: &BuildRequest<'_>) -> String {
+        let project_dir = request
+            .project_file
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        template
+            .replace("{project_file}", &request.project_file.to_string_lossy())
+            .replace("{project_dir}", &project_dir)
+            .replace("{config}", &request.config)
+            .replace("{platform}", &request.platform)
+            .replace("{target}", if request.rebuild { "Build" } else { "Make" })
+            .replace("{installation_path}", &request.configuration.installation_path)
+    }
+}
+
+#[async_trait]
+impl BuildBackend for CustomCommandBackend {
+    fn name(&self) -> &str {
+        "custom command"
+    }
+
+    async fn start(&self, request: &BuildRequest<'_>) -> Result<BuildProcess> {
+        if self.program.trim().is_empty() {
+            anyhow::bail!("The custom build command has no program configured.");
+        }
+        let mut command = Command::new(self.expand(&self.program, request));
+        command.args(self.args.iter().map(|arg| self.expand(arg, request)));
+        if let Some(dir) = request.project_file.parent() {
+            command.current_dir(dir);
+        }
+        command.envs(&request.configuration.extra_env);
+        spawn_process(command)
+    }
+}
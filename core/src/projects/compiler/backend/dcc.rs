@@ -0,0 +1,195 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use dproj_rs::DprojBuilder;
+use dproj_rs::dproj::PropertyGroup;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use super::{BuildBackend, BuildProcess, BuildRequest, build_env, spawn_process};
+
+/// Runs the command-line compiler (dcc32, dcc64, …) for the platform
+/// directly, without MSBuild.
+///
+/// When the project has a `.dproj`, the command line is built from the
+/// property group of the effective configuration and platform (see
+/// [`dcc_arguments`]).  Otherwise the `.dpr` / `.dpk` is compiled as is and
+/// the compiler picks up a `.cfg` next to it, as older projects expect.
+pub struct DccBackend;
+
+#[async_trait]
+impl BuildBackend for DccBackend {
+    fn name(&self) -> &str {
+        "DCC"
+    }
+
+    async fn start(&self, request: &BuildRequest<'_>) -> Result<BuildProcess> {
+        let compiler = dcc_executable(&request.configuration.installation_path, &request.platform)?;
+        let envs = build_env(request.configuration)?;
+        let mut command = Command::new(compiler);
+        match &request.project.dproj {
+            Some(dproj_path) if Path::new(dproj_path).exists() => {
+                let dproj = DprojBuilder::new()
+                    .env(envs.clone().unwrap_or_default())
+                    .from_file(dproj_path)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse dproj: {}", e))?;
+                let group = dproj
+                    .active_property_group_for(&request.config, &request.platform)
+                    .map_err(|e| anyhow::anyhow!("Failed to evaluate dproj for {}/{}: {}", request.config, request.platform, e))?;
+                let main_source = match dproj.get_main_source() {
+                    Ok(path) => path,
+                    _ => main_source(request)?,
+                };
+                command.args(dcc_arguments(&group, &main_source, request.rebuild));
+                // Paths in the .dproj are relative to the .dproj itself.
+                if let Some(dir) = Path::new(dproj_path).parent() {
+                    command.current_dir(dir);
+                }
+            }
+            _ => {
+                let main_source = main_source(request)?;
+                command
+                    .arg(if request.rebuild { "-B" } else { "-M" })
+                    .arg(&main_source);
+                if let Some(dir) = main_source.parent() {
+                    command.current_dir(dir);
+                }
+            }
+        }
+        if let Some(envs) = envs {
+            command.envs(envs);
+        }
+        spawn_process(command)
+    }
+}
+
+/// Command-line arguments for the compiler equivalent to what MSBuild passes
+/// for `group`: build mode, defines, search paths, output directories,
+/// namespaces, unit aliases and warning settings, followed by `main_source`.
+pub fn dcc_arguments(group: &PropertyGroup, main_source: &Path, rebuild: bool) -> Vec<String> {
+    let options = &group.dcc_options;
+    let mut args = vec![if rebuild { "-B" } else { "-M" }.to_string()];
+    let mut push = |switch: &str, value: Option<&String>| {
+        if let Some(value) = value.and_then(|value| join_list(value)) {
+            args.push(format!("{switch}{value}"));
+        }
+    };
+
+    push("-D", options.define.as_ref());
+    push("-U", options.unit_search_path.as_ref());
+    // MSBuild falls back to the unit search path for these.
+    push("-I", options.include_path.as_ref().or(options.unit_search_path.as_ref()));
+    push("-R", options.resource_path.as_ref().or(options.unit_search_path.as_ref()));
+    push("-O", options.obj_path.as_ref().or(options.unit_search_path.as_ref()));
+    push("-NS", options.namespace.as_ref());
+    push("-A", options.unit_alias.as_ref());
+    push("-NU", options.dcu_output.as_ref());
+    push("-E", options.exe_output.as_ref());
+    push("-LE", options.bpl_output.as_ref());
+    push("-LN", options.dcp_output.as_ref());
+
+    if is_true(options.console_target.as_deref()) {
+        args.push("-CC".to_string());
+    }
+    if is_false(options.hints.as_deref()) {
+        args.push("-H-".to_string());
+    }
+    if is_false(options.warnings.as_deref()) {
+        args.push("-W-".to_string());
+    }
+    args.extend(warning_switches(group));
+    args.push(main_source.to_string_lossy().to_string());
+    args
+}
+
+/// `-W+ID`, `-W-ID` or `-W^ID` for every individual warning setting, sorted
+/// by id so that the command line is stable.
+fn warning_switches(group: &PropertyGroup) -> Vec<String> {
+    let mut switches: Vec<String> = group
+        .dcc_options
+        .warning_directives
+        .iter()
+        .filter_map(|(tag, value)| {
+            let id = tag.strip_prefix("DCC_")?;
+            // Warning ids are upper case; other DCC_* properties are not.
+            if id.chars().any(|c| c.is_ascii_lowercase()) {
+                return None;
+            }
+            let state = match value.trim().to_lowercase().as_str() {
+                "true" => '+',
+                "false" => '-',
+                "error" => '^',
+                _ => return None,
+            };
+            Some(format!("-W{state}{id}"))
+        })
+        .collect();
+    switches.sort();
+    switches
+}
+
+/// Join a `;`-separated .dproj list, dropping the empty entries left behind
+/// by `$(Var)` references that expand to nothing.
+fn join_list(value: &str) -> Option<String> {
+    let items: Vec<&str> = value
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect();
+    if items.is_empty() {
+        None
+    } else {
+        Some(items.join(";"))
+    }
+}
+
+fn is_true(value: Option<&str>) -> bool {
+    value.is_some_and(|value| value.trim().eq_ignore_ascii_case("true"))
+}
+
+fn is_false(value: Option<&str>) -> bool {
+    value.is_some_and(|value| value.trim().eq_ignore_ascii_case("false"))
+}
+
+/// File name of the command-line compiler for `platform`.
+pub fn dcc_name(platform: &str) -> Option<&'static str> {
+    match platform.to_lowercase().as_str() {
+        "win32" => Some("dcc32"),
+        "win64" => Some("dcc64"),
+        "osx64" => Some("dccosx64"),
+        "osxarm64" => Some("dccosxarm64"),
+        "linux64" => Some("dcclinux64"),
+        "android" => Some("dccaarm"),
+        "android64" => Some("dccaarm64"),
+        "iosdevice64" => Some("dcciosarm64"),
+        _ => None,
+    }
+}
+
+/// `<installation_path>\bin\<dcc>.exe` for `platform`.
+pub fn dcc_executable(installation_path: &str, platform: &str) -> Result<PathBuf> {
+    let Some(name) = dcc_name(platform) else {
+        anyhow::bail!("No command-line compiler known for platform: {}", platform);
+    };
+    let path = Path::new(installation_path).join("bin").join(format!("{name}.exe"));
+    if !path.exists() {
+        anyhow::bail!("Cannot find {} at path: {}", name, path.display());
+    }
+    Ok(path)
+}
+
+/// The compiler takes the `.dpr` / `.dpk`, not the `.dproj`.
+fn main_source(request: &BuildRequest<'_>) -> Result<PathBuf> {
+    let project = request.project;
+    project
+        .dpr
+        .iter()
+        .chain(project.dpk.iter())
+        .map(PathBuf::from)
+        .find(|path| path.exists())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Project \"{}\" has no .dpr or .dpk to compile directly.",
+                project.name
+            )
+        })
+}
@@ -0,0 +1,135 @@
+//! Build backends: how a single project is actually built.
+//!
+//! The [`Compiler`](super::Compiler) owns everything around a build — banners,
+//! progress events, diagnostics, cancellation — and delegates starting the
+//! build itself to a [`BuildBackend`], selected per
+//! [`CompilerConfiguration`] through its [`BuildBackendKind`].
+
+mod custom;
+mod dcc;
+mod msbuild;
+
+pub use custom::*;
+pub use dcc::*;
+pub use msbuild::*;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncRead;
+use tokio::process::Command;
+
+use crate::projects::{BuildBackendKind, CompilerConfiguration, Project};
+use crate::utils::{DEFAULT_GRACE_PERIOD, ProcessTree};
+
+/// Everything a backend needs to build one project.
+#[derive(Debug, Clone)]
+pub struct BuildRequest<'a> {
+    pub project: &'a Project,
+    /// The `.dproj`, `.dpr` or `.dpk` of the project.
+    pub project_file: PathBuf,
+    pub configuration: &'a CompilerConfiguration,
+    /// Effective build configuration, e.g. "Debug".
+    pub config: String,
+    /// Effective platform, e.g. "Win32".
+    pub platform: String,
+    pub rebuild: bool,
+}
+
+/// A started build: its output streams and a handle to wait for or stop it.
+pub struct BuildProcess {
+    pub stdout: Box<dyn AsyncRead + Unpin + Send>,
+    pub stderr: Box<dyn AsyncRead + Unpin + Send>,
+    pub handle: Box<dyn BuildHandle>,
+}
+
+#[async_trait]
+pub trait BuildHandle: Send {
+    /// Wait for the build to finish and return its exit code.
+    async fn wait(&mut self) -> Result<i32>;
+    /// Stop the build, including every process it spawned.
+    async fn kill(&mut self);
+}
+
+#[async_trait]
+pub trait BuildBackend: Send + Sync {
+    /// Short name for logs, e.g. "MSBuild".
+    fn name(&self) -> &str;
+    /// Start building `request`.  Output is read from the returned streams
+    /// until they close; the exit code decides success.
+    async fn start(&self, request: &BuildRequest<'_>) -> Result<BuildProcess>;
+}
+
+/// The backend selected by `configuration`.
+pub fn backend_for(configuration: &CompilerConfiguration) -> Arc<dyn BuildBackend> {
+    match &configuration.backend {
+        BuildBackendKind::MsBuild => Arc::new(MsBuildBackend),
+        BuildBackendKind::Dcc => Arc::new(DccBackend),
+        BuildBackendKind::Custom { program, args } => Arc::new(CustomCommandBackend {
+            program: program.clone(),
+            args: args.clone(),
+        }),
+    }
+}
+
+/// Spawn `command` with piped output as a [`BuildProcess`].  The build runs
+/// as a [`ProcessTree`], so cancelling it also stops everything it started.
+pub fn spawn_process(mut command: Command) -> Result<BuildProcess> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut tree = ProcessTree::spawn(&mut command)?;
+    let stdout = tree.child_mut().stdout.take()
+        .ok_or_else(|| anyhow::anyhow!("Unable to access child process STDOUT"))?;
+    let stderr = tree.child_mut().stderr.take()
+        .ok_or_else(|| anyhow::anyhow!("Unable to access child process STDERR"))?;
+    Ok(BuildProcess {
+        stdout: Box::new(stdout),
+        stderr: Box::new(stderr),
+        handle: Box::new(ProcessHandle { tree, grace_period: DEFAULT_GRACE_PERIOD }),
+    })
+}
+
+/// [`BuildHandle`] of a spawned OS process tree.
+pub struct ProcessHandle {
+    tree: ProcessTree,
+    /// How long the build gets to stop on its own when killed.
+    pub grace_period: Duration,
+}
+
+#[async_trait]
+impl BuildHandle for ProcessHandle {
+    async fn wait(&mut self) -> Result<i32> {
+        let status = self.tree.wait().await?;
+        Ok(status.code().unwrap_or(-1))
+    }
+
+    async fn kill(&mut self) {
+        // Stop the whole tree so that the compilers started by MSBuild or a
+        // wrapper script (dcc32.exe, dcc64.exe, …) do not keep file locks
+        // that make the next compilation of the project fail immediately.
+        self.tree.terminate(self.grace_period).await;
+    }
+}
+
+/// Build environment of `configuration`: the `SET` lines of its environment
+/// script (`rsvars.bat` unless `env_script` says otherwise) plus `extra_env`.
+/// `None` when there is neither.
+pub(crate) fn build_env(configuration: &CompilerConfiguration) -> Result<Option<HashMap<String, String>>> {
+    let script = configuration.env_script_path();
+    let mut envs = if script.exists() {
+        dproj_rs::rsvars::parse_rsvars_file(&script)
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", script.display(), e))?
+    } else if configuration.env_script.is_some() {
+        anyhow::bail!("Cannot find environment script at path: {}", script.display());
+    } else {
+        if configuration.extra_env.is_empty() {
+            return Ok(None);
+        }
+        HashMap::new()
+    };
+    envs.extend(configuration.extra_env.clone());
+    Ok(Some(envs))
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use rust_search::SearchBuilder;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+use crate::projects::CompilerConfiguration;
+
+use super::{BuildBackend, BuildProcess, BuildRequest, build_env, spawn_process};
+
+/// Builds the `.dproj` with MSBuild inside the `rsvars.bat` environment
+/// (or the configuration's `env_script` / `extra_env`).
+pub struct MsBuildBackend;
+
+#[async_trait]
+impl BuildBackend for MsBuildBackend {
+    fn name(&self) -> &str {
+        "MSBuild"
+    }
+
+    async fn start(&self, request: &BuildRequest<'_>) -> Result<BuildProcess> {
+        if !request.configuration.has_build_environment() {
+            anyhow::bail!(
+                "Cannot find environment script at path: {}, or set BDS in extra_env",
+                request.configuration.env_script_path().to_string_lossy()
+            );
+        }
+        let envs = build_env(request.configuration)?.unwrap_or_default();
+        let args = request.configuration.build_arguments.join(" ");
+        let target = if request.rebuild { "Build" } else { "Make" };
+
+        let mut command = Command::new(msbuild_executable(request.configuration)?);
+        command
+            .arg(&request.project_file)
+            .arg(format!("/t:Clean,{}", target))
+            .args(args.split_whitespace())
+            .arg(format!("/p:Config={}", request.config))
+            .arg(format!("/p:Configuration={}", request.config))
+            .arg(format!("/p:Platform={}", request.platform))
+            .envs(envs);
+        spawn_process(command)
+    }
+}
+
+/// The configuration's `msbuild_path`, or [`find_msbuild`] when not set.
+pub fn msbuild_executable(configuration: &CompilerConfiguration) -> Result<String> {
+    match &configuration.msbuild_path {
+        Some(path) if PathBuf::from(path).is_file() => Ok(path.clone()),
+        Some(path) => anyhow::bail!("Cannot find msbuild.exe at path: {}", path),
+        _ => find_msbuild(),
+    }
+}
+
+pub fn find_msbuild() -> Result<String> {
+    let mut search: Vec<String> = SearchBuilder::default()
+        .location(r"C:\Windows\Microsoft.NET\Framework\")
+        .search_input("msbuild.exe")
+        .depth(2)
+        .ignore_case()
+        .build()
+        .collect();
+    search.retain(|path| path.to_lowercase().ends_with("msbuild.exe"));
+    search.sort_by(
+        |left, right| {
+            let left_version = left
+                .split('\\')
+                .rev()
+                .nth(1)
+                .unwrap_or("v0");
+            let right_version = right
+                .split('\\')
+                .rev()
+                .nth(1)
+                .unwrap_or("v0");
+            right_version.cmp(left_version)
+        });
+    if let Some(msbuild_path) = search.first() {
+        return Ok(msbuild_path.clone());
+    }
+    anyhow::bail!(
+        "Cannot find msbuild.exe in C:\\Windows\\Microsoft.NET\\Framework\\. Please ensure that MSBuild is installed and try again."
+    );
+}
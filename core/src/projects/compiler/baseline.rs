@@ -0,0 +1,151 @@
+//! Warning baselines: a committed per-project record of known warnings and
+//! hints, so that builds can fail only on diagnostics that are new.
+//!
+//! The baseline of `App.dproj` is stored next to it as
+//! `App.ddk-baseline.json`.  Entries are keyed by file (relative to the
+//! project directory), code and a normalized message — never by line, so
+//! unrelated edits that shift code around do not turn known warnings into
+//! new ones.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::projects::{CompileDiagnostic, DiagnosticKind, Project};
+
+pub const BASELINE_FILE_SUFFIX: &str = ".ddk-baseline.json";
+const BASELINE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarningBaseline {
+    pub version: u32,
+    pub entries: Vec<BaselineEntry>,
+}
+
+/// A known diagnostic and how many times it occurs.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub file: String,
+    pub code: String,
+    pub message: String,
+    pub count: usize,
+}
+
+/// Diagnostics of a build split by whether the baseline knows them.
+/// Errors are never baselined and appear in neither list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineComparison {
+    pub new: Vec<CompileDiagnostic>,
+    pub known: Vec<CompileDiagnostic>,
+}
+
+impl BaselineComparison {
+    pub fn extend(&mut self, other: BaselineComparison) {
+        self.new.extend(other.new);
+        self.known.extend(other.known);
+    }
+}
+
+type BaselineKey = (String, String, String);
+
+impl WarningBaseline {
+    /// Record every warning and hint in `diagnostics`.
+    pub fn from_diagnostics(diagnostics: &[CompileDiagnostic], project_dir: &Path) -> Self {
+        let mut counts: BTreeMap<BaselineKey, usize> = BTreeMap::new();
+        for diagnostic in diagnostics.iter().filter(|d| is_baselined(d)) {
+            *counts.entry(baseline_key(diagnostic, project_dir)).or_default() += 1;
+        }
+        let entries = counts
+            .into_iter()
+            .map(|((file, code, message), count)| BaselineEntry { file, code, message, count })
+            .collect();
+        WarningBaseline { version: BASELINE_VERSION, entries }
+    }
+
+    /// Load a baseline, returning `None` when the file does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        let baseline = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid baseline file {}", path.display()))?;
+        Ok(Some(baseline))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content + "\n")
+            .with_context(|| format!("Failed to write baseline {}", path.display()))
+    }
+
+    /// Mark each warning / hint of `diagnostics` as new or known.  A key that
+    /// the baseline records `n` times covers its first `n` occurrences only.
+    pub fn compare(&self, diagnostics: &[CompileDiagnostic], project_dir: &Path) -> BaselineComparison {
+        let mut remaining: BTreeMap<BaselineKey, usize> = self
+            .entries
+            .iter()
+            .map(|e| ((e.file.clone(), e.code.clone(), e.message.clone()), e.count))
+            .collect();
+        let mut comparison = BaselineComparison::default();
+        for diagnostic in diagnostics.iter().filter(|d| is_baselined(d)) {
+            match remaining.get_mut(&baseline_key(diagnostic, project_dir)) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    comparison.known.push(diagnostic.clone());
+                }
+                _ => comparison.new.push(diagnostic.clone()),
+            }
+        }
+        comparison
+    }
+}
+
+/// Where the baseline of `project` lives: next to its project file.
+pub fn baseline_path(project: &Project) -> Result<PathBuf> {
+    let project_file = project.get_project_file()?;
+    let stem = project_file
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| project.name.clone());
+    let dir = project_file.parent().map(Path::to_path_buf).unwrap_or_default();
+    Ok(dir.join(format!("{stem}{BASELINE_FILE_SUFFIX}")))
+}
+
+/// Collapse whitespace and replace digit runs with `#`, so that messages
+/// mentioning line numbers or counts stay stable across edits.
+pub fn normalize_message(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut in_digits = false;
+    for word in message.split_whitespace() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        for c in word.chars() {
+            if c.is_ascii_digit() {
+                if !in_digits {
+                    out.push('#');
+                }
+                in_digits = true;
+            } else {
+                out.push(c);
+                in_digits = false;
+            }
+        }
+        in_digits = false;
+    }
+    out
+}
+
+fn is_baselined(diagnostic: &CompileDiagnostic) -> bool {
+    matches!(diagnostic.severity, DiagnosticKind::WARN | DiagnosticKind::HINT)
+}
+
+fn baseline_key(diagnostic: &CompileDiagnostic, project_dir: &Path) -> BaselineKey {
+    let file = crate::reports::relative_to_root(&diagnostic.file, project_dir)
+        .unwrap_or_else(|| diagnostic.file.replace('\\', "/"))
+        .to_lowercase();
+    (file, diagnostic.code.clone(), normalize_message(&diagnostic.message))
+}
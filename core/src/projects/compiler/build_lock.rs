@@ -0,0 +1,180 @@
+//! Build lock shared by every DDK process.
+//!
+//! `ddk-server`, `ddk` and `ddk-mcp-server` each have their own build queue
+//! (see [`queue`](super::queue)), but build the same projects.  A compilation
+//! therefore also holds `build.lock` next to `projects.ron` while it builds;
+//! `build.lock.ron` records who holds it.  Another process either waits for
+//! the lock or fails right away, depending on its [`LockPolicy`].
+//!
+//! A process that does not hold the lock can still cancel the build: it
+//! leaves a request in `build.cancel`, which the holder polls.
+
+use anyhow::Result;
+use fslock::LockFile;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::compiler_state;
+use super::queue::program_name;
+use crate::projects::ProjectsData;
+use crate::utils::{FilePath, process_alive};
+
+/// How often a waiting process retries the lock and the holder looks for a
+/// cancel request.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What a compilation does when another process holds the build lock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LockPolicy {
+    /// Wait until the other build has finished.
+    #[default]
+    Wait,
+    /// Fail immediately.
+    Fail,
+}
+
+/// The process holding the build lock and what it builds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildLockHolder {
+    pub pid: u32,
+    pub program: String,
+    /// What is built, e.g. "Compiling Project App".
+    pub description: String,
+    /// Unix timestamp in milliseconds.
+    pub started_at: i64,
+}
+
+impl BuildLockHolder {
+    /// A holder record for this process.
+    pub fn current(description: String) -> Self {
+        BuildLockHolder {
+            pid: std::process::id(),
+            program: program_name(),
+            description,
+            started_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+impl fmt::Display for BuildLockHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let started = chrono::DateTime::from_timestamp_millis(self.started_at)
+            .map(|t| t.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+            .unwrap_or_default();
+        write!(f, "'{}' in {} (pid {}) since {}", self.description, self.program, self.pid, started)
+    }
+}
+
+/// The build lock, held until dropped.
+pub struct BuildLock {
+    _file: LockFile,
+    cancel_watcher: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        self.cancel_watcher.abort();
+        let _ = std::fs::remove_file(holder_path());
+        let _ = std::fs::remove_file(cancel_path());
+    }
+}
+
+fn lock_path() -> PathBuf {
+    <ProjectsData as FilePath>::get_file_path().with_file_name("build.lock")
+}
+
+fn holder_path() -> PathBuf {
+    <ProjectsData as FilePath>::get_file_path().with_file_name("build.lock.ron")
+}
+
+fn cancel_path() -> PathBuf {
+    <ProjectsData as FilePath>::get_file_path().with_file_name("build.cancel")
+}
+
+/// Take the build lock if no other process holds it.
+pub fn try_acquire(holder: &BuildLockHolder) -> Result<Option<BuildLock>> {
+    let path = lock_path();
+    std::fs::create_dir_all(path.parent().expect("Failed to get parent directory"))?;
+    let mut file = LockFile::open(&path)?;
+    if !file.try_lock()? {
+        return Ok(None);
+    }
+    // A cancel request left behind by an earlier build must not hit this one.
+    let _ = std::fs::remove_file(cancel_path());
+    if let Ok(serialized) = ron::ser::to_string_pretty(holder, PrettyConfig::default()) {
+        let _ = std::fs::write(holder_path(), serialized);
+    }
+    let cancel_watcher = tokio::spawn(async {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if take_cancel_request() {
+                compiler_state::cancel();
+            }
+        }
+    });
+    Ok(Some(BuildLock { _file: file, cancel_watcher }))
+}
+
+/// Take the build lock.  With [`LockPolicy::Wait`], `on_wait` is called once
+/// with the holder and the lock is retried until it is free; `None` when
+/// the compilation is cancelled meanwhile.
+pub async fn acquire(
+    holder: &BuildLockHolder,
+    policy: LockPolicy,
+    on_wait: impl AsyncFnOnce(&BuildLockHolder),
+) -> Result<Option<BuildLock>> {
+    let mut on_wait = Some(on_wait);
+    loop {
+        if let Some(lock) = try_acquire(holder)? {
+            return Ok(Some(lock));
+        }
+        let other = current_holder();
+        if policy == LockPolicy::Fail {
+            match other {
+                Some(other) => anyhow::bail!("Another DDK process is building: {}", other),
+                _ => anyhow::bail!("Another DDK process is building."),
+            }
+        }
+        if let Some(other) = other
+            && let Some(on_wait) = on_wait.take()
+        {
+            on_wait(&other).await;
+        }
+        if compiler_state::is_cancelled() {
+            return Ok(None);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// The process holding the build lock, if any.
+pub fn current_holder() -> Option<BuildLockHolder> {
+    let content = std::fs::read_to_string(holder_path()).ok()?;
+    let holder: BuildLockHolder = ron::from_str(&content).ok()?;
+    process_alive(holder.pid).then_some(holder)
+}
+
+/// Ask the process holding the build lock to cancel its build.
+pub fn request_cancel() -> Result<BuildLockHolder> {
+    let Some(holder) = current_holder() else {
+        anyhow::bail!("No build is running.");
+    };
+    std::fs::write(cancel_path(), holder.pid.to_string())?;
+    Ok(holder)
+}
+
+/// Whether a cancel request for this process is pending; consumes it.
+fn take_cancel_request() -> bool {
+    let path = cancel_path();
+    let requested = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| content.trim().parse::<u32>().ok())
+        .is_some_and(|pid| pid == std::process::id());
+    if requested {
+        let _ = std::fs::remove_file(path);
+    }
+    requested
+}
@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard};
+
+use super::queue::JobId;
 
 //------------------------------------------------------
 static ACTIVE: AtomicBool = AtomicBool::new(false);
@@ -25,6 +27,28 @@ pub fn cancel() {
     CANCELLED.store(true, Ordering::SeqCst);
 }
 
+//------------------------------------------------------
+static JOB: Mutex<Option<JobId>> = Mutex::new(None);
+
+fn job_lock() -> MutexGuard<'static, Option<JobId>> {
+    JOB.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Record that build queue job `id` is the one being compiled.
+pub fn start_job(id: JobId) {
+    *job_lock() = Some(id);
+}
+
+/// Cancel the compilation if it is job `id`; returns whether it was.  A
+/// cancel that arrives once the job has finished cannot stop the next one.
+pub fn cancel_job(id: JobId) -> bool {
+    let job = job_lock();
+    if *job == Some(id) {
+        cancel();
+    }
+    *job == Some(id)
+}
+
 //------------------------------------------------------
 static SUCCESS: AtomicBool = AtomicBool::new(false);
 
@@ -50,6 +74,8 @@ pub fn get_code() -> i32 {
 //------------------------------------------------------
 
 pub fn reset() {
+    let mut job = job_lock();
+    *job = None;
     ACTIVE.store(false, Ordering::SeqCst);
     SUCCESS.store(false, Ordering::SeqCst);
     CODE.store(-1, Ordering::SeqCst);
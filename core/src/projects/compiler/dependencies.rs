@@ -0,0 +1,217 @@
+//! Build order of the projects of a multi-project compilation.
+//!
+//! A project depends on another project of the same compilation when
+//!
+//! * the group project declares it (`<Dependencies>` of a `<Projects>` item), or
+//! * it consumes what the other project produces: the other project builds a
+//!   package that it requires (`<DCCReference Include="Pkg.dcp"/>`) or links
+//!   as a runtime package (`DCC_UsePackage`), or one of its unit search path
+//!   directories is a DCU, DCP or BPL output directory of that package.
+//!
+//! [`BuildGraph`] orders the projects so that dependencies are built first;
+//! the compiler starts a project once everything it depends on was built.
+
+use dproj_rs::{Dproj, DprojBuilder};
+use dproj_rs::dproj::PropertyGroup;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use crate::projects::Project;
+use crate::utils::normalize_path;
+
+/// What a project produces for, and consumes from, other projects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectReferences {
+    /// Name of the package the project builds (lowercase), `None` for
+    /// applications and libraries.
+    pub package: Option<String>,
+    /// DCU, DCP and BPL output directories (see [`path_key`]).
+    pub output_dirs: Vec<String>,
+    /// Packages the project requires or links at runtime (lowercase).
+    pub packages: Vec<String>,
+    /// Unit search path directories (see [`path_key`]).
+    pub search_dirs: Vec<String>,
+}
+
+impl ProjectReferences {
+    /// References of `project` built for `config` / `platform`.  `None` when
+    /// the project has no `.dproj` or it cannot be evaluated.
+    pub fn load(project: &Project, config: &str, platform: &str, env: HashMap<String, String>) -> Option<Self> {
+        let dproj_path = project.dproj.as_ref()?;
+        let dproj = DprojBuilder::new().env(env).from_file(dproj_path).ok()?;
+        let group = dproj.active_property_group_for(config, platform).ok()?;
+        let directory = Path::new(dproj_path).parent()?;
+        Some(Self::from_dproj(&dproj, &group, directory))
+    }
+
+    /// References of `dproj`, whose effective property group is `group`.
+    /// Relative paths are resolved against `directory`.
+    pub fn from_dproj(dproj: &Dproj, group: &PropertyGroup, directory: &Path) -> Self {
+        let package = dproj
+            .get_main_source()
+            .ok()
+            .or_else(|| group.project_properties.main_source.as_ref().map(|source| directory.join(source)))
+            .filter(|source| has_extension(&source.to_string_lossy(), "dpk"))
+            .and_then(|source| file_stem(&source.to_string_lossy()).map(str::to_lowercase));
+
+        let options = &group.dcc_options;
+        let output_dirs = [&options.dcu_output, &options.dcp_output, &options.bpl_output]
+            .into_iter()
+            .flatten()
+            .map(|dir| path_key(directory, dir))
+            .collect();
+
+        let mut packages: Vec<String> = dproj
+            .project
+            .item_groups
+            .iter()
+            .flat_map(|item_group| &item_group.dcc_references)
+            .filter(|reference| has_extension(&reference.include, "dcp"))
+            .filter_map(|reference| file_stem(&reference.include))
+            .map(|name| name.to_lowercase())
+            .collect();
+        let runtime_packages = group
+            .project_properties
+            .use_packages
+            .as_deref()
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"));
+        if runtime_packages && let Some(use_package) = &options.use_package {
+            packages.extend(split_list(use_package).map(|name| name.to_lowercase()));
+        }
+
+        let search_dirs = options
+            .unit_search_path
+            .as_deref()
+            .map(|paths| split_list(paths).map(|dir| path_key(directory, dir)).collect())
+            .unwrap_or_default();
+
+        ProjectReferences { package, output_dirs, packages, search_dirs }
+    }
+
+    /// Whether this project consumes the package built by `other`.
+    pub fn depends_on(&self, other: &ProjectReferences) -> bool {
+        let Some(package) = &other.package else {
+            return false;
+        };
+        self.packages.contains(package)
+            || self.search_dirs.iter().any(|dir| other.output_dirs.contains(dir))
+    }
+}
+
+/// Dependencies between the projects of a compilation, by their index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildGraph {
+    dependencies: Vec<BTreeSet<usize>>,
+}
+
+impl BuildGraph {
+    /// A graph of `len` projects without dependencies.
+    pub fn new(len: usize) -> Self {
+        BuildGraph { dependencies: vec![BTreeSet::new(); len] }
+    }
+
+    /// Infer the dependencies between projects from what they produce and
+    /// consume.  Projects without references (`None`) have no inferred
+    /// dependencies and nothing depends on them.
+    pub fn infer(references: &[Option<ProjectReferences>]) -> Self {
+        let mut graph = Self::new(references.len());
+        for (project, consumer) in references.iter().enumerate() {
+            let Some(consumer) = consumer else { continue };
+            for (dependency, producer) in references.iter().enumerate() {
+                if let Some(producer) = producer
+                    && dependency != project
+                    && consumer.depends_on(producer)
+                {
+                    graph.add_dependency(project, dependency);
+                }
+            }
+        }
+        graph
+    }
+
+    pub fn len(&self) -> usize {
+        self.dependencies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+
+    /// Record that `project` has to be built after `dependency`.
+    pub fn add_dependency(&mut self, project: usize, dependency: usize) {
+        if project != dependency && dependency < self.len() {
+            self.dependencies[project].insert(dependency);
+        }
+    }
+
+    pub fn dependencies(&self, project: usize) -> &BTreeSet<usize> {
+        &self.dependencies[project]
+    }
+
+    /// Build order: every project comes after its dependencies and otherwise
+    /// keeps its position.  Projects caught in a dependency cycle (and those
+    /// depending on them) follow in their original order.
+    pub fn order(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.len());
+        let mut done = vec![false; self.len()];
+        while order.len() < self.len() {
+            let next = (0..self.len()).find(|&project| {
+                !done[project] && self.dependencies[project].iter().all(|&dependency| done[dependency])
+            });
+            let Some(next) = next else { break };
+            done[next] = true;
+            order.push(next);
+        }
+        order.extend((0..self.len()).filter(|&project| !done[project]));
+        order
+    }
+
+    /// Projects that (indirectly) depend on themselves, in index order.
+    pub fn cyclic(&self) -> Vec<usize> {
+        (0..self.len()).filter(|&project| self.reaches(project, project)).collect()
+    }
+
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut visited = vec![false; self.len()];
+        let mut stack: Vec<usize> = self.dependencies[from].iter().copied().collect();
+        while let Some(project) = stack.pop() {
+            if project == to {
+                return true;
+            }
+            if !std::mem::replace(&mut visited[project], true) {
+                stack.extend(self.dependencies[project].iter().copied());
+            }
+        }
+        false
+    }
+}
+
+/// Comparable form of a path from a project file: relative to `base`,
+/// `.` / `..` resolved, `/` separated, lowercase and without a trailing
+/// separator.
+pub fn path_key(base: &Path, path: &str) -> String {
+    let path = path.trim().replace('\\', "/");
+    let is_absolute = path.starts_with('/') || path.as_bytes().get(1) == Some(&b':');
+    let path = if is_absolute { Path::new(&path).to_path_buf() } else { base.join(&path) };
+    normalize_path(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+        .trim_end_matches('/')
+        .to_lowercase()
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(';').map(str::trim).filter(|item| !item.is_empty() && !item.starts_with("$("))
+}
+
+fn has_extension(path: &str, extension: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, ext)| ext.eq_ignore_ascii_case(extension))
+}
+
+/// File name without directory and extension; accepts `\` and `/`.
+fn file_stem(path: &str) -> Option<&str> {
+    let name = path.rsplit(['\\', '/']).next()?;
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    (!stem.is_empty()).then_some(stem)
+}
@@ -0,0 +1,286 @@
+//! Build history: every compilation that got to build is recorded in
+//! `build-history.ron` next to `projects.ron`, so that one can look up when a
+//! project last built green, with which compiler, and how its build time
+//! developed.
+//!
+//! The raw compiler output of each project is kept in
+//! `build-logs/<build id>/<project id>.log` for the most recent builds.
+//!
+//! A compilation takes its id with [`next_id`] and records itself while it
+//! still holds the [build lock](super::build_lock), so the language server,
+//! the MCP server and the CLI never write the history at the same time.
+
+use anyhow::Result;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use super::{ProjectOutcome, ProjectStatus};
+use crate::projects::{DiagnosticCounts, ProjectsData};
+use crate::utils::FilePath;
+
+pub type BuildId = u64;
+
+/// How many builds are kept; older ones are dropped first.
+const BUILDS_KEPT: usize = 1000;
+/// How many of the most recent builds keep their logs.
+const LOGS_KEPT: usize = 50;
+
+/// A finished compilation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildRecord {
+    pub id: BuildId,
+    /// What was built, e.g. "Compiling Workspace Main".
+    pub description: String,
+    /// Unix timestamps in milliseconds.
+    pub started_at: i64,
+    pub finished_at: i64,
+    /// Key of the compiler configuration, e.g. "23.0", and its product name.
+    pub compiler_key: String,
+    pub compiler_name: String,
+    pub rebuild: bool,
+    pub success: bool,
+    pub cancelled: bool,
+    pub code: i32,
+    /// Program that ran the build, e.g. "ddk-server".
+    pub program: String,
+    /// One entry per project of the compilation, in build order.
+    pub projects: Vec<ProjectRecord>,
+}
+
+/// What happened to a single project of a recorded build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectRecord {
+    pub project_id: usize,
+    pub project_name: String,
+    pub config: String,
+    pub platform: String,
+    pub status: ProjectStatus,
+    pub code: i32,
+    pub duration_ms: u64,
+    pub diagnostics: DiagnosticCounts,
+    /// Raw compiler output of the project, while it is kept.
+    #[serde(default)]
+    pub log: Option<String>,
+}
+
+impl ProjectRecord {
+    pub fn new(outcome: &ProjectOutcome, config: String, platform: String, log: Option<String>) -> Self {
+        ProjectRecord {
+            project_id: outcome.project_id,
+            project_name: outcome.project_name.clone(),
+            config,
+            platform,
+            status: outcome.status,
+            code: outcome.code,
+            duration_ms: outcome.duration_ms,
+            diagnostics: outcome.diagnostics,
+            log,
+        }
+    }
+
+    pub fn status_str(&self) -> &'static str {
+        match self.status {
+            ProjectStatus::Succeeded => "succeeded",
+            ProjectStatus::UpToDate => "up to date",
+            ProjectStatus::Failed => "failed",
+            ProjectStatus::Cancelled => "cancelled",
+            ProjectStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl BuildRecord {
+    pub fn duration_ms(&self) -> u64 {
+        (self.finished_at - self.started_at).max(0) as u64
+    }
+
+    /// Diagnostics of every project of the build.
+    pub fn diagnostics(&self) -> DiagnosticCounts {
+        let mut counts = DiagnosticCounts::default();
+        for project in &self.projects {
+            counts.merge(project.diagnostics);
+        }
+        counts
+    }
+
+    pub fn project(&self, project_id: usize) -> Option<&ProjectRecord> {
+        self.projects.iter().find(|project| project.project_id == project_id)
+    }
+
+    /// Outcome of the whole build: "succeeded", "failed" or "cancelled".
+    pub fn status_str(&self) -> &'static str {
+        if self.cancelled {
+            "cancelled"
+        } else if self.success {
+            "succeeded"
+        } else {
+            "failed"
+        }
+    }
+}
+
+/// The compiler configuration, configuration and platform a project was
+/// built with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuiltWith {
+    pub compiler_key: String,
+    pub config: String,
+    pub platform: String,
+}
+
+/// Which builds [`query`] returns.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    /// Only builds that compiled this project.
+    #[serde(default)]
+    pub project_id: Option<usize>,
+    /// Only builds with this compiler configuration.
+    #[serde(default)]
+    pub compiler_key: Option<String>,
+    /// Only successful (`true`) or failed and cancelled (`false`) builds.
+    /// With `project_id`, the outcome of that project counts.
+    #[serde(default)]
+    pub success: Option<bool>,
+    /// At most this many builds, the most recent ones.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, record: &BuildRecord) -> bool {
+        if let Some(key) = &self.compiler_key
+            && &record.compiler_key != key
+        {
+            return false;
+        }
+        let succeeded = match self.project_id {
+            Some(project_id) => match record.project(project_id) {
+                Some(project) => project.status.is_success(),
+                _ => return false,
+            },
+            _ => record.success,
+        };
+        self.success.is_none_or(|success| success == succeeded)
+    }
+}
+
+fn history_path() -> PathBuf {
+    <ProjectsData as FilePath>::get_file_path().with_file_name("build-history.ron")
+}
+
+fn logs_dir(id: BuildId) -> PathBuf {
+    <ProjectsData as FilePath>::get_file_path().with_file_name("build-logs").join(id.to_string())
+}
+
+/// Where the raw compiler output of `project_id` in build `id` is saved.
+pub fn log_path(id: BuildId, project_id: usize) -> PathBuf {
+    logs_dir(id).join(format!("{project_id}.log"))
+}
+
+/// Every recorded build, oldest first.  An unreadable history is empty.
+pub fn load() -> Vec<BuildRecord> {
+    std::fs::read_to_string(history_path())
+        .ok()
+        .and_then(|content| ron::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Id of the next build recorded.
+pub fn next_id() -> BuildId {
+    load().last().map_or(1, |last| last.id + 1)
+}
+
+/// Append `record`, dropping the oldest builds beyond the kept ones and the
+/// logs of all but the most recent builds.
+pub fn record(record: BuildRecord) -> Result<()> {
+    let mut records = load();
+    records.push(record);
+    let excess = records.len().saturating_sub(BUILDS_KEPT);
+    for old in records.drain(..excess) {
+        let _ = std::fs::remove_dir_all(logs_dir(old.id));
+    }
+    let logged = records.len().saturating_sub(LOGS_KEPT);
+    for old in &mut records[..logged] {
+        if old.projects.iter().any(|project| project.log.is_some()) {
+            let _ = std::fs::remove_dir_all(logs_dir(old.id));
+            old.projects.iter_mut().for_each(|project| project.log = None);
+        }
+    }
+    let path = history_path();
+    std::fs::create_dir_all(path.parent().expect("Failed to get parent directory"))?;
+    std::fs::write(path, ron::ser::to_string_pretty(&records, PrettyConfig::default())?)?;
+    Ok(())
+}
+
+/// Builds matching `query`, most recent first.
+pub fn query(query: &HistoryQuery) -> Vec<BuildRecord> {
+    load()
+        .into_iter()
+        .rev()
+        .filter(|record| query.matches(record))
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// What each project was built with by the most recent build that compiled
+/// it successfully; a build that found it up to date compiled nothing.
+pub fn last_built() -> HashMap<usize, BuiltWith> {
+    let mut built = HashMap::new();
+    for record in load().into_iter().rev() {
+        for project in record.projects.iter().filter(|project| project.status == ProjectStatus::Succeeded) {
+            built.entry(project.project_id).or_insert_with(|| BuiltWith {
+                compiler_key: record.compiler_key.clone(),
+                config: project.config.clone(),
+                platform: project.platform.clone(),
+            });
+        }
+    }
+    built
+}
+
+/// The build with id `id`.
+pub fn get(id: BuildId) -> Option<BuildRecord> {
+    load().into_iter().find(|record| record.id == id)
+}
+
+/// Local date and time of a Unix timestamp in milliseconds.
+pub fn format_time(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+impl fmt::Display for BuildRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Build #{}: {}", self.id, self.description)?;
+        writeln!(f, "  Started:     {} ({:.1}s)", format_time(self.started_at), self.duration_ms() as f64 / 1000.0)?;
+        writeln!(f, "  Compiler:    {} ({})", self.compiler_name, self.compiler_key)?;
+        writeln!(f, "  Action:      {}", if self.rebuild { "Rebuild" } else { "Compile" })?;
+        match self.status_str() {
+            "failed" => writeln!(f, "  Result:      failed (exit code {})", self.code)?,
+            status => writeln!(f, "  Result:      {status}")?,
+        }
+        writeln!(f, "  Program:     {}", self.program)?;
+        writeln!(f, "  Diagnostics: {}", self.diagnostics())?;
+        writeln!(f, "  Projects:")?;
+        for project in &self.projects {
+            writeln!(
+                f,
+                "    [{}] {:<30} {:<16} {:<9} {:>7.1}s  {}",
+                project.project_id,
+                project.project_name,
+                format!("{}/{}", project.config, project.platform),
+                project.status_str(),
+                project.duration_ms as f64 / 1000.0,
+                project.diagnostics,
+            )?;
+            if let Some(log) = &project.log {
+                writeln!(f, "        log: {log}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+//! Diagnostics of the most recent build of each project.
+//!
+//! Every finished project build replaces the diagnostics of its project in
+//! `diagnostics/<project id>.ron` next to `projects.ron`, so they can be
+//! looked at again (`ddk diagnostics`, MCP `delphi_get_diagnostics`) without
+//! rebuilding, also from another DDK process than the one that built.  Reads
+//! are served from memory while the file has not changed.
+
+use anyhow::Result;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use super::history::BuildId;
+use crate::projects::{CompileDiagnostic, DiagnosticCounts, DiagnosticKind, ProjectsData};
+use crate::utils::{FilePath, normalize_path};
+
+/// Diagnostics of the last build of a project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectDiagnostics {
+    pub project_id: usize,
+    pub project_name: String,
+    /// Build of the [history](super::history) the diagnostics come from.
+    pub build_id: BuildId,
+    /// Unix timestamp in milliseconds.
+    pub finished_at: i64,
+    pub diagnostics: Vec<CompileDiagnostic>,
+}
+
+impl ProjectDiagnostics {
+    pub fn counts(&self) -> DiagnosticCounts {
+        let mut counts = DiagnosticCounts::default();
+        for diagnostic in &self.diagnostics {
+            counts.add(diagnostic.severity);
+        }
+        counts
+    }
+}
+
+/// Which diagnostics [`query`] returns.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticsQuery {
+    /// Only diagnostics of this project.
+    #[serde(default)]
+    pub project_id: Option<usize>,
+    /// Only diagnostics of this severity; errors include fatal errors.
+    #[serde(default)]
+    pub severity: Option<DiagnosticKind>,
+    /// Only diagnostics whose file path contains this text, ignoring case.
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Only diagnostics with this code, e.g. "W1036", ignoring case.
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+impl DiagnosticsQuery {
+    pub fn matches(&self, diagnostic: &CompileDiagnostic) -> bool {
+        self.severity.is_none_or(|severity| match severity {
+            DiagnosticKind::ERROR => diagnostic.severity.is_error(),
+            _ => diagnostic.severity == severity,
+        })
+            && self.code.as_ref().is_none_or(|code| diagnostic.code.eq_ignore_ascii_case(code))
+            && self
+                .file
+                .as_ref()
+                .is_none_or(|file| diagnostic.file.to_lowercase().contains(&file.to_lowercase()))
+    }
+}
+
+/// Modification time and length of a stored file, to tell whether the
+/// cached diagnostics are still current.
+type Stamp = (SystemTime, u64);
+type Cache = HashMap<usize, (Stamp, Arc<ProjectDiagnostics>)>;
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+fn cache() -> MutexGuard<'static, Option<Cache>> {
+    CACHE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn store_dir() -> PathBuf {
+    <ProjectsData as FilePath>::get_file_path().with_file_name("diagnostics")
+}
+
+fn store_path(project_id: usize) -> PathBuf {
+    store_dir().join(format!("{project_id}.ron"))
+}
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Replace the diagnostics of `diagnostics.project_id`.
+pub fn store(diagnostics: ProjectDiagnostics) -> Result<()> {
+    let path = store_path(diagnostics.project_id);
+    std::fs::create_dir_all(store_dir())?;
+    std::fs::write(&path, ron::ser::to_string_pretty(&diagnostics, PrettyConfig::default())?)?;
+    if let Some(stamp) = stamp(&path) {
+        cache()
+            .get_or_insert_with(HashMap::new)
+            .insert(diagnostics.project_id, (stamp, Arc::new(diagnostics)));
+    }
+    Ok(())
+}
+
+/// Diagnostics of the last build of `project_id`, if it was built.
+pub fn get(project_id: usize) -> Option<Arc<ProjectDiagnostics>> {
+    let path = store_path(project_id);
+    let stamp = stamp(&path)?;
+    let mut cache = cache();
+    let cache = cache.get_or_insert_with(HashMap::new);
+    if let Some((cached, diagnostics)) = cache.get(&project_id)
+        && *cached == stamp
+    {
+        return Some(diagnostics.clone());
+    }
+    let content = std::fs::read_to_string(&path).ok()?;
+    let diagnostics: Arc<ProjectDiagnostics> = Arc::new(ron::from_str(&content).ok()?);
+    cache.insert(project_id, (stamp, diagnostics.clone()));
+    Some(diagnostics)
+}
+
+/// Last-build diagnostics of every built project, by project id.
+pub fn all() -> Vec<Arc<ProjectDiagnostics>> {
+    let mut ids: Vec<usize> = std::fs::read_dir(store_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.path().file_stem()?.to_str()?.parse().ok())
+        .collect();
+    ids.sort_unstable();
+    ids.into_iter().filter_map(get).collect()
+}
+
+/// Last-build diagnostics matching `query`, per project.  Projects without
+/// a matching diagnostic are still listed, with none.
+pub fn query(query: &DiagnosticsQuery) -> Vec<ProjectDiagnostics> {
+    let projects = match query.project_id {
+        Some(project_id) => get(project_id).into_iter().collect(),
+        _ => all(),
+    };
+    projects
+        .into_iter()
+        .map(|project| ProjectDiagnostics {
+            project_id: project.project_id,
+            project_name: project.project_name.clone(),
+            build_id: project.build_id,
+            finished_at: project.finished_at,
+            diagnostics: project.diagnostics.iter().filter(|d| query.matches(d)).cloned().collect(),
+        })
+        .collect()
+}
+
+/// Last-build diagnostics at the 1-based `line` of `file`, of every project.
+/// File names are compared ignoring case, as on Windows.
+pub fn at(file: &Path, line: u32) -> Vec<CompileDiagnostic> {
+    let file = normalize_path(file).to_string_lossy().to_lowercase();
+    all()
+        .iter()
+        .flat_map(|project| project.diagnostics.iter())
+        .filter(|diagnostic| {
+            diagnostic.line == line && normalize_path(&diagnostic.file).to_string_lossy().to_lowercase() == file
+        })
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,437 @@
+//! Build matrix: a project, or every project of a workspace, built once for
+//! every combination of build configuration, platform and compiler.
+//!
+//! The combinations are built one after another as a single job of the
+//! build queue.  Each one is built like a regular compilation of the
+//! project or workspace with the configuration, platform and compiler of
+//! the combination applied on top of the saved settings (which stay
+//! unchanged), and is recorded in the [`history`] on its own.  Projects are
+//! built even when their output looks [`up_to_date`]: every compiler writes
+//! the same output files.  The footer of the job is a grid with one row per
+//! configuration and platform and one column per compiler.
+
+use super::*;
+use std::fmt;
+
+/// One combination of a build matrix and its outcome.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatrixCell {
+    pub configuration: String,
+    pub platform: String,
+    pub compiler_key: String,
+    /// Product name of the compiler, e.g. "Delphi 12 Athens".
+    pub compiler_name: String,
+    /// `Succeeded` when every project built, `Skipped` when the matrix
+    /// stopped before the combination.
+    pub status: ProjectStatus,
+    pub code: i32,
+    pub duration_ms: u64,
+    /// Outcome of each project of the combination, in build order.
+    pub projects: Vec<ProjectOutcome>,
+    /// Entry of the combination in the build history.
+    #[serde(default)]
+    pub history_id: Option<history::BuildId>,
+}
+
+impl fmt::Display for MatrixCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} | {} | {}", self.configuration, self.platform, self.compiler_name)
+    }
+}
+
+/// What a build matrix builds, resolved from its parameters.
+struct MatrixTarget<'a> {
+    name: String,
+    projects: Vec<&'a Project>,
+    /// Link the single project is built from.
+    project_link_id: Option<usize>,
+    /// Compiler the projects are built with when no compiler is selected.
+    compiler_key: String,
+}
+
+/// Values of one axis of the matrix: the `selected` ones, spelled as in
+/// `available`, or all `available` ones when none are selected.
+fn axis(kind: &str, selected: &[String], available: Vec<String>) -> Result<Vec<String>> {
+    if selected.is_empty() {
+        if available.is_empty() {
+            anyhow::bail!("No {kind}s found in the project files; select them explicitly.");
+        }
+        return Ok(available);
+    }
+    let mut values: Vec<String> = Vec::new();
+    for value in selected {
+        let value = match available.iter().find(|a| a.eq_ignore_ascii_case(value.trim())) {
+            Some(known) => known.clone(),
+            _ if available.is_empty() => value.trim().to_string(),
+            _ => anyhow::bail!("Unknown {kind} \"{value}\". Available: {}", available.join(", ")),
+        };
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    Ok(values)
+}
+
+/// The configurations (without "Base") and enabled platforms of the
+/// `.dproj` files of `projects`, in order of appearance.
+fn available_targets(projects: &[&Project]) -> (Vec<String>, Vec<String>) {
+    let (mut configurations, mut platforms): (Vec<String>, Vec<String>) = (Vec::new(), Vec::new());
+    for project in projects {
+        let Some(dproj_path) = &project.dproj else { continue };
+        let Ok(dproj) = dproj_cache::get_or_load(project.id, &PathBuf::from(dproj_path)) else { continue };
+        for configuration in dproj_cache::get_configurations(&dproj) {
+            if configuration != "Base" && !configurations.contains(&configuration) {
+                configurations.push(configuration);
+            }
+        }
+        for (platform, enabled) in dproj_cache::get_platforms(&dproj) {
+            if enabled && !platforms.contains(&platform) {
+                platforms.push(platform);
+            }
+        }
+    }
+    (configurations, platforms)
+}
+
+impl Compiler {
+    /// The project `project_id`, or else the projects of `workspace_id`.
+    fn matrix_target(&self, project_id: Option<usize>, workspace_id: Option<usize>) -> Result<MatrixTarget<'_>> {
+        let data = &self.projects_data;
+        if let Some(project_id) = project_id {
+            let project = data
+                .get_project(project_id)
+                .ok_or_else(|| anyhow::anyhow!("Project with id {} not found", project_id))?;
+            let link = |links: &[ProjectLink]| links.iter().find(|link| link.project_id == project_id).map(|link| link.id);
+            let (project_link_id, compiler_key) = match data.workspaces.iter().find_map(|ws| Some((link(&ws.project_links)?, ws))) {
+                Some((link_id, workspace)) => (link_id, workspace.compiler_id.clone()),
+                _ => match data.group_project.as_ref().and_then(|gp| link(&gp.project_links)) {
+                    Some(link_id) => (link_id, data.group_project_compiler_id.clone()),
+                    _ => anyhow::bail!("No workspace or group project contains project with id {}", project_id),
+                },
+            };
+            return Ok(MatrixTarget {
+                name: project.name.clone(),
+                projects: vec![project],
+                project_link_id: Some(project_link_id),
+                compiler_key,
+            });
+        }
+        let Some(workspace_id) = workspace_id else {
+            anyhow::bail!("A build matrix needs a project or a workspace.");
+        };
+        let workspace = data
+            .get_workspace(workspace_id)
+            .ok_or_else(|| anyhow::anyhow!("Workspace with id {} not found", workspace_id))?;
+        let projects = workspace
+            .project_links
+            .iter()
+            .map(|link| {
+                data.get_project(link.project_id)
+                    .ok_or_else(|| anyhow::anyhow!("Project with id {} not found", link.project_id))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(MatrixTarget {
+            name: workspace.name.clone(),
+            projects,
+            project_link_id: None,
+            compiler_key: workspace.compiler_id.clone(),
+        })
+    }
+
+    /// Build every combination of a [`CompileProjectParams::Matrix`] as a
+    /// single job of the build queue.
+    pub(super) async fn compile_matrix(&self) -> Result<CompileResult> {
+        let CompileProjectParams::Matrix {
+            project_id,
+            workspace_id,
+            configurations,
+            platforms,
+            compilers,
+            rebuild,
+            keep_going,
+            jobs,
+            ..
+        } = &self.params
+        else {
+            anyhow::bail!("Not a build matrix.");
+        };
+        let target = self.matrix_target(*project_id, *workspace_id)?;
+        let (available_configurations, available_platforms) = available_targets(&target.projects);
+        let configurations = axis("configuration", configurations, available_configurations)?;
+        let platforms = axis("platform", platforms, available_platforms)?;
+        let compilers = {
+            let configs = COMPILER_CONFIGURATIONS.read().await;
+            let keys = if compilers.is_empty() { std::slice::from_ref(&target.compiler_key) } else { compilers.as_slice() };
+            let mut resolved: Vec<(String, String)> = Vec::new();
+            for key in keys {
+                let Some(configuration) = configs.get(key) else {
+                    let available: Vec<String> = configs.keys().cloned().collect();
+                    anyhow::bail!("Unknown compiler key: \"{key}\". Available keys: {}", available.join(", "));
+                };
+                if !resolved.iter().any(|(k, _)| k == key) {
+                    resolved.push((key.clone(), configuration.product_name.clone()));
+                }
+            }
+            resolved
+        };
+        let keys: Vec<&str> = compilers.iter().map(|(key, _)| key.as_str()).collect();
+        let names: Vec<&str> = compilers.iter().map(|(_, name)| name.as_str()).collect();
+        let banner = CompBanner::new(
+            format!("Build Matrix {}", target.name),
+            format!("{} × {} × {}", configurations.join(", "), platforms.join(", "), keys.join(", ")),
+            names.join(", "),
+            *rebuild,
+        );
+
+        let mut ticket = queue::enqueue(self.job_id, banner.title.clone());
+        let job_id = ticket.id();
+        let jobs_ahead = ticket.jobs_ahead();
+        if jobs_ahead > 0 {
+            CompilerProgress::notify_queued(self.client.as_ref(), job_id, jobs_ahead).await;
+        }
+        if !ticket.wait_turn().await {
+            return Ok(CompileResult {
+                success: false,
+                cancelled: true,
+                code: -1,
+                compiler_name: names.join(", "),
+                projects: Vec::new(),
+                job_id,
+                history_id: None,
+                diagnostics: Vec::new(),
+                matrix: Vec::new(),
+            });
+        }
+        // The queue runs one job at a time, so no other compilation is active.
+        compiler_state::activate();
+        defer! {
+            compiler_state::reset()
+        }
+
+        clear_stale_diagnostics(self.client.as_ref()).await;
+        CompilerProgress::notify_start(self.client.as_ref(), job_id, banner.into_multi_header_vec()).await;
+        let holder = build_lock::BuildLockHolder::current(banner.title.clone());
+        let client = self.client.as_ref();
+        let on_wait = async |other: &build_lock::BuildLockHolder| {
+            CompilerProgress::notify_stdout(client, None, format!("Waiting for the build {other} to finish...")).await;
+        };
+        let mut cells = Vec::new();
+        for (compiler_key, compiler_name) in &compilers {
+            for configuration in &configurations {
+                for platform in &platforms {
+                    cells.push(MatrixCell {
+                        configuration: configuration.clone(),
+                        platform: platform.clone(),
+                        compiler_key: compiler_key.clone(),
+                        compiler_name: compiler_name.clone(),
+                        status: ProjectStatus::Skipped,
+                        code: -1,
+                        duration_ms: 0,
+                        projects: Vec::new(),
+                        history_id: None,
+                    });
+                }
+            }
+        }
+        let mut diagnostics = Vec::new();
+        let result = match build_lock::acquire(&holder, self.lock_policy, on_wait).await {
+            Ok(Some(_lock)) => {
+                for cell in cells.iter_mut() {
+                    if compiler_state::is_cancelled() {
+                        break;
+                    }
+                    self.build_cell(&target, cell, *rebuild, *keep_going, *jobs, &mut diagnostics).await;
+                    if !cell.status.is_success() && !keep_going {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            Ok(None) => Err(anyhow::anyhow!("Compilation cancelled by user.")),
+            Err(e) => Err(e),
+        };
+        let cancelled = compiler_state::is_cancelled();
+        match cells.iter().find(|cell| !cell.status.is_success()) {
+            Some(cell) => {
+                compiler_state::set_success(false);
+                compiler_state::set_code(cell.code);
+            }
+            _ => {
+                compiler_state::set_success(true);
+                compiler_state::set_code(0);
+            }
+        }
+        let compile_result = CompileResult {
+            success: compiler_state::is_success(),
+            cancelled,
+            code: compiler_state::get_code(),
+            compiler_name: names.join(", "),
+            projects: cells.iter().flat_map(|cell| cell.projects.iter().cloned()).collect(),
+            job_id,
+            history_id: None,
+            diagnostics,
+            matrix: cells,
+        };
+        ticket.set_outcome(if cancelled {
+            queue::JobState::Cancelled
+        } else if compile_result.success {
+            queue::JobState::Succeeded
+        } else {
+            queue::JobState::Failed
+        });
+        let mut footer = banner.into_footer_vec(&[]);
+        let bottom = footer.pop();
+        footer.extend(grid_lines(&compile_result.matrix));
+        footer.extend(bottom);
+        CompilerProgress::notify_completed(
+            self.client.as_ref(),
+            job_id,
+            compile_result.success,
+            compile_result.cancelled,
+            compile_result.code,
+            footer,
+            compile_result.projects.clone(),
+        ).await;
+        if !cancelled {
+            result?;
+        }
+        Ok(compile_result)
+    }
+
+    /// Build the projects of `target` with the configuration, platform and
+    /// compiler of `cell` and record the outcome in `cell`; the diagnostics
+    /// of its builds are added to `diagnostics`.
+    async fn build_cell(
+        &self,
+        target: &MatrixTarget<'_>,
+        cell: &mut MatrixCell,
+        rebuild: bool,
+        keep_going: bool,
+        jobs: Option<usize>,
+        diagnostics: &mut Vec<CompileDiagnostic>,
+    ) {
+        let mut projects_data = self.projects_data.clone();
+        for project in projects_data.projects.iter_mut() {
+            if target.projects.iter().any(|p| p.id == project.id) {
+                project.active_configuration = Some(cell.configuration.clone());
+                project.active_platform = Some(cell.platform.clone());
+            }
+        }
+        for workspace in projects_data.workspaces.iter_mut() {
+            workspace.compiler_id = cell.compiler_key.clone();
+        }
+        projects_data.group_project_compiler_id = cell.compiler_key.clone();
+        let event_id = "matrix".to_string();
+        let params = match &self.params {
+            CompileProjectParams::Matrix { project_id: Some(project_id), .. } => CompileProjectParams::Project {
+                project_id: *project_id,
+                project_link_id: target.project_link_id,
+                rebuild,
+                event_id,
+                with_dependencies: false,
+            },
+            CompileProjectParams::Matrix { workspace_id: Some(workspace_id), .. } => CompileProjectParams::AllInWorkspace {
+                workspace_id: *workspace_id,
+                rebuild,
+                event_id,
+                keep_going,
+                jobs,
+            },
+            _ => return,
+        };
+        let compiler = Compiler {
+            client: self.client.clone(),
+            params,
+            projects_data,
+            backend: self.backend.clone(),
+            job_id: None,
+            lock_policy: self.lock_policy,
+        };
+        let started = std::time::Instant::now();
+        let mut parameters = match compiler.parameters().await {
+            Ok(parameters) => parameters,
+            Err(e) => {
+                CompilerProgress::notify_stderr(self.client.as_ref(), None, format!("Failed to build {cell}: {e}")).await;
+                cell.status = ProjectStatus::Failed;
+                return;
+            }
+        };
+        parameters.banner = parameters.banner.with_config_platform(cell.configuration.clone(), cell.platform.clone());
+        // The compilers of a matrix write to the same output files, so an
+        // output newer than its sources may well be another compiler's.
+        parameters.check_up_to_date = false;
+        let header = match parameters.only_one_project {
+            true => parameters.banner.into_header_vec(),
+            false => parameters.banner.into_multi_header_vec(),
+        };
+        for line in header {
+            CompilerProgress::notify_stdout(self.client.as_ref(), None, line).await;
+        }
+        let build_id = history::next_id();
+        let started_at = chrono::Utc::now().timestamp_millis();
+        let result = compiler.do_compile(&parameters, build_id, &mut cell.projects, diagnostics).await;
+        cell.history_id = compiler.record_history(&parameters, build_id, started_at, &cell.projects).await;
+        cell.duration_ms = started.elapsed().as_millis() as u64;
+        (cell.status, cell.code) = match result {
+            _ if compiler_state::is_cancelled() => (ProjectStatus::Cancelled, -1),
+            Ok(()) if compiler_state::is_success() => (ProjectStatus::Succeeded, 0),
+            Ok(()) => (ProjectStatus::Failed, compiler_state::get_code()),
+            Err(e) => {
+                CompilerProgress::notify_stderr(self.client.as_ref(), None, format!("Failed to build {cell}: {e}")).await;
+                (ProjectStatus::Failed, compiler_state::get_code())
+            }
+        };
+        for line in footer_lines(&parameters, &cell.projects) {
+            CompilerProgress::notify_stdout(self.client.as_ref(), None, line).await;
+        }
+    }
+}
+
+/// Footer rows of a build matrix: one row per configuration and platform
+/// with the status of each compiler, then a summary.
+fn grid_lines(cells: &[MatrixCell]) -> Vec<String> {
+    let mut rows: Vec<(&str, &str)> = Vec::new();
+    let mut columns: Vec<&str> = Vec::new();
+    for cell in cells {
+        if !rows.contains(&(&cell.configuration, &cell.platform)) {
+            rows.push((&cell.configuration, &cell.platform));
+        }
+        if !columns.contains(&cell.compiler_key.as_str()) {
+            columns.push(&cell.compiler_key);
+        }
+    }
+    let config_width = rows.iter().map(|(config, _)| config.chars().count()).max().unwrap_or(0).max(6);
+    let platform_width = rows.iter().map(|(_, platform)| platform.chars().count()).max().unwrap_or(0).max(8);
+    let column_width = columns.iter().map(|key| key.chars().count()).max().unwrap_or(0).max(12);
+    let mut lines = vec![String::new()];
+    let mut header = format!("  {:<config_width$} {:<platform_width$}", "Config", "Platform");
+    for key in &columns {
+        header.push_str(&format!(" │ {key:<column_width$}"));
+    }
+    lines.push(header.trim_end().to_string());
+    for (config, platform) in &rows {
+        let mut line = format!("  {config:<config_width$} {platform:<platform_width$}");
+        for key in &columns {
+            let status = cells
+                .iter()
+                .find(|cell| cell.configuration == *config && cell.platform == *platform && cell.compiler_key == *key)
+                .map_or("", |cell| match cell.status {
+                    ProjectStatus::Succeeded | ProjectStatus::UpToDate => "✅ OK",
+                    ProjectStatus::Failed => "❌ FAILED",
+                    ProjectStatus::Cancelled => "⚠️ CANCELLED",
+                    ProjectStatus::Skipped => "⏭️ SKIPPED",
+                });
+            line.push_str(&format!(" │ {status:<column_width$}"));
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    let count = |status: ProjectStatus| cells.iter().filter(|cell| cell.status == status).count();
+    lines.push(format!(
+        "  {} of {} combination(s) succeeded, {} failed, {} skipped.",
+        cells.iter().filter(|cell| cell.status.is_success()).count(),
+        cells.len(),
+        count(ProjectStatus::Failed),
+        count(ProjectStatus::Skipped)
+    ));
+    lines
+}
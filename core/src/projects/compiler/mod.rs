@@ -1,16 +1,29 @@
+pub mod backend;
+pub mod baseline;
+pub mod build_lock;
 pub mod compiler_state;
+pub mod dependencies;
+pub mod history;
+pub mod last_diagnostics;
+pub mod matrix;
+pub mod queue;
+pub mod up_to_date;
+
+pub use backend::*;
+pub use dependencies::*;
 
 use super::*;
 use crate::files::dproj as dproj_cache;
+use crate::files::groupproj::parse_groupproj_dependencies;
 use crate::state::PROJECTS_DATA;
 use crate::{CompileProjectParams, CompilerProgress};
 use anyhow::Result;
-use rust_search::SearchBuilder;
 use scopeguard::defer;
-use std::path::PathBuf;
-use std::process::Stdio;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::io::{AsyncRead, BufReader};
-use tokio::process::Command;
 use tower_lsp::lsp_types::{Diagnostic, Url};
 
 #[derive(Debug, Clone)]
@@ -18,12 +31,89 @@ pub struct CompileResult {
     pub success: bool,
     pub cancelled: bool,
     pub code: i32,
+    /// Product name of the compiler configuration that was used.
+    pub compiler_name: String,
+    /// One entry per project of the compilation, in build order.
+    pub projects: Vec<ProjectOutcome>,
+    /// Build queue job of the compilation (see [`queue`]).
+    pub job_id: queue::JobId,
+    /// Entry of the compilation in the [`history`]; `None` when it did not
+    /// get to build or could not be recorded, and for a build matrix, whose
+    /// combinations are recorded one by one.
+    pub history_id: Option<history::BuildId>,
+    /// Every diagnostic the builds reported, in build order.
+    pub diagnostics: Vec<CompileDiagnostic>,
+    /// One entry per combination of a build matrix, in build order; empty
+    /// for other compilations.
+    pub matrix: Vec<matrix::MatrixCell>,
+}
+
+/// What happened to a single project of a compilation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectStatus {
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// Not attempted because the compilation stopped before it.
+    Skipped,
+    /// Not built because its output is newer than its sources (see
+    /// [`up_to_date`]).
+    #[serde(rename = "up_to_date")]
+    UpToDate,
+}
+
+impl ProjectStatus {
+    /// Whether the project's output is current: built or already up to date.
+    pub fn is_success(self) -> bool {
+        matches!(self, ProjectStatus::Succeeded | ProjectStatus::UpToDate)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectOutcome {
+    pub project_id: usize,
+    pub project_name: String,
+    pub status: ProjectStatus,
+    pub code: i32,
+    pub duration_ms: u64,
+    /// Diagnostics the build of the project reported.
+    #[serde(default)]
+    pub diagnostics: DiagnosticCounts,
+    /// How the build broke the project's [`WarningPolicy`]; such a build
+    /// fails even when the compiler succeeded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy_violations: Vec<String>,
+}
+
+impl ProjectOutcome {
+    fn skipped(project: &Project) -> Self {
+        Self::not_built(project, ProjectStatus::Skipped, -1)
+    }
+
+    fn not_built(project: &Project, status: ProjectStatus, code: i32) -> Self {
+        ProjectOutcome {
+            project_id: project.id,
+            project_name: project.name.clone(),
+            status,
+            code,
+            duration_ms: 0,
+            diagnostics: DiagnosticCounts::default(),
+            policy_violations: Vec::new(),
+        }
+    }
 }
 
 pub struct Compiler {
     client: Option<tower_lsp::Client>,
     params: CompileProjectParams,
     projects_data: ProjectsData,
+    /// Overrides the backend selected by the compiler configuration.
+    backend: Option<Arc<dyn BuildBackend>>,
+    /// Queue the compilation under this job id instead of a new one.
+    job_id: Option<queue::JobId>,
+    /// Whether to wait for a build of another process to finish.
+    lock_policy: build_lock::LockPolicy,
 }
 
 impl Compiler {
@@ -33,6 +123,9 @@ impl Compiler {
             client: Some(client),
             params: params.clone(),
             projects_data: PROJECTS_DATA.read().await.clone(),
+            backend: None,
+            job_id: None,
+            lock_policy: build_lock::LockPolicy::Wait,
         }
     }
 
@@ -44,16 +137,41 @@ impl Compiler {
             client: None,
             params: params.clone(),
             projects_data: PROJECTS_DATA.read().await.clone(),
+            backend: None,
+            job_id: None,
+            lock_policy: build_lock::LockPolicy::Wait,
         }
     }
 
+    /// Build with `backend` instead of the one selected by the compiler
+    /// configuration, e.g. a scripted backend in tests.
+    pub fn with_backend(mut self, backend: Arc<dyn BuildBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Queue the compilation as job `job_id`, reserved with
+    /// [`queue::next_job_id`].
+    pub fn with_job_id(mut self, job_id: queue::JobId) -> Self {
+        self.job_id = Some(job_id);
+        self
+    }
+
+    /// Fail instead of waiting when another process is building.
+    pub fn with_lock_policy(mut self, lock_policy: build_lock::LockPolicy) -> Self {
+        self.lock_policy = lock_policy;
+        self
+    }
+
     async fn get_project_parameters<'a>(
         &'a self,
         project_id: usize,
         project_link_id: Option<usize>,
         rebuild: bool,
+        with_dependencies: bool,
     ) -> Result<CompilationParameters<'a>> {
         let configuration;
+        let mut workspace_id = None;
         let project = self
             .projects_data
             .get_project(project_id)
@@ -61,17 +179,18 @@ impl Compiler {
         if let Some(link_id) = project_link_id {
             if self.projects_data.is_project_link_in_group_project(link_id) {
                 configuration = self.projects_data.group_projects_compiler().await;
-            } else if let Some(workspace_id) = self
+            } else if let Some(id) = self
                 .projects_data
                 .get_workspace_id_containing_project_link(link_id)
             {
                 let workspace =
                     self.projects_data
-                        .get_workspace(workspace_id)
+                        .get_workspace(id)
                         .ok_or_else(|| {
-                            anyhow::anyhow!("Workspace with id {} not found", workspace_id)
+                            anyhow::anyhow!("Workspace with id {} not found", id)
                         })?;
                 configuration = workspace.compiler().await;
+                workspace_id = Some(workspace.id);
             } else {
                 anyhow::bail!(
                     "No workspace or group project contains project link with id {}",
@@ -79,7 +198,7 @@ impl Compiler {
                 );
             }
         } else {
-            let workspace_id = self
+            let id = self
                 .projects_data
                 .workspaces
                 .iter()
@@ -102,10 +221,24 @@ impl Compiler {
                 })?;
             configuration = self
                 .projects_data
-                .get_workspace(workspace_id)
-                .ok_or_else(|| anyhow::anyhow!("Workspace with id {} not found", workspace_id))?
+                .get_workspace(id)
+                .ok_or_else(|| anyhow::anyhow!("Workspace with id {} not found", id))?
                 .compiler().await;
+            workspace_id = Some(id);
+        }
+        let mut projects = Vec::new();
+        if with_dependencies
+            && let Some(workspace) = workspace_id.and_then(|id| self.projects_data.get_workspace(id))
+        {
+            for dependency in workspace.transitive_dependencies(project_id) {
+                projects.push(
+                    self.projects_data
+                        .get_project(dependency)
+                        .ok_or_else(|| anyhow::anyhow!("Project with id {} not found", dependency))?,
+                );
+            }
         }
+        projects.push(project);
         let target = project.get_project_file()?;
         let compiler_name = configuration.product_name.clone();
         // Resolve config/platform for the banner
@@ -121,10 +254,14 @@ impl Compiler {
              project.active_platform.clone().unwrap_or_else(|| "Win32".to_string()))
         };
         return Ok(CompilationParameters {
-            projects: vec![project],
+            only_one_project: projects.len() == 1,
+            check_up_to_date: !rebuild && projects.len() > 1,
+            projects,
             configuration,
             rebuild,
-            only_one_project: true,
+            workspace_id,
+            keep_going: false,
+            jobs: 1,
             banner: CompBanner::new(
                 format!("Compiling Project {}", project.name),
                 target.to_string_lossy().to_string(),
@@ -159,6 +296,10 @@ impl Compiler {
             configuration,
             rebuild,
             only_one_project: false,
+            check_up_to_date: !rebuild,
+            workspace_id: Some(workspace_id),
+            keep_going: self.params.keep_going(),
+            jobs: self.params.jobs(),
             banner: CompBanner::new(
                 format!("Compiling Workspace {}", workspace.name),
                 format!("Projects of Workspace '{}'", workspace.name),
@@ -192,6 +333,10 @@ impl Compiler {
             configuration,
             rebuild,
             only_one_project: false,
+            check_up_to_date: !rebuild,
+            workspace_id: None,
+            keep_going: self.params.keep_going(),
+            jobs: self.params.jobs(),
             banner: CompBanner::new(
                 format!("Compiling Group Project {}", group_project.name),
                 format!("Projects of Group Project '{}'", group_project.name),
@@ -207,6 +352,7 @@ impl Compiler {
         rebuild: bool,
     ) -> Result<CompilationParameters<'a>> {
         let (projects, configuration, banner);
+        let mut link_workspace_id = None;
         if let Some(workspace_id) = self
             .projects_data
             .get_workspace_id_containing_project_link(project_link_id)
@@ -227,6 +373,7 @@ impl Compiler {
                     })
                     .collect::<Result<Vec<_>>>()?;
                 configuration = workspace.compiler().await;
+                link_workspace_id = Some(workspace_id);
                 let project_name = projects
                     .first()
                     .map(|p| p.name.clone())
@@ -291,42 +438,78 @@ impl Compiler {
             configuration,
             rebuild,
             only_one_project: false,
+            check_up_to_date: !rebuild,
+            workspace_id: link_workspace_id,
+            keep_going: self.params.keep_going(),
+            jobs: self.params.jobs(),
             banner,
         });
     }
 
-    pub async fn compile(&self) -> Result<CompileResult> {
-        if !compiler_state::activate() {
-            anyhow::bail!(
-                "Another compilation is already in progress. Please wait until it finishes."
-            );
-        }
-        defer! {
-            compiler_state::reset()
-        }
-
-        let parameters = match self.params {
+    /// What to build for `self.params`; a build matrix has no single set of
+    /// parameters (see [`matrix`]).
+    async fn parameters(&self) -> Result<CompilationParameters<'_>> {
+        Ok(match self.params {
             CompileProjectParams::Project {
                 project_id,
                 project_link_id,
                 rebuild,
-                event_id: _
-            } => self.get_project_parameters(project_id, project_link_id, rebuild).await?,
+                with_dependencies,
+                ..
+            } => self.get_project_parameters(project_id, project_link_id, rebuild, with_dependencies).await?,
             CompileProjectParams::AllInWorkspace {
                 workspace_id,
                 rebuild,
-                event_id: _
+                ..
             } => self.get_all_workspace_parameters(workspace_id, rebuild).await?,
             CompileProjectParams::AllInGroupProject {
                 rebuild,
-                event_id: _
+                ..
             } => self.get_all_group_project_parameters(rebuild).await?,
             CompileProjectParams::FromLink {
                 project_link_id,
                 rebuild,
-                event_id: _,
+                ..
             } => self.get_from_link_parameters(project_link_id, rebuild).await?,
-        };
+            CompileProjectParams::Matrix { .. } => anyhow::bail!("A build matrix is built one combination at a time."),
+        })
+    }
+
+    /// Queue the compilation and build it once the jobs queued before it
+    /// have finished.
+    pub async fn compile(&self) -> Result<CompileResult> {
+        if let CompileProjectParams::Matrix { .. } = self.params {
+            return self.compile_matrix().await;
+        }
+        let parameters = self.parameters().await?;
+
+        // Declared before the guard below, so the job only finishes (and
+        // lets the next one start) after the compiler state was reset.
+        let mut ticket = queue::enqueue(self.job_id, parameters.banner.title.clone());
+        let job_id = ticket.id();
+        let jobs_ahead = ticket.jobs_ahead();
+        if jobs_ahead > 0 {
+            CompilerProgress::notify_queued(self.client.as_ref(), job_id, jobs_ahead).await;
+        }
+        if !ticket.wait_turn().await {
+            return Ok(CompileResult {
+                success: false,
+                cancelled: true,
+                code: -1,
+                compiler_name: parameters.configuration.product_name.clone(),
+                projects: Vec::new(),
+                job_id,
+                history_id: None,
+                diagnostics: Vec::new(),
+                matrix: Vec::new(),
+            });
+        }
+        // The queue runs one job at a time, so no other compilation is active.
+        compiler_state::activate();
+        defer! {
+            compiler_state::reset()
+        }
+
         clear_stale_diagnostics(self.client.as_ref()).await;
         // Actual compilation process
         let start_lines = if parameters.only_one_project {
@@ -336,9 +519,30 @@ impl Compiler {
         };
         CompilerProgress::notify_start(
             self.client.as_ref(),
+            job_id,
             start_lines
         ).await;
-        let result = self.do_compile(&parameters).await;
+        let mut outcomes = Vec::with_capacity(parameters.projects.len());
+        let mut diagnostics = Vec::new();
+        let holder = build_lock::BuildLockHolder::current(parameters.banner.title.clone());
+        let client = self.client.as_ref();
+        let on_wait = async |other: &build_lock::BuildLockHolder| {
+            CompilerProgress::notify_stdout(client, None, format!("Waiting for the build {other} to finish...")).await;
+        };
+        let mut history_id = None;
+        let result = match build_lock::acquire(&holder, self.lock_policy, on_wait).await {
+            Ok(Some(_lock)) => {
+                // Holding the lock, so no other process takes the same id or
+                // writes the history at the same time.
+                let build_id = history::next_id();
+                let started_at = chrono::Utc::now().timestamp_millis();
+                let result = self.do_compile(&parameters, build_id, &mut outcomes, &mut diagnostics).await;
+                history_id = self.record_history(&parameters, build_id, started_at, &outcomes).await;
+                result
+            }
+            Ok(None) => Err(anyhow::anyhow!("Compilation cancelled by user.")),
+            Err(e) => Err(e),
+        };
         let cancelled = compiler_state::is_cancelled();
         // Treat cancellation as a non-error outcome so no upstream error is logged
         let result = if cancelled { Ok(()) } else { result };
@@ -346,170 +550,290 @@ impl Compiler {
             success: compiler_state::is_success(),
             cancelled,
             code: compiler_state::get_code(),
+            compiler_name: parameters.configuration.product_name.clone(),
+            projects: outcomes,
+            job_id,
+            history_id,
+            diagnostics,
+            matrix: Vec::new(),
         };
+        ticket.set_outcome(if cancelled {
+            queue::JobState::Cancelled
+        } else if compile_result.success {
+            queue::JobState::Succeeded
+        } else {
+            queue::JobState::Failed
+        });
         CompilerProgress::notify_completed(
             self.client.as_ref(),
+            job_id,
             compile_result.success,
             compile_result.cancelled,
             compile_result.code,
-            parameters.banner.into_footer_vec(),
+            footer_lines(&parameters, &compile_result.projects),
+            compile_result.projects.clone(),
         ).await;
         result?;
         return Ok(compile_result);
     }
 
-    async fn do_compile(&self, parameters: &CompilationParameters<'_>) -> Result<()> {
-        for project in &parameters.projects {
-            if compiler_state::is_cancelled() {
-                return Err(anyhow::anyhow!("Compilation cancelled by user."));
-            }
-
-            // Resolve effective configuration/platform for this project early,
-            // so the banner can display it and MSBuild receives the right args.
-            let (eff_config, eff_platform) = if let Some(dproj_path) = &project.dproj {
-                if let Ok(dproj_obj) = dproj_cache::get_or_load(project.id, &PathBuf::from(dproj_path)) {
-                    project.effective_config_platform(&dproj_obj)
-                } else {
-                    (project.active_configuration.clone().unwrap_or_else(|| "Debug".to_string()),
-                     project.active_platform.clone().unwrap_or_else(|| "Win32".to_string()))
-                }
-            } else {
-                (project.active_configuration.clone().unwrap_or_else(|| "Debug".to_string()),
-                 project.active_platform.clone().unwrap_or_else(|| "Win32".to_string()))
-            };
-
-            if !parameters.only_one_project {
-                CompilerProgress::notify_single_project_started(
+    /// Add the finished compilation to the build history; a failure to do
+    /// so is reported but does not fail the compilation.
+    async fn record_history(
+        &self,
+        parameters: &CompilationParameters<'_>,
+        build_id: history::BuildId,
+        started_at: i64,
+        outcomes: &[ProjectOutcome],
+    ) -> Option<history::BuildId> {
+        let compiler_key = compiler_key(&parameters.configuration).await;
+        let projects = outcomes
+            .iter()
+            .map(|outcome| {
+                let (config, platform) = parameters
+                    .projects
+                    .iter()
+                    .find(|project| project.id == outcome.project_id)
+                    .map(|project| effective_target(project))
+                    .unwrap_or_default();
+                let log = history::log_path(build_id, outcome.project_id);
+                let log = log.exists().then(|| log.to_string_lossy().to_string());
+                history::ProjectRecord::new(outcome, config, platform, log)
+            })
+            .collect();
+        let record = history::BuildRecord {
+            id: build_id,
+            description: parameters.banner.title.clone(),
+            started_at,
+            finished_at: chrono::Utc::now().timestamp_millis(),
+            compiler_key,
+            compiler_name: parameters.configuration.product_name.clone(),
+            rebuild: parameters.rebuild,
+            success: compiler_state::is_success() && !compiler_state::is_cancelled(),
+            cancelled: compiler_state::is_cancelled(),
+            code: compiler_state::get_code(),
+            program: queue::program_name(),
+            projects,
+        };
+        match history::record(record) {
+            Ok(()) => Some(build_id),
+            Err(e) => {
+                CompilerProgress::notify_stderr(
                     self.client.as_ref(),
-                    project.id,
-                    CompBanner::new(
-                        format!("Compiling Project: {}", project.name),
-                        project.get_project_file()?.to_string_lossy().to_string(),
-                        parameters.configuration.product_name.clone(),
-                        parameters.rebuild,
-                    ).with_config_platform(eff_config.clone(), eff_platform.clone())
-                    .into_project_header_vec()
+                    None,
+                    format!("Failed to record the build in the build history: {}", e),
                 ).await;
+                None
             }
+        }
+    }
 
-            let rsvars_path = PathBuf::from(&parameters.configuration.installation_path)
-                .join("bin")
-                .join("rsvars.bat");
-            if !rsvars_path.exists() {
-                anyhow::bail!(
-                    "Cannot find rsvars.bat at path: {}",
-                    rsvars_path.to_string_lossy()
-                );
-            }
-            let envs = dproj_rs::rsvars::parse_rsvars_file(&rsvars_path)
-                .map_err(|e| anyhow::anyhow!("Failed to parse rsvars.bat: {}", e))?;
-            let project_file = project.get_project_file()?;
-            let args = parameters.configuration.build_arguments.join(" ");
-            let target = if parameters.rebuild { "Build" } else { "Make" };
-
-            let msbuild_path = find_msbuild()?;
-            let mut child_process = Command::new(msbuild_path)
-                .arg(project_file)
-                .arg(format!("/t:Clean,{}", target))
-                .args(args.split_whitespace())
-                .arg(format!("/p:Config={}", eff_config))
-                .arg(format!("/p:Configuration={}", eff_config))
-                .arg(format!("/p:Platform={}", eff_platform))
-                .envs(envs)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .kill_on_drop(true)
-                .spawn()?;
-
-            // Capture the PID before taking stdio handles so we can kill the
-            // entire process tree on cancellation (taskkill /F /T kills MSBuild
-            // AND every compiler child process it spawned, e.g. dcc32.exe).
-            let child_pid = child_process.id();
-
-            let stdout = child_process.stdout.take()
-                .ok_or_else(|| anyhow::anyhow!("Unable to access child process STDOUT"))?;
-            let stderr = child_process.stderr.take()
-                .ok_or_else(|| anyhow::anyhow!("Unable to access child process STDERR"))?;
-
-            let out_reader = BufReader::new(stdout);
-            let err_reader = BufReader::new(stderr);
-
-            let project_dir = project.get_project_file()?
-                .parent()
-                .map(|p| p.to_path_buf())
-                .unwrap_or_else(|| PathBuf::from(&project.directory));
-
-            let stdout_task = tokio::spawn(process_output_lines(
-                self.client.clone(), // Option<tower_lsp::Client>
-                out_reader,
-                parameters.configuration.product_name.clone(),
-                OutputKind::Stdout,
-                project_dir.clone(),
-            ));
-
-            let stderr_task = tokio::spawn(process_output_lines(
-                self.client.clone(), // Option<tower_lsp::Client>
-                err_reader,
-                parameters.configuration.product_name.clone(),
-                OutputKind::Stderr,
-                project_dir,
-            ));
-
-            let cancel_signal = async {
-                loop {
-                    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
-                    if compiler_state::is_cancelled() {
-                        break;
+    /// Keep the diagnostics of a finished project build as the last ones of
+    /// the project; a failure to do so is reported but changes nothing else.
+    async fn store_diagnostics(&self, project: &Project, build_id: history::BuildId, diagnostics: Vec<CompileDiagnostic>) {
+        let stored = last_diagnostics::store(last_diagnostics::ProjectDiagnostics {
+            project_id: project.id,
+            project_name: project.name.clone(),
+            build_id,
+            finished_at: chrono::Utc::now().timestamp_millis(),
+            diagnostics,
+        });
+        if let Err(e) = stored {
+            CompilerProgress::notify_stderr(
+                self.client.as_ref(),
+                Some(project.id),
+                format!("Failed to save the diagnostics of project \"{}\": {}", project.name, e),
+            ).await;
+        }
+    }
+
+    /// Build `parameters.projects`, dependencies first and up to
+    /// `parameters.jobs` at a time, recording an outcome for each in build
+    /// order.  Without `keep_going` the first failing project ends the
+    /// compilation and the projects not started yet are recorded as
+    /// skipped; with it only the projects depending on a failed one are.
+    /// With `check_up_to_date`, a project is not built when it is up to date
+    /// and none of its dependencies was built.
+    /// The output of each project is saved to its log in build `build_id`
+    /// and its diagnostics are added to `reported`.
+    async fn do_compile(
+        &self,
+        parameters: &CompilationParameters<'_>,
+        build_id: history::BuildId,
+        outcomes: &mut Vec<ProjectOutcome>,
+        reported: &mut Vec<CompileDiagnostic>,
+    ) -> Result<()> {
+        let projects = &parameters.projects;
+        // Resolve effective configuration/platform for every project early,
+        // so the banners can display it and the backend receives the right
+        // arguments.
+        let targets: Vec<(String, String)> = projects.iter().map(|p| effective_target(p)).collect();
+        let (order, graph) = if parameters.only_one_project {
+            (vec![0], BuildGraph::new(1))
+        } else {
+            self.build_order(parameters, &targets).await
+        };
+        let mut position = vec![0; projects.len()];
+        for (pos, &index) in order.iter().enumerate() {
+            position[index] = pos;
+        }
+        // Dependencies that are built before a project; in a cycle the
+        // project listed first is built first.
+        let (graph, position) = (&graph, &position);
+        let dependencies = move |index: usize| {
+            graph
+                .dependencies(index)
+                .iter()
+                .copied()
+                .filter(move |&dependency| position[dependency] < position[index])
+        };
+
+        let backend = match &self.backend {
+            Some(backend) => backend.clone(),
+            _ => backend_for(&parameters.configuration),
+        };
+        let check_up_to_date = parameters.check_up_to_date;
+        let (env, last_built, compiler_key) = match check_up_to_date {
+            true => (
+                build_env(&parameters.configuration).ok().flatten().unwrap_or_default(),
+                history::last_built(),
+                compiler_key(&parameters.configuration).await,
+            ),
+            false => (HashMap::new(), HashMap::new(), String::new()),
+        };
+        let mut results: Vec<Option<ProjectOutcome>> = vec![None; projects.len()];
+        let mut pending: Vec<usize> = order.clone();
+        let mut running = tokio::task::JoinSet::new();
+        let mut running_projects = HashMap::new();
+        let mut stopped = false;
+        let mut error = None;
+        loop {
+            while !stopped && !compiler_state::is_cancelled() && running.len() < parameters.jobs {
+                // A project whose dependency failed cannot be built.
+                for index in pending.clone() {
+                    let blocked = dependencies(index).any(|dependency| {
+                        results[dependency].as_ref().is_some_and(|o| !o.status.is_success())
+                    });
+                    if blocked {
+                        pending.retain(|&p| p != index);
+                        results[index] = Some(ProjectOutcome::skipped(projects[index]));
                     }
                 }
-            };
+                let ready = pending.iter().position(|&index| {
+                    dependencies(index).all(|dependency| {
+                        results[dependency].as_ref().is_some_and(|o| o.status.is_success())
+                    })
+                });
+                let Some(ready) = ready else { break };
+                let index = pending.remove(ready);
+                let project = projects[index];
+                let (config, platform) = targets[index].clone();
+                let dependencies_up_to_date = dependencies(index).all(|dependency| {
+                    results[dependency].as_ref().is_some_and(|o| o.status == ProjectStatus::UpToDate)
+                });
+                // The output may have been written by another compiler or for
+                // another target.
+                let built_alike = last_built.get(&project.id).is_some_and(|built| {
+                    built.compiler_key == compiler_key && built.config == config && built.platform == platform
+                });
+                if check_up_to_date && dependencies_up_to_date && built_alike {
+                    let (project_clone, env) = (project.clone(), env.clone());
+                    let (config, platform) = (config.clone(), platform.clone());
+                    let current = tokio::task::spawn_blocking(move || {
+                        up_to_date::is_up_to_date(&project_clone, &config, &platform, env)
+                    }).await.unwrap_or(false);
+                    if current {
+                        results[index] = Some(ProjectOutcome::not_built(project, ProjectStatus::UpToDate, 0));
+                        CompilerProgress::notify_single_project_completed(
+                            self.client.as_ref(),
+                            project.id,
+                            true,
+                            false,
+                            0,
+                            project_banner(parameters, project, &targets[index])?.into_project_footer_vec(ProjectStatus::UpToDate, &[]),
+                        ).await;
+                        continue;
+                    }
+                }
+                if !parameters.only_one_project {
+                    CompilerProgress::notify_single_project_started(
+                        self.client.as_ref(),
+                        project.id,
+                        project_banner(parameters, project, &targets[index])?.into_project_header_vec(),
+                    ).await;
+                }
+                let job = BuildJob {
+                    client: self.client.clone(),
+                    backend: backend.clone(),
+                    project: project.clone(),
+                    configuration: parameters.configuration.clone(),
+                    config,
+                    platform,
+                    rebuild: parameters.rebuild,
+                    policy: self.warning_policy(parameters, project),
+                    log: history::log_path(build_id, project.id),
+                };
+                let handle = running.spawn(job.run());
+                running_projects.insert(handle.id(), (index, std::time::Instant::now()));
+            }
 
-            let result = tokio::select! {
-                status = child_process.wait() => {
-                    let status = status?;
-                    stdout_task.await?;
-                    stderr_task.await?;
-                    compiler_state::set_success(status.success());
-                    compiler_state::set_code(status.code().unwrap_or(-1));
-                    Ok(())
+            let Some(joined) = running.join_next_with_id().await else { break };
+            let (task_id, result) = match joined {
+                Ok((task_id, result)) => (task_id, result),
+                Err(e) => (e.id(), Err(anyhow::anyhow!("Build task failed: {}", e))),
+            };
+            let Some((index, started)) = running_projects.remove(&task_id) else { continue };
+            let project = projects[index];
+            let cancelled = compiler_state::is_cancelled();
+            let mut diagnostics = DiagnosticCounts::default();
+            let mut policy_violations = Vec::new();
+            if let Ok((_, parsed)) = &result {
+                parsed.iter().for_each(|diagnostic| diagnostics.add(diagnostic.severity));
+                if !cancelled {
+                    policy_violations = self.warning_policy(parameters, project).violations(parsed);
+                    self.store_diagnostics(project, build_id, parsed.clone()).await;
                 }
-                _ = cancel_signal => {
-                    // Kill the whole process tree so that child processes spawned
-                    // by MSBuild (dcc32.exe, dcc64.exe, …) are also terminated.
-                    // Without this, those processes keep file locks and the next
-                    // compilation attempt on the same project fails immediately.
-                    if let Some(pid) = child_pid {
-                        let _ = std::process::Command::new("taskkill")
-                            .args(["/F", "/T", "/PID", &pid.to_string()])
-                            .output();
+                reported.extend(parsed.iter().cloned());
+            }
+            let (status, code) = match &result {
+                _ if cancelled => (ProjectStatus::Cancelled, -1),
+                Ok((0, _)) if !policy_violations.is_empty() => (ProjectStatus::Failed, 0),
+                Ok((0, _)) => (ProjectStatus::Succeeded, 0),
+                Ok((code, _)) => (ProjectStatus::Failed, *code),
+                Err(e) => {
+                    // The project could not be built at all, e.g. a missing
+                    // compiler; report it like a failed build.
+                    if !parameters.only_one_project {
+                        CompilerProgress::notify_stderr(
+                            self.client.as_ref(),
+                            Some(project.id),
+                            format!("Failed to compile project \"{}\": {}", project.name, e),
+                        ).await;
                     }
-                    // Fallback: also ask Tokio to kill the root process handle.
-                    let _ = child_process.kill().await;
-                    stdout_task.abort();
-                    stderr_task.abort();
-                    compiler_state::set_success(false);
-                    compiler_state::set_code(-1);
-                    Err(anyhow::anyhow!("Compilation cancelled by user."))
+                    (ProjectStatus::Failed, -1)
                 }
             };
+            results[index] = Some(ProjectOutcome {
+                project_id: project.id,
+                project_name: project.name.clone(),
+                status,
+                code,
+                duration_ms: started.elapsed().as_millis() as u64,
+                diagnostics,
+                policy_violations: policy_violations.clone(),
+            });
 
             if !parameters.only_one_project {
                 CompilerProgress::notify_single_project_completed(
                     self.client.as_ref(),
                     project.id,
-                    compiler_state::is_success(),
-                    compiler_state::is_cancelled(),
-                    compiler_state::get_code(),
-                    CompBanner::new(
-                        format!("Compiling Project: {}", project.name),
-                        project.get_project_file()?.to_string_lossy().to_string(),
-                        parameters.configuration.product_name.clone(),
-                        parameters.rebuild,
-                    ).with_config_platform(eff_config.clone(), eff_platform.clone())
-                    .into_project_footer_vec()
+                    status == ProjectStatus::Succeeded,
+                    cancelled,
+                    code,
+                    project_banner(parameters, project, &targets[index])?.into_project_footer_vec(status, &policy_violations),
                 ).await
             }
-            if compiler_state::is_success() {
+            if status == ProjectStatus::Succeeded {
                 let exe_missing = project.exe.as_deref()
                     .map_or(true, |p| p.is_empty() || !PathBuf::from(p).exists());
                 if exe_missing {
@@ -520,16 +844,330 @@ impl Compiler {
                         let _ = projects_data.save().await;
                     }
                 }
+            } else if !parameters.keep_going || cancelled {
+                stopped = true;
+                // A failed build is a result, not an error; only a project
+                // that could not be built at all ends a compilation with an
+                // error.
+                if let Err(e) = result
+                    && error.is_none()
+                {
+                    error = Some(e);
+                }
+            }
+        }
+
+        for index in pending {
+            results[index] = Some(ProjectOutcome::skipped(projects[index]));
+        }
+        outcomes.extend(order.iter().filter_map(|&index| results[index].take()));
+        match outcomes.iter().find(|o| !o.status.is_success()) {
+            Some(outcome) => {
+                compiler_state::set_success(false);
+                compiler_state::set_code(outcome.code);
+            }
+            _ => {
+                compiler_state::set_success(true);
+                compiler_state::set_code(0);
+            }
+        }
+        if compiler_state::is_cancelled() {
+            return Err(anyhow::anyhow!("Compilation cancelled by user."));
+        }
+        match error {
+            Some(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Warning policy `project` is built with: its own on top of the one of
+    /// the workspace it is compiled from.
+    fn warning_policy(&self, parameters: &CompilationParameters<'_>, project: &Project) -> WarningPolicy {
+        self.projects_data.warning_policy(parameters.workspace_id, project)
+    }
+
+    /// Order of `parameters.projects` for a multi-project compile and the
+    /// dependencies between them, declared by the workspace or group project
+    /// or inferred from the `.dproj` files.
+    async fn build_order(
+        &self,
+        parameters: &CompilationParameters<'_>,
+        targets: &[(String, String)],
+    ) -> (Vec<usize>, BuildGraph) {
+        let projects = &parameters.projects;
+        let env = build_env(&parameters.configuration).ok().flatten().unwrap_or_default();
+        let references: Vec<Option<ProjectReferences>> = projects
+            .iter()
+            .zip(targets)
+            .map(|(project, (config, platform))| ProjectReferences::load(project, config, platform, env.clone()))
+            .collect();
+        let mut graph = BuildGraph::infer(&references);
+
+        if let Some(workspace) = parameters.workspace_id.and_then(|id| self.projects_data.get_workspace(id)) {
+            let index_of = |project_id: usize| projects.iter().position(|project| project.id == project_id);
+            for dependency in &workspace.dependencies {
+                if let (Some(project), Some(depends_on)) = (index_of(dependency.project_id), index_of(dependency.depends_on)) {
+                    graph.add_dependency(project, depends_on);
+                }
+            }
+        }
+
+        if let Some(group_project) = &self.projects_data.group_project
+            && let Ok(declared) = parse_groupproj_dependencies(PathBuf::from(&group_project.path))
+        {
+            let base = Path::new("");
+            let index_of = |path: &Path| {
+                let key = path_key(base, &path.to_string_lossy());
+                projects.iter().position(|project| {
+                    project.dproj.as_deref().is_some_and(|dproj| path_key(base, dproj) == key)
+                })
+            };
+            for (project, depends_on) in &declared {
+                let Some(project) = index_of(project) else { continue };
+                for dependency in depends_on {
+                    if let Some(dependency) = index_of(dependency) {
+                        graph.add_dependency(project, dependency);
+                    }
+                }
+            }
+        }
+
+        let cyclic = graph.cyclic();
+        if !cyclic.is_empty() {
+            let names: Vec<&str> = cyclic.iter().map(|&index| projects[index].name.as_str()).collect();
+            CompilerProgress::notify_stderr(
+                self.client.as_ref(),
+                None,
+                format!(
+                    "Dependency cycle between projects {}; they are built in list order.",
+                    names.join(", ")
+                ),
+            ).await;
+        }
+        (graph.order(), graph)
+    }
+}
+
+/// Key of `configuration` among the compiler configurations; empty when it is
+/// not one of them.
+async fn compiler_key(configuration: &CompilerConfiguration) -> String {
+    COMPILER_CONFIGURATIONS
+        .read()
+        .await
+        .iter()
+        .find(|(_, candidate)| *candidate == configuration)
+        .map(|(key, _)| key.clone())
+        .unwrap_or_default()
+}
+
+/// Effective (configuration, platform) of `project`: its own overrides,
+/// then the defaults of its `.dproj`.
+fn effective_target(project: &Project) -> (String, String) {
+    if let Some(dproj_path) = &project.dproj
+        && let Ok(dproj_obj) = dproj_cache::get_or_load(project.id, &PathBuf::from(dproj_path))
+    {
+        return project.effective_config_platform(&dproj_obj);
+    }
+    (project.active_configuration.clone().unwrap_or_else(|| "Debug".to_string()),
+     project.active_platform.clone().unwrap_or_else(|| "Win32".to_string()))
+}
+
+/// Banner of a single project within a multi-project compile.
+fn project_banner(
+    parameters: &CompilationParameters<'_>,
+    project: &Project,
+    (config, platform): &(String, String),
+) -> Result<CompBanner> {
+    Ok(CompBanner::new(
+        format!("Compiling Project: {}", project.name),
+        project.get_project_file()?.to_string_lossy().to_string(),
+        parameters.configuration.product_name.clone(),
+        parameters.rebuild,
+    ).with_config_platform(config.clone(), platform.clone()))
+}
+
+/// The build of one project, run on its own task so that independent
+/// projects can be built at the same time.
+struct BuildJob {
+    client: Option<tower_lsp::Client>,
+    backend: Arc<dyn BuildBackend>,
+    project: Project,
+    configuration: CompilerConfiguration,
+    config: String,
+    platform: String,
+    rebuild: bool,
+    policy: WarningPolicy,
+    /// File the raw output of the build is saved to.
+    log: PathBuf,
+}
+
+impl BuildJob {
+    /// Start the backend and wait for it, streaming its output.  Returns the
+    /// exit code of the build and the diagnostics it reported.
+    async fn run(self) -> Result<(i32, Vec<CompileDiagnostic>)> {
+        let project = &self.project;
+        let request = BuildRequest {
+            project,
+            project_file: project.get_project_file()?,
+            configuration: &self.configuration,
+            config: self.config.clone(),
+            platform: self.platform.clone(),
+            rebuild: self.rebuild,
+        };
+        let BuildProcess { stdout, stderr, mut handle } = self.backend.start(&request).await?;
+
+        let out_reader = BufReader::new(stdout);
+        let err_reader = BufReader::new(stderr);
+
+        let project_file = project.get_project_file()?;
+        let project_dir = project_file
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from(&project.directory));
+
+        // A build without a log is still a build.
+        let log = self.log.parent()
+            .and_then(|dir| std::fs::create_dir_all(dir).ok())
+            .and_then(|_| std::fs::File::create(&self.log).ok())
+            .map(|file| Arc::new(std::sync::Mutex::new(file)));
+
+        let context = OutputContext {
+            compiler_name: self.configuration.product_name.clone(),
+            language: self.configuration.output_language,
+            project_id: project.id,
+            project_dir,
+            project_file: Some(project_file),
+            policy: self.policy.clone(),
+        };
+
+        let stdout_task = tokio::spawn(process_output_lines(
+            self.client.clone(), // Option<tower_lsp::Client>
+            out_reader,
+            OutputKind::Stdout,
+            context.clone(),
+            log.clone(),
+        ));
+
+        let stderr_task = tokio::spawn(process_output_lines(
+            self.client.clone(), // Option<tower_lsp::Client>
+            err_reader,
+            OutputKind::Stderr,
+            context,
+            log,
+        ));
+
+        let cancel_signal = async {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+                if compiler_state::is_cancelled() {
+                    break;
+                }
+            }
+        };
+
+        tokio::select! {
+            code = handle.wait() => {
+                let code = code?;
+                let mut diagnostics = stdout_task.await?;
+                diagnostics.extend(stderr_task.await?);
+                Ok((code, diagnostics))
+            }
+            _ = cancel_signal => {
+                handle.kill().await;
+                stdout_task.abort();
+                stderr_task.abort();
+                Err(anyhow::anyhow!("Compilation cancelled by user."))
             }
-            result?;
         }
-        return Ok(());
     }
 }
 
 enum OutputKind {
     Stdout,
     Stderr,
+    /// A saved build log parsed offline: nothing is sent anywhere.
+    Log,
+}
+
+/// What the output of a project's build is parsed against.
+#[derive(Clone)]
+struct OutputContext {
+    compiler_name: String,
+    language: OutputLanguage,
+    project_id: usize,
+    /// Relative file names are resolved against it.
+    project_dir: PathBuf,
+    /// Diagnostics without a position are attached to it.
+    project_file: Option<PathBuf>,
+    /// Applied to the diagnostics before they are reported.
+    policy: WarningPolicy,
+}
+
+impl OutputContext {
+    /// Resolve a relative file name against the project directory, if the
+    /// file exists there.
+    fn resolve(&self, file: &mut String) {
+        if file.is_empty() || !Path::new(file.as_str()).is_relative() {
+            return;
+        }
+        let resolved = self.project_dir.join(file.as_str());
+        if resolved.exists() {
+            *file = resolved.to_string_lossy().to_string();
+        }
+    }
+}
+
+/// Where the lines and diagnostics of a build's output go: the progress
+/// broadcast, the client's Problems panel and the returned diagnostics.
+struct OutputSink {
+    client: Option<tower_lsp::Client>,
+    kind: OutputKind,
+    project_id: usize,
+    parsed: Vec<CompileDiagnostic>,
+    /// Diagnostics of `last_file` not published yet.
+    diagnostics: Vec<Diagnostic>,
+    last_file: String,
+}
+
+impl OutputSink {
+    async fn line(&self, line: String) {
+        match self.kind {
+            OutputKind::Stdout => CompilerProgress::notify_stdout(self.client.as_ref(), Some(self.project_id), line).await,
+            OutputKind::Stderr => CompilerProgress::notify_stderr(self.client.as_ref(), Some(self.project_id), line).await,
+            OutputKind::Log => {}
+        }
+    }
+
+    /// Report a diagnostic, followed by the continuation lines it took its
+    /// related locations from.
+    async fn diagnostic(&mut self, diagnostic: CompilerLineDiagnostic, continuation: Vec<String>) {
+        self.parsed.push(diagnostic.to_compile_diagnostic(self.project_id));
+        if matches!(self.kind, OutputKind::Log) {
+            return;
+        }
+        if self.last_file != diagnostic.file && !self.diagnostics.is_empty() {
+            self.publish().await;
+        }
+        self.last_file = diagnostic.file.clone();
+        self.line(format!("{}", &diagnostic)).await;
+        for line in continuation {
+            self.line(line).await;
+        }
+        self.diagnostics.push(diagnostic.into());
+    }
+
+    async fn publish(&mut self) {
+        compiler_state::track_diagnosed_file(self.last_file.clone());
+        publish_diagnostics(self.client.as_ref(), &self.last_file, &self.diagnostics).await;
+        self.diagnostics.clear();
+    }
+
+    async fn finish(mut self) -> Vec<CompileDiagnostic> {
+        if !self.diagnostics.is_empty() {
+            self.publish().await;
+        }
+        self.parsed
+    }
 }
 
 lazy_static::lazy_static! {
@@ -542,16 +1180,32 @@ lazy_static::lazy_static! {
 async fn process_output_lines<R: AsyncRead + Unpin + Send>(
     client: Option<tower_lsp::Client>,
     mut reader: BufReader<R>,
-    compiler_name: String,
     kind: OutputKind,
-    project_dir: PathBuf,
-) {
+    context: OutputContext,
+    log: Option<Arc<std::sync::Mutex<std::fs::File>>>,
+) -> Vec<CompileDiagnostic> {
+    use std::io::Write;
     use tokio::io::AsyncBufReadExt;
-    let mut diagnostics: Vec<Diagnostic> = Vec::new();
-    let mut last_file = String::new();
+    let offline = matches!(kind, OutputKind::Log);
+    let mut sink = OutputSink {
+        client,
+        kind,
+        project_id: context.project_id,
+        parsed: Vec::new(),
+        diagnostics: Vec::new(),
+        last_file: String::new(),
+    };
+    // A diagnostic is reported once the line after it shows it has no more
+    // continuation lines; these are kept to be echoed after it.
+    let mut pending: Option<CompilerLineDiagnostic> = None;
+    let mut continuation: Vec<String> = Vec::new();
+    // Whether the last diagnostic was suppressed by the warning policy, so
+    // its continuation lines are dropped too.
+    let mut suppressed = false;
     // Tracks the last emitted (file, line, code) key to deduplicate consecutive identical
     // diagnostics that Delphi 2007 outputs twice (once wrapped in MSBuild format, once plain).
-    let mut last_diag_key: Option<(String, u32, String)> = None;
+    // Diagnostics without a position are told apart by their message as well.
+    let mut last_diag_key: Option<(String, u32, String, String)> = None;
     let mut buf = Vec::new();
 
     loop {
@@ -561,6 +1215,12 @@ async fn process_output_lines<R: AsyncRead + Unpin + Send>(
             Ok(_) => {}
             Err(_) => break,
         }
+        // The log keeps the output as the compiler wrote it, undecoded.
+        if let Some(log) = &log
+            && let Ok(mut file) = log.lock()
+        {
+            let _ = file.write_all(&buf);
+        }
         // Decode using the configured compiler encoding
         let line = crate::encoding::decode_line(&buf)
             .trim_end_matches(['\r', '\n'])
@@ -573,86 +1233,105 @@ async fn process_output_lines<R: AsyncRead + Unpin + Send>(
         if PATH_ONLY_LINE_REGEX.is_match(&line) {
             continue;
         }
-        if compiler_state::is_cancelled() {
+        if !offline && compiler_state::is_cancelled() {
             break;
         }
-        if let Some(mut diagnostic) = CompilerLineDiagnostic::from_line(&line, compiler_name.clone()) {
-            // Resolve relative file paths against the project directory
-            let file_path = PathBuf::from(&diagnostic.file);
-            if file_path.is_relative() {
-                let resolved = project_dir.join(&file_path);
-                if resolved.exists() {
-                    diagnostic.file = resolved.to_string_lossy().to_string();
+        if let Some(mut diagnostic) =
+            CompilerLineDiagnostic::from_localized_line(&line, context.compiler_name.clone(), context.language)
+        {
+            context.resolve(&mut diagnostic.file);
+            let Some(severity) = context.policy.severity(&diagnostic.code, diagnostic.kind) else {
+                // Suppressed, together with its continuation lines.
+                if let Some(previous) = pending.take() {
+                    sink.diagnostic(previous, std::mem::take(&mut continuation)).await;
                 }
+                suppressed = true;
+                continue;
+            };
+            diagnostic.kind = severity;
+            suppressed = false;
+            // Without a position, the diagnostic belongs to the project unless it
+            // names an existing file, e.g. "MSBUILD : error MSB1009: ...".
+            if !diagnostic.has_position()
+                && !Path::new(&diagnostic.file).is_file()
+                && let Some(project_file) = &context.project_file
+            {
+                diagnostic.file = project_file.to_string_lossy().to_string();
             }
             // Deduplicate: Delphi 2007 emits the same diagnostic twice – once in the
             // Borland.Delphi.Targets MSBuild wrapper and once as a plain indented line.
             // Skip the second occurrence when it has the same (file, line, code) as the
             // diagnostic we just emitted.
-            let key = (diagnostic.file.clone(), diagnostic.line, diagnostic.code.clone());
+            let message = match diagnostic.has_position() {
+                true => String::new(),
+                false => diagnostic.message.clone(),
+            };
+            let key = (diagnostic.file.clone(), diagnostic.line, diagnostic.code.clone(), message);
             if last_diag_key.as_ref() == Some(&key) {
                 continue;
             }
             last_diag_key = Some(key);
-            if last_file != diagnostic.file && !diagnostics.is_empty() {
-                compiler_state::track_diagnosed_file(last_file.clone());
-                publish_diagnostics(client.as_ref(), &last_file, &diagnostics).await;
-                diagnostics.clear();
-            }
-            last_file = diagnostic.file.clone();
-            let formatted = format!("{}", &diagnostic);
-            match kind {
-                OutputKind::Stdout => CompilerProgress::notify_stdout(client.as_ref(), formatted).await,
-                OutputKind::Stderr => CompilerProgress::notify_stderr(client.as_ref(), formatted).await,
+            if let Some(previous) = pending.replace(diagnostic) {
+                sink.diagnostic(previous, std::mem::take(&mut continuation)).await;
             }
-            diagnostics.push(diagnostic.into());
             continue;
         }
-        match kind {
-            OutputKind::Stdout => CompilerProgress::notify_stdout(client.as_ref(), line).await,
-            OutputKind::Stderr => CompilerProgress::notify_stderr(client.as_ref(), line).await,
+        if let Some(diagnostic) = &mut pending
+            && line.starts_with(char::is_whitespace)
+            && let Some(mut related) = CompilerLineDiagnostic::related_from_line(&line)
+        {
+            context.resolve(&mut related.file);
+            diagnostic.related.push(related);
+            continuation.push(line);
+            continue;
         }
+        if suppressed
+            && line.starts_with(char::is_whitespace)
+            && CompilerLineDiagnostic::related_from_line(&line).is_some()
+        {
+            continue;
+        }
+        suppressed = false;
+        if let Some(previous) = pending.take() {
+            sink.diagnostic(previous, std::mem::take(&mut continuation)).await;
+        }
+        sink.line(line).await;
     }
 
-    if !diagnostics.is_empty() {
-        compiler_state::track_diagnosed_file(last_file.clone());
-        publish_diagnostics(client.as_ref(), &last_file, &diagnostics).await;
+    if let Some(previous) = pending.take() {
+        sink.diagnostic(previous, continuation).await;
     }
+    sink.finish().await
 }
 
-fn find_msbuild() -> Result<String> {
-    let mut search: Vec<String> = SearchBuilder::default()
-        .location(r"C:\Windows\Microsoft.NET\Framework\")
-        .search_input("msbuild.exe")
-        .depth(2)
-        .ignore_case()
-        .build()
-        .collect();
-    search.retain(|path| path.to_lowercase().ends_with("msbuild.exe"));
-    search.sort_by(
-        |left, right| {
-            let left_version = left
-                .split('\\')
-                .rev()
-                .nth(1)
-                .unwrap_or("v0");
-            let right_version = right
-                .split('\\')
-                .rev()
-                .nth(1)
-                .unwrap_or("v0");
-            right_version.cmp(left_version)
-        });
-    if let Some(msbuild_path) = search.first() {
-        return Ok(msbuild_path.clone());
-    }
-    anyhow::bail!(
-        "Cannot find msbuild.exe in C:\\Windows\\Microsoft.NET\\Framework\\. Please ensure that MSBuild is installed and try again."
-    );
+/// Parse a saved build log the way the output of a running build is parsed
+/// (same decoding, filtering, path resolution and deduplication), without
+/// sending anything to a client.  Relative file names are resolved against
+/// `project_dir`, diagnostics without a position are attached to
+/// `project_file` if given; the diagnostics are attributed to `project_id`
+/// and `policy` is applied to them like to those of a build.
+pub async fn parse_build_log(
+    log: &Path,
+    project_id: usize,
+    project_dir: &Path,
+    project_file: Option<&Path>,
+    policy: WarningPolicy,
+    language: OutputLanguage,
+) -> Result<Vec<CompileDiagnostic>> {
+    let file = tokio::fs::File::open(log)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to open build log {}: {}", log.display(), e))?;
+    let context = OutputContext {
+        compiler_name: String::new(),
+        language,
+        project_id,
+        project_dir: project_dir.to_path_buf(),
+        project_file: project_file.map(Path::to_path_buf),
+        policy,
+    };
+    Ok(process_output_lines(None, BufReader::new(file), OutputKind::Log, context, None).await)
 }
 
-
-
 async fn clear_stale_diagnostics(client: Option<&tower_lsp::Client>) {
     // Always drain the tracked files to prevent stale state
     let files = compiler_state::take_diagnosed_files();
@@ -679,6 +1358,74 @@ async fn publish_diagnostics(
         .await;
 }
 
+/// Footer of a compilation: the outcome of each project of a multi-project
+/// compile, or how a single project broke its warning policy.
+fn footer_lines(parameters: &CompilationParameters<'_>, outcomes: &[ProjectOutcome]) -> Vec<String> {
+    if parameters.only_one_project {
+        let violations: Vec<String> = outcomes
+            .iter()
+            .flat_map(|outcome| outcome.policy_violations.iter().cloned())
+            .collect();
+        return parameters.banner.into_footer_vec(&violations);
+    }
+    let mut footer = parameters.banner.into_footer_vec(&[]);
+    let bottom = footer.pop();
+    footer.extend(project_table_lines(outcomes));
+    footer.extend(bottom);
+    footer
+}
+
+/// Footer rows of a multi-project compile: one line per project with its
+/// status, exit code and build time.
+fn project_table_lines(outcomes: &[ProjectOutcome]) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for outcome in outcomes {
+        let (icon, status) = match outcome.status {
+            ProjectStatus::Succeeded => ("✅", "OK"),
+            ProjectStatus::UpToDate => ("✅", "UP TO DATE"),
+            ProjectStatus::Failed => ("❌", "FAILED"),
+            ProjectStatus::Cancelled => ("⚠️", "CANCELLED"),
+            ProjectStatus::Skipped => ("⏭️", "SKIPPED"),
+        };
+        let details = match outcome.status {
+            ProjectStatus::Skipped | ProjectStatus::UpToDate => String::new(),
+            ProjectStatus::Failed => format!(
+                "exit {} {:>7.1}s",
+                outcome.code,
+                outcome.duration_ms as f64 / 1000.0
+            ),
+            _ => format!("{:>7.1}s", outcome.duration_ms as f64 / 1000.0),
+        };
+        let name: String = outcome.project_name.chars().take(36).collect();
+        lines.push(format!("  {icon} {name:<36} {status:<10} {details}").trim_end().to_string());
+        for violation in &outcome.policy_violations {
+            lines.push(format!("       ↳ Warning policy: {violation}"));
+        }
+    }
+    let failed = outcomes.iter().filter(|o| o.status == ProjectStatus::Failed).count();
+    let skipped = outcomes.iter().filter(|o| o.status == ProjectStatus::Skipped).count();
+    let succeeded = outcomes.iter().filter(|o| o.status.is_success()).count();
+    let up_to_date = outcomes.iter().filter(|o| o.status == ProjectStatus::UpToDate).count();
+    let up_to_date = match up_to_date {
+        0 => String::new(),
+        count => format!(" ({count} up to date)"),
+    };
+    lines.push(format!(
+        "  {} of {} project(s) succeeded{}, {} failed, {} skipped.",
+        succeeded,
+        outcomes.len(),
+        up_to_date,
+        failed,
+        skipped
+    ));
+    lines
+}
+
+/// A banner line reporting a violation of the warning policy.
+fn policy_line(violation: &str) -> String {
+    format_line(&format!("⚠️  Warning policy: {violation}"), 70)
+}
+
 fn format_line(text: &str, total_width: usize) -> String {
     let padding = total_width.saturating_sub(text.len() + 2);
     if padding == 0 {
@@ -694,6 +1441,14 @@ struct CompilationParameters<'compiler> {
     configuration: CompilerConfiguration,
     rebuild: bool,
     only_one_project: bool,
+    /// Skip the projects whose output is up to date (see [`up_to_date`]).
+    check_up_to_date: bool,
+    /// Workspace the projects are compiled from, if any.
+    workspace_id: Option<usize>,
+    /// Continue with the next project after a failed one.
+    keep_going: bool,
+    /// How many projects are built at the same time.
+    jobs: usize,
     banner: CompBanner,
 }
 
@@ -781,24 +1536,25 @@ impl CompBanner {
         lines
     }
 
-    fn into_project_footer_vec(&self) -> Vec<String> {
-        let cancelled = compiler_state::is_cancelled();
-        let success = compiler_state::is_success();
-        let status_str = if cancelled {
-            "⚠️  CANCELLED"
-        } else if success {
-            "✅ SUCCESS"
-        } else {
-            "❌ FAILED"
+    /// The footer of a project build; `violations` of the warning policy are
+    /// listed above the status.
+    fn into_project_footer_vec(&self, status: ProjectStatus, violations: &[String]) -> Vec<String> {
+        let status_str = match status {
+            ProjectStatus::Succeeded => "✅ SUCCESS",
+            ProjectStatus::UpToDate => "✅ UP TO DATE",
+            ProjectStatus::Failed => "❌ FAILED",
+            ProjectStatus::Cancelled => "⚠️  CANCELLED",
+            ProjectStatus::Skipped => "⏭️  SKIPPED",
         };
         let mut lines = vec![BANNER_PROJECT_TOP.to_string()];
         lines.extend(self.base_lines());
+        lines.extend(violations.iter().map(|violation| policy_line(violation)));
         lines.push(format_line(&format!("Status: {}", status_str), 70));
         lines.push(BANNER_PROJECT_BOTTOM.to_string());
         lines
     }
 
-    fn into_footer_vec(&self) -> Vec<String> {
+    fn into_footer_vec(&self, violations: &[String]) -> Vec<String> {
         let cancelled = compiler_state::is_cancelled();
         let success = compiler_state::is_success();
         let (status_str, top, bottom) = if cancelled {
@@ -810,6 +1566,7 @@ impl CompBanner {
         };
         let mut lines = vec![top.to_string()];
         lines.extend(self.base_lines());
+        lines.extend(violations.iter().map(|violation| policy_line(violation)));
         lines.push(format_line(&format!("Status: {}", status_str), 70));
         lines.push(bottom.to_string());
         lines
@@ -0,0 +1,314 @@
+//! Build queue of this process.
+//!
+//! [`Compiler::compile`](super::Compiler::compile) no longer rejects a
+//! request while another build runs: every compilation becomes a job that is
+//! queued and built once the jobs queued before it have finished.  Jobs run
+//! one at a time, in the order they were queued.  A queued job can be
+//! cancelled before it starts, the running one is stopped like before.
+//!
+//! Every process mirrors its jobs to `<config>/ddk/build-queue/<pid>.ron`, so
+//! [`all_jobs`] (`ddk queue`) also lists the builds of the language server
+//! and the MCP server.
+
+use anyhow::Result;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use tokio::sync::Notify;
+
+use super::compiler_state;
+use crate::utils::process_alive;
+
+pub type JobId = u64;
+
+/// How many finished jobs are kept for inspection.
+const FINISHED_JOBS_KEPT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        !matches!(self, JobState::Queued | JobState::Running)
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        };
+        f.pad(state)
+    }
+}
+
+/// A compilation in the queue of a DDK process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildJobInfo {
+    pub id: JobId,
+    /// What is built, e.g. "Compiling Workspace Main".
+    pub description: String,
+    pub state: JobState,
+    /// Process that owns the job and the name of its executable.
+    pub pid: u32,
+    pub program: String,
+    /// Unix timestamps in milliseconds.
+    pub queued_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static JOBS: Mutex<Vec<BuildJobInfo>> = Mutex::new(Vec::new());
+
+fn changed() -> &'static Notify {
+    static CHANGED: OnceLock<Notify> = OnceLock::new();
+    CHANGED.get_or_init(Notify::new)
+}
+
+fn jobs_lock() -> MutexGuard<'static, Vec<BuildJobInfo>> {
+    JOBS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Reserve the id of a job that is queued later with [`enqueue`], so that
+/// progress events of the job can be recognized before it is queued.
+pub fn next_job_id() -> JobId {
+    NEXT_ID.fetch_add(1, Ordering::SeqCst)
+}
+
+/// Queue a compilation.  The job waits in [`JobTicket::wait_turn`] and
+/// finishes when the ticket is dropped.
+pub fn enqueue(id: Option<JobId>, description: String) -> JobTicket {
+    let id = id.unwrap_or_else(next_job_id);
+    let mut jobs = jobs_lock();
+    jobs.push(BuildJobInfo {
+        id,
+        description,
+        state: JobState::Queued,
+        pid: std::process::id(),
+        program: program_name(),
+        queued_at: now(),
+        started_at: None,
+        finished_at: None,
+    });
+    save_snapshot(&jobs);
+    JobTicket { id, outcome: None }
+}
+
+/// Jobs of this process: finished ones first, then the running and the
+/// queued ones in queue order.
+pub fn jobs() -> Vec<BuildJobInfo> {
+    jobs_lock().clone()
+}
+
+/// Jobs of every running DDK process, this one included, by queue time.
+pub fn all_jobs() -> Vec<BuildJobInfo> {
+    let own = std::process::id();
+    let mut all = jobs();
+    if let Ok(entries) = std::fs::read_dir(snapshot_dir()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let pid = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse::<u32>().ok());
+            let Some(pid) = pid else { continue };
+            if pid == own {
+                continue;
+            }
+            if !process_alive(pid) {
+                let _ = std::fs::remove_file(&path);
+                continue;
+            }
+            if let Ok(content) = std::fs::read_to_string(&path)
+                && let Ok(jobs) = ron::from_str::<Vec<BuildJobInfo>>(&content)
+            {
+                all.extend(jobs);
+            }
+        }
+    }
+    all.sort_by_key(|job| (job.queued_at, job.pid, job.id));
+    all
+}
+
+/// The job that is being built, if any.
+pub fn running_job() -> Option<JobId> {
+    jobs_lock().iter().find(|job| job.state == JobState::Running).map(|job| job.id)
+}
+
+/// Cancel job `id`: a queued job is dropped from the queue, the running one
+/// is stopped.
+pub fn cancel(id: JobId) -> Result<()> {
+    let mut jobs = jobs_lock();
+    let Some(job) = jobs.iter_mut().find(|job| job.id == id) else {
+        anyhow::bail!("Build job #{} not found", id);
+    };
+    match job.state {
+        JobState::Queued => {
+            job.state = JobState::Cancelled;
+            job.finished_at = Some(now());
+            save_snapshot(&jobs);
+            drop(jobs);
+            changed().notify_waiters();
+        }
+        JobState::Running => {
+            compiler_state::cancel_job(id);
+        }
+        state => anyhow::bail!("Build job #{} has already {}", id, state),
+    }
+    Ok(())
+}
+
+/// Cancel the running job; returns its id.
+pub fn cancel_running() -> Option<JobId> {
+    let id = running_job()?;
+    compiler_state::cancel_job(id);
+    Some(id)
+}
+
+/// Cancel every queued job and the running one.
+pub fn cancel_all() {
+    let queued: Vec<JobId> = jobs_lock()
+        .iter()
+        .filter(|job| job.state == JobState::Queued)
+        .map(|job| job.id)
+        .collect();
+    for id in queued {
+        let _ = cancel(id);
+    }
+    cancel_running();
+}
+
+/// Place of a job in the queue of a compilation: its queue entry is
+/// finished when the ticket is dropped.
+pub struct JobTicket {
+    id: JobId,
+    outcome: Option<JobState>,
+}
+
+impl JobTicket {
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// Jobs that are built before this one.
+    pub fn jobs_ahead(&self) -> usize {
+        let jobs = jobs_lock();
+        let queued_before = jobs
+            .iter()
+            .take_while(|job| job.id != self.id)
+            .filter(|job| job.state == JobState::Queued)
+            .count();
+        let running = jobs.iter().filter(|job| job.state == JobState::Running).count();
+        queued_before + running
+    }
+
+    /// Wait until every job queued before this one has finished and mark
+    /// the job as running.  `false` when the job was cancelled while queued.
+    pub async fn wait_turn(&self) -> bool {
+        loop {
+            let notified = changed().notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if let Some(started) = self.try_start() {
+                return started;
+            }
+            notified.await;
+        }
+    }
+
+    fn try_start(&self) -> Option<bool> {
+        let mut jobs = jobs_lock();
+        let running = jobs.iter().any(|job| job.state == JobState::Running);
+        let first_queued = jobs.iter().find(|job| job.state == JobState::Queued).map(|job| job.id);
+        let job = jobs.iter_mut().find(|job| job.id == self.id)?;
+        match job.state {
+            JobState::Queued if !running && first_queued == Some(self.id) => {
+                job.state = JobState::Running;
+                job.started_at = Some(now());
+                compiler_state::start_job(self.id);
+                save_snapshot(&jobs);
+                Some(true)
+            }
+            JobState::Queued => None,
+            JobState::Running => Some(true),
+            _ => Some(false),
+        }
+    }
+
+    /// State the job finishes with when the ticket is dropped; without one a
+    /// running job counts as failed.
+    pub fn set_outcome(&mut self, state: JobState) {
+        self.outcome = Some(state);
+    }
+}
+
+impl Drop for JobTicket {
+    fn drop(&mut self) {
+        let mut jobs = jobs_lock();
+        if let Some(job) = jobs.iter_mut().find(|job| job.id == self.id)
+            && !job.state.is_finished()
+        {
+            job.state = match (job.state, self.outcome) {
+                (JobState::Queued, _) => JobState::Cancelled,
+                (_, Some(outcome)) => outcome,
+                _ => JobState::Failed,
+            };
+            job.finished_at = Some(now());
+        }
+        let finished = jobs.iter().filter(|job| job.state.is_finished()).count();
+        let mut excess = finished.saturating_sub(FINISHED_JOBS_KEPT);
+        jobs.retain(|job| {
+            if excess > 0 && job.state.is_finished() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+        // Finished jobs first, so the queue reads top to bottom.
+        jobs.sort_by_key(|job| !job.state.is_finished());
+        save_snapshot(&jobs);
+        drop(jobs);
+        changed().notify_waiters();
+    }
+}
+
+/// File name of this process' executable, without extension.
+pub(crate) fn program_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+        .unwrap_or_default()
+}
+
+fn snapshot_dir() -> PathBuf {
+    dirs::config_dir().unwrap_or_default().join("ddk").join("build-queue")
+}
+
+/// Mirror the jobs of this process for other processes; best effort.
+fn save_snapshot(jobs: &[BuildJobInfo]) {
+    let dir = snapshot_dir();
+    let path = dir.join(format!("{}.ron", std::process::id()));
+    if jobs.is_empty() {
+        let _ = std::fs::remove_file(path);
+        return;
+    }
+    if let Ok(serialized) = ron::ser::to_string_pretty(jobs, PrettyConfig::default()) {
+        let _ = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(path, serialized));
+    }
+}
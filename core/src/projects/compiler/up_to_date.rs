@@ -0,0 +1,156 @@
+//! Whether a project needs building at all.
+//!
+//! A bulk compile does not build a project whose output (the `.exe` / `.dll`
+//! of an application or library, the `.bpl` of a package) is newer than
+//! each of its sources: the `.dproj`, the `.dpr` / `.dpk`, the units reachable
+//! through `uses` clauses and the forms, resources and include files named
+//! by `{$R}` / `{$I}` directives.  Units are looked for next to the unit
+//! using them, next to the main source and on the unit search path; units
+//! that are not found there (RTL, VCL, precompiled third-party units) are not
+//! taken into account.  Whenever the output or the sources cannot be
+//! determined, the project is built.
+//!
+//! The modification times cannot tell which compiler wrote the output, so
+//! the compiler also checks that the last successful build of the project in
+//! the [history](super::history) used the same compiler, configuration and
+//! platform.
+
+use dproj_rs::DprojBuilder;
+use dproj_rs::dproj::PropertyGroup;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::files::sources::main_source_unit_paths;
+use crate::projects::{Project, used_unit_names};
+use crate::utils::normalize_path;
+
+lazy_static::lazy_static! {
+    /// Matches `{$R file}`, `{$RESOURCE 'file' 'file.rc'}`, `{$I file}` and
+    /// `{$INCLUDE file}`, but not switches like `{$R-}` or `{$I+}`.
+    static ref FILE_DIRECTIVE_REGEX: regex::Regex =
+        regex::Regex::new(r"(?i)\{\$(?P<kind>RESOURCE|INCLUDE|R|I)\s+(?:'(?P<quoted>[^']+)'|(?P<name>[^\s}]+))").unwrap();
+}
+
+/// A path written in a project file, with `\` separators on every platform.
+fn native(path: &str) -> PathBuf {
+    PathBuf::from(path.trim().replace('\\', std::path::MAIN_SEPARATOR_STR))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
+}
+
+/// Whether `project`, built for `config` / `platform`, is up to date: its
+/// output exists and was written after every one of its sources changed.
+/// `env` expands the variables of the `.dproj`, e.g. `$(BDSCOMMONDIR)`.
+pub fn is_up_to_date(project: &Project, config: &str, platform: &str, env: HashMap<String, String>) -> bool {
+    let Some(dproj_path) = project.dproj.as_deref().map(native) else {
+        return false;
+    };
+    let Ok(dproj) = DprojBuilder::new().env(env).from_file(&dproj_path) else {
+        return false;
+    };
+    let Ok(group) = dproj.active_property_group_for(config, platform) else {
+        return false;
+    };
+    let Some(directory) = dproj_path.parent() else {
+        return false;
+    };
+    let main_source = project
+        .dpr
+        .as_deref()
+        .or(project.dpk.as_deref())
+        .map(native)
+        .or_else(|| dproj.get_main_source().ok())
+        .or_else(|| group.project_properties.main_source.as_deref().map(|source| directory.join(native(source))));
+    let Some(main_source) = main_source else {
+        return false;
+    };
+    let Some(output) = output_file(&main_source, &group, directory) else {
+        return false;
+    };
+    let Some(built) = modified(&output) else {
+        return false;
+    };
+    let search_dirs: Vec<PathBuf> = group
+        .dcc_options
+        .unit_search_path
+        .iter()
+        .chain(&group.dcc_options.include_path)
+        .flat_map(|paths| paths.split(';'))
+        .filter(|dir| !dir.trim().is_empty() && !dir.contains("$("))
+        .map(|dir| normalize_path(directory.join(native(dir))))
+        .collect();
+    let mut sources = source_files(&main_source, &search_dirs);
+    sources.push(dproj_path);
+    sources.iter().all(|source| modified(source).is_some_and(|changed| changed < built))
+}
+
+/// File a build of the project writes, `None` when it is not known, e.g. a
+/// package without a `.bpl` output directory.
+pub fn output_file(main_source: &Path, group: &PropertyGroup, directory: &Path) -> Option<PathBuf> {
+    let stem = main_source.file_stem()?.to_string_lossy().to_string();
+    let properties = &group.project_properties;
+    let is = |value: &Option<String>, expected: &str| value.as_deref().is_some_and(|v| v.trim().eq_ignore_ascii_case(expected));
+    let is_package = main_source.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dpk"))
+        || is(&properties.app_type, "Package");
+    let (output_dir, extension) = if is_package {
+        (group.dcc_options.bpl_output.as_deref()?, "bpl")
+    } else {
+        let extension = if is(&properties.app_type, "Library") || is(&properties.gen_dll, "true") { "dll" } else { "exe" };
+        (group.dcc_options.exe_output.as_deref().unwrap_or("."), extension)
+    };
+    if output_dir.contains("$(") {
+        return None;
+    }
+    Some(normalize_path(directory.join(native(output_dir)).join(format!("{stem}.{extension}"))))
+}
+
+/// The main source and every existing file it (indirectly) uses or names in
+/// a resource or include directive.  Units are looked for next to the unit
+/// using them, next to `main_source` and in `search_dirs`.
+pub fn source_files(main_source: &Path, search_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let main_dir = main_source.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+    let mut pending = vec![normalize_path(main_source)];
+    pending.extend(main_source_unit_paths(main_source));
+    while let Some(file) = pending.pop() {
+        if !seen.insert(file.to_string_lossy().to_lowercase()) || !file.is_file() {
+            continue;
+        }
+        files.push(file.clone());
+        let is_text = ["pas", "dpr", "dpk", "inc"]
+            .iter()
+            .any(|ext| file.extension().is_some_and(|e| e.eq_ignore_ascii_case(ext)));
+        if !is_text {
+            continue;
+        }
+        let Ok(raw) = std::fs::read(&file) else { continue };
+        let (content, _) = crate::encoding::decode_source(&raw);
+        let dir = file.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let candidates = |name: &str| -> Option<PathBuf> {
+            [&dir, &main_dir]
+                .into_iter()
+                .chain(search_dirs)
+                .map(|candidate_dir| candidate_dir.join(name))
+                .find(|path| path.is_file())
+                .map(normalize_path)
+        };
+        for unit in used_unit_names(&content) {
+            pending.extend(candidates(&format!("{unit}.pas")));
+        }
+        for directive in FILE_DIRECTIVE_REGEX.captures_iter(&content) {
+            let Some(name) = directive.name("quoted").or(directive.name("name")) else { continue };
+            let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+            let mut name = native(&name.as_str().replace('*', &stem));
+            let resource = directive["kind"].to_uppercase().starts_with('R');
+            if name.extension().is_none() {
+                name.set_extension(if resource { "res" } else { "pas" });
+            }
+            pending.extend(candidates(&name.to_string_lossy()));
+        }
+    }
+    files
+}
@@ -11,6 +11,47 @@ use crate::utils::{FilePath, Load};
 
 pub(crate) const DEFAULT_COMPILERS: &str = include_str!("presets/default_compilers.ron");
 
+/// How projects are built with a compiler configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildBackendKind {
+    /// MSBuild on the `.dproj`, inside the `rsvars.bat` environment.
+    #[default]
+    MsBuild,
+    /// The platform's command-line compiler (dcc32, dcc64, …) on the
+    /// `.dpr` / `.dpk`, without MSBuild.
+    Dcc,
+    /// A user-defined command, see [`CustomCommandBackend`](super::CustomCommandBackend)
+    /// for the supported placeholders.
+    Custom { program: String, args: Vec<String> },
+}
+
+/// Language of the labels in the compiler output, e.g. "Warnung" and
+/// "Fehler" in a German installation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OutputLanguage {
+    /// Recognize the labels of every supported language.
+    #[default]
+    Auto,
+    English,
+    German,
+    French,
+    Japanese,
+}
+
+impl OutputLanguage {
+    pub const ALL: [OutputLanguage; 5] = [
+        OutputLanguage::Auto,
+        OutputLanguage::English,
+        OutputLanguage::German,
+        OutputLanguage::French,
+        OutputLanguage::Japanese,
+    ];
+
+    pub fn is_auto(&self) -> bool {
+        *self == OutputLanguage::Auto
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PartialCompilerConfiguration {
     pub condition: Option<String>,
@@ -20,6 +61,19 @@ pub struct PartialCompilerConfiguration {
     pub compiler_version: Option<usize>,
     pub installation_path: Option<String>,
     pub build_arguments: Option<Vec<String>>,
+    #[serde(default)]
+    pub backend: Option<BuildBackendKind>,
+    /// An empty string resets to the default.
+    #[serde(default)]
+    pub msbuild_path: Option<String>,
+    /// An empty string resets to the default.
+    #[serde(default)]
+    pub env_script: Option<String>,
+    /// Replaces the whole map.
+    #[serde(default)]
+    pub extra_env: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub output_language: Option<OutputLanguage>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -31,6 +85,22 @@ pub struct CompilerConfiguration {
     pub compiler_version: usize,
     pub installation_path: String,
     pub build_arguments: Vec<String>,
+    #[serde(default)]
+    pub backend: BuildBackendKind,
+    /// `msbuild.exe` to use instead of the newest one found under
+    /// `C:\Windows\Microsoft.NET\Framework`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub msbuild_path: Option<String>,
+    /// Script whose `SET` lines make up the build environment, instead of
+    /// `<installation_path>\bin\rsvars.bat`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_script: Option<String>,
+    /// Variables set for every build on top of the environment script.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra_env: HashMap<String, String>,
+    /// Language the compiler output is parsed in.
+    #[serde(default, skip_serializing_if = "OutputLanguage::is_auto")]
+    pub output_language: OutputLanguage,
 }
 
 impl CompilerConfiguration {
@@ -56,6 +126,92 @@ impl CompilerConfiguration {
         if let Some(build_arguments) = &partial.build_arguments {
             self.build_arguments = build_arguments.clone();
         }
+        if let Some(backend) = &partial.backend {
+            self.backend = backend.clone();
+        }
+        if let Some(msbuild_path) = &partial.msbuild_path {
+            self.msbuild_path = non_empty(msbuild_path);
+        }
+        if let Some(env_script) = &partial.env_script {
+            self.env_script = non_empty(env_script);
+        }
+        if let Some(extra_env) = &partial.extra_env {
+            self.extra_env = extra_env.clone();
+        }
+        if let Some(output_language) = partial.output_language {
+            self.output_language = output_language;
+        }
+    }
+
+    /// The configured environment script, or `<installation_path>\bin\rsvars.bat`.
+    pub fn env_script_path(&self) -> PathBuf {
+        match &self.env_script {
+            Some(env_script) => PathBuf::from(env_script),
+            _ => PathBuf::from(&self.installation_path).join("bin").join("rsvars.bat"),
+        }
+    }
+
+    /// Whether MSBuild can find the Delphi targets: either the environment
+    /// script exists or `extra_env` provides `BDS` itself.
+    pub fn has_build_environment(&self) -> bool {
+        self.env_script_path().exists() || self.extra_env.contains_key("BDS")
+    }
+
+    pub fn validate(&self, key: &str) -> Result<()> {
+        if key.trim().is_empty() {
+            anyhow::bail!("Compiler key cannot be empty.");
+        }
+        if self.condition.trim().is_empty() {
+            anyhow::bail!("Compiler condition cannot be empty for key: {}", key);
+        }
+        if self.product_name.trim().is_empty() {
+            anyhow::bail!("Compiler product name cannot be empty for key: {}", key);
+        }
+        if self.installation_path.trim().is_empty() {
+            anyhow::bail!("Compiler installation path cannot be empty for key: {}", key);
+        }
+        let path = PathBuf::from(&self.installation_path);
+        if !path.exists() {
+            anyhow::bail!("Compiler installation path does not exist for key: {}: {}", key, self.installation_path);
+        }
+        if !path.is_dir() {
+            anyhow::bail!("Compiler installation path is not a directory for key: {}: {}", key, self.installation_path);
+        }
+        if let Some(msbuild_path) = &self.msbuild_path
+            && !PathBuf::from(msbuild_path).is_file()
+        {
+            anyhow::bail!("MSBuild not found for key: {}: {}", key, msbuild_path);
+        }
+        if let Some(env_script) = &self.env_script
+            && !PathBuf::from(env_script).is_file()
+        {
+            anyhow::bail!("Environment script not found for key: {}: {}", key, env_script);
+        }
+        if self.extra_env.keys().any(|name| name.trim().is_empty() || name.contains('=')) {
+            anyhow::bail!("Invalid environment variable name in extra_env for key: {}", key);
+        }
+        match &self.backend {
+            BuildBackendKind::MsBuild if !self.has_build_environment() => {
+                anyhow::bail!(
+                    "rsvars.bat not found in compiler installation path for key: {}: {} (set env_script, or BDS in extra_env)",
+                    key,
+                    self.env_script_path().display()
+                );
+            }
+            BuildBackendKind::Custom { program, .. } if program.trim().is_empty() => {
+                anyhow::bail!("Custom build command cannot be empty for key: {}", key);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(value.to_string())
     }
 }
 
@@ -163,29 +319,7 @@ impl CompilerConfigurations {
 
     pub fn validate(&self) -> Result<()> {
         for (key, compiler) in &self._compilers {
-            if key.trim().is_empty() {
-                anyhow::bail!("Compiler key cannot be empty.");
-            }
-            if compiler.condition.trim().is_empty() {
-                anyhow::bail!("Compiler condition cannot be empty for key: {}", key);
-            }
-            if compiler.product_name.trim().is_empty() {
-                anyhow::bail!("Compiler product name cannot be empty for key: {}", key);
-            }
-            if compiler.installation_path.trim().is_empty() {
-                anyhow::bail!("Compiler installation path cannot be empty for key: {}", key);
-            }
-            let path = PathBuf::from(&compiler.installation_path);
-            if !path.exists() {
-                anyhow::bail!("Compiler installation path does not exist for key: {}: {}", key, compiler.installation_path);
-            }
-            if !path.is_dir() {
-                anyhow::bail!("Compiler installation path is not a directory for key: {}: {}", key, compiler.installation_path);
-            }
-            let rsvars_path = path.join("bin").join("rsvars.bat");
-            if !rsvars_path.exists() {
-                anyhow::bail!("rsvars.bat not found in compiler installation path for key: {}: {}", key, rsvars_path.display());
-            }
+            compiler.validate(key)?;
         }
         Ok(())
     }
@@ -1,29 +1,116 @@
 use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
 use tower_lsp::lsp_types::*;
+use std::collections::HashMap;
 use std::fmt::Display;
 
+use super::{DiagnosticExplanation, OutputLanguage};
+
 // Standard MSBuild / dcc32 format:
 // <file>(<line>[,<col>]): (error|warning|hint|fatal) <CODE>: <message> [<project>]
-const MSBUILD_OUTPUT_REGEX: &str = r"^(?P<file>.*?)[(](?P<line>\d+)(?:,(?P<column>\d+))?[)]:\s+(?P<kind>.*?)\s+(?P<code>[A-Z]\d+):\s+(?P<message>.*?)(?:\s+\[.*\])?$";
+// The code is a Delphi one (E2003) or an MSBuild one (MSB3073).
+const MSBUILD_OUTPUT_REGEX: &str = r"^(?P<file>.*?)[(](?P<line>\d+)(?:,(?P<column>\d+))?[)]:\s+(?P<kind>.*?)\s+(?P<code>[A-Z]+\d+):\s+(?P<message>.*?)(?:\s+\[.*\])?$";
 
 // Delphi 2007 / Borland MSBuild wrapper format:
 // <target_file> : (warning|error|hint|fatal) : <source_file>(<line>) <localized_label>: <CODE> <message> [<project>]
-const DELPHI2007_MSBUILD_REGEX: &str = r"^.*?\s+:\s+(?:warning|error|hint|fatal)\s+:\s+(?P<file>.*?)[(](?P<line>\d+)(?:,(?P<column>\d+))?[)]\s+\S+\s+(?P<code>[A-Z]\d+)\s+(?P<message>.*?)(?:\s+\[.*\])?$";
+const DELPHI2007_MSBUILD_REGEX: &str = r"^.*?\s+:\s+(?:warning|error|hint|fatal)\s+:\s+(?P<file>.*?)[(](?P<line>\d+)(?:,(?P<column>\d+))?[)]\s+(?P<kind>.+?)\s+(?P<code>[A-Z]\d+)\s+(?P<message>.*?)(?:\s+\[.*\])?$";
 
 // Delphi 2007 simple / duplicate format (indented line, no MSBuild wrapper):
 //   <source_file>(<line>) <localized_label>: <CODE> <message>
-const DELPHI2007_SIMPLE_REGEX: &str = r"^\s+(?P<file>[A-Za-z]:\\[^(]*?)[(](?P<line>\d+)(?:,(?P<column>\d+))?[)]\s+\S+\s+(?P<code>[A-Z]\d+)\s+(?P<message>.*)$";
+const DELPHI2007_SIMPLE_REGEX: &str = r"^\s+(?P<file>[A-Za-z]:\\[^(]*?)[(](?P<line>\d+)(?:,(?P<column>\d+))?[)]\s+(?P<kind>.+?)\s+(?P<code>[A-Z]\d+)\s+(?P<message>.*)$";
+
+// The formats below print a localized label, "{label}" stands for the labels
+// of the output language (see `LABELS`).
+
+// IDE-style tool prefix with a position:
+// [<tool> <label>] <file>(<line>[,<col>]): <CODE> <message>
+const IDE_REGEX: &str = r"^\[(?P<tool>\w+)\s+(?P<kind>{label})\]\s+(?P<file>[^(]+?)[(](?P<line>\d+)(?:,(?P<column>\d+))?[)]:\s+(?P<code>[A-Z]\d+):?\s+(?P<message>.*)$";
+
+// Command-line dcc32 format, possibly indented:
+// <file>(<line>[,<col>]) <label>: <CODE> <message>
+const DCC_REGEX: &str = r"^\s*(?P<file>[^\s(][^(]*?)[(](?P<line>\d+)(?:,(?P<column>\d+))?[)]\s+(?P<kind>{label}):\s+(?P<code>[A-Z]\d+)\s+(?P<message>.*)$";
+
+// The formats below carry no source position.  Such diagnostics get line 0
+// and are attached to the project file when no file is known.
+
+// IDE-style tool prefix, e.g. from dcc32 or the C++ linker:
+// [<tool> <label>] [<label>: ][<CODE> ]<message>
+const TOOL_PREFIX_REGEX: &str = r"^\[(?P<tool>\w+)\s+(?P<kind>{label})\]\s+(?:(?:{label}):\s+)?(?:(?P<code>[A-Z]\d+)\s+)?(?P<message>.+)$";
 
-#[derive(Debug)]
+// Plain dcc32 format:
+// <label>: <CODE> <message>
+const DCC_NO_POSITION_REGEX: &str = r"^(?P<kind>{label}):\s+(?P<code>[A-Z]\d+)\s+(?P<message>.+)$";
+
+// A continuation line of the previous diagnostic, e.g. the candidates of an
+// ambiguous overloaded call:
+//   <file>(<line>[,<col>])[:] <message>
+const RELATED_REGEX: &str = r"^\s+(?P<file>[^\s(][^(]*?)[(](?P<line>\d+)(?:,(?P<column>\d+))?[)]:?\s+(?P<message>.+)$";
+
+// MSBuild format without a position (MSBuild's labels are never localized):
+// [<origin> : ](error|warning|fatal error) <CODE>: <message> [<project>]
+const MSBUILD_NO_POSITION_REGEX: &str = r"^(?:(?P<file>.*?)\s*:\s+)?(?P<kind>(?i:fatal\s+error|error|warning))\s+(?P<code>[A-Z]+\d+)\s*:\s+(?P<message>.*?)(?:\s+\[.*\])?$";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DiagnosticKind {
+    /// An error that stops the compiler, e.g. F1026 or F2613.
+    FATAL,
     ERROR,
     WARN,
     HINT,
 }
 
+impl DiagnosticKind {
+    /// Whether the diagnostic fails the build: errors and fatal errors.
+    pub fn is_error(self) -> bool {
+        matches!(self, DiagnosticKind::FATAL | DiagnosticKind::ERROR)
+    }
+}
+
+/// The labels a RAD Studio installation prints for each severity.
+struct Labels {
+    language: OutputLanguage,
+    fatal: &'static [&'static str],
+    error: &'static [&'static str],
+    warning: &'static [&'static str],
+    hint: &'static [&'static str],
+}
+
+const LABELS: &[Labels] = &[
+    Labels {
+        language: OutputLanguage::English,
+        fatal: &["Fatal Error", "Fatal"],
+        error: &["Error"],
+        warning: &["Warning"],
+        hint: &["Hint"],
+    },
+    Labels {
+        language: OutputLanguage::German,
+        fatal: &["Schwerwiegender Fehler", "Schwerwiegend"],
+        error: &["Fehler"],
+        warning: &["Warnung"],
+        hint: &["Hinweis"],
+    },
+    Labels {
+        language: OutputLanguage::French,
+        fatal: &["Erreur fatale", "Fatale"],
+        error: &["Erreur"],
+        warning: &["Avertissement"],
+        hint: &["Conseil"],
+    },
+    Labels {
+        language: OutputLanguage::Japanese,
+        fatal: &["致命的エラー", "致命的"],
+        error: &["エラー"],
+        warning: &["警告"],
+        hint: &["ヒント"],
+    },
+];
+
 impl Display for DiagnosticKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            DiagnosticKind::FATAL => write!(f, "FATAL"),
             DiagnosticKind::ERROR => write!(f, "ERROR"),
             DiagnosticKind::WARN => write!(f, "WARN"),
             DiagnosticKind::HINT => write!(f, "HINT"),
@@ -40,6 +127,102 @@ pub struct CompilerLineDiagnostic {
     pub code: String,
     pub kind: DiagnosticKind,
     pub compiler_name: String,
+    /// Locations named on the continuation lines of the diagnostic.
+    pub related: Vec<RelatedLocation>,
+}
+
+/// A location a diagnostic refers to on a continuation line, e.g. one of the
+/// candidates of an ambiguous overloaded call (E2251).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RelatedLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+/// Serializable form of a [`CompilerLineDiagnostic`], tagged with the project
+/// whose compilation produced it.  This is what `--json` consumers and the
+/// in-process compiler broadcast receive instead of the formatted line.
+///
+/// `line` is 0 for diagnostics without a source position, such as fatal
+/// errors, MSBuild and linker errors.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CompileDiagnostic {
+    pub project_id: usize,
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub code: String,
+    pub severity: DiagnosticKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<RelatedLocation>,
+}
+
+/// `file:line[:column]`, just `file` without a line, or nothing.
+fn location(file: &str, line: u32, column: Option<u32>) -> String {
+    match (line, column) {
+        (0, _) => file.to_string(),
+        (_, Some(column)) => format!("{file}:{line}:{column}"),
+        _ => format!("{file}:{line}"),
+    }
+}
+
+impl Display for CompileDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { severity, code, message, .. } = self;
+        match location(&self.file, self.line, self.column).as_str() {
+            "" => write!(f, "[{severity}][{code}] {message}"),
+            location => write!(f, "[{severity}][{code}] {location} - {message}"),
+        }
+    }
+}
+
+impl std::str::FromStr for DiagnosticKind {
+    type Err = anyhow::Error;
+
+    /// Parse "fatal", "error", "warning" (or "warn") and "hint", ignoring case.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "fatal" => Ok(DiagnosticKind::FATAL),
+            "error" => Ok(DiagnosticKind::ERROR),
+            "warning" | "warn" => Ok(DiagnosticKind::WARN),
+            "hint" => Ok(DiagnosticKind::HINT),
+            _ => anyhow::bail!("Unknown severity '{}'; expected fatal, error, warning or hint", value),
+        }
+    }
+}
+
+/// Number of diagnostics of a build by severity.  Fatal errors count as
+/// errors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DiagnosticCounts {
+    pub errors: usize,
+    pub warnings: usize,
+    pub hints: usize,
+}
+
+impl DiagnosticCounts {
+    pub fn add(&mut self, kind: DiagnosticKind) {
+        match kind {
+            DiagnosticKind::FATAL | DiagnosticKind::ERROR => self.errors += 1,
+            DiagnosticKind::WARN => self.warnings += 1,
+            DiagnosticKind::HINT => self.hints += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: DiagnosticCounts) {
+        self.errors += other.errors;
+        self.warnings += other.warnings;
+        self.hints += other.hints;
+    }
+}
+
+impl Display for DiagnosticCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} error(s), {} warning(s), {} hint(s)", self.errors, self.warnings, self.hints)
+    }
 }
 
 impl Display for CompilerLineDiagnostic {
@@ -47,19 +230,10 @@ impl Display for CompilerLineDiagnostic {
         let time = self.time.format("%H:%M:%S%.3f");
         let kind = &self.kind;
         let code = &self.code;
-        let file = &self.file;
-        let line = &self.line;
         let message = &self.message;
-        if let Some(column) = self.column {
-            write!(
-                f,
-                "{time}: [{kind}][{code}] {file}:{line}:{column} - {message}",
-            )
-        } else {
-            write!(
-                f,
-                "{time}: [{kind}][{code}] {file}:{line} - {message}"
-            )
+        match location(&self.file, self.line, self.column).as_str() {
+            "" => write!(f, "{time}: [{kind}][{code}] {message}"),
+            location => write!(f, "{time}: [{kind}][{code}] {location} - {message}"),
         }
     }
 }
@@ -68,77 +242,243 @@ lazy_static::lazy_static! {
     pub static ref COMPILER_OUTPUT_REGEX: regex::Regex = regex::Regex::new(MSBUILD_OUTPUT_REGEX).unwrap();
     static ref DELPHI2007_MSBUILD_OUTPUT_REGEX: regex::Regex = regex::Regex::new(DELPHI2007_MSBUILD_REGEX).unwrap();
     static ref DELPHI2007_SIMPLE_OUTPUT_REGEX: regex::Regex = regex::Regex::new(DELPHI2007_SIMPLE_REGEX).unwrap();
+    static ref MSBUILD_NO_POSITION_OUTPUT_REGEX: regex::Regex = regex::Regex::new(MSBUILD_NO_POSITION_REGEX).unwrap();
+    static ref RELATED_LINE_REGEX: regex::Regex = regex::Regex::new(RELATED_REGEX).unwrap();
+    static ref LOCALIZED_PATTERNS: HashMap<OutputLanguage, LocalizedPatterns> = OutputLanguage::ALL
+        .iter()
+        .map(|language| (*language, LocalizedPatterns::new(*language)))
+        .collect();
 }
 
-fn build_from_captures(captures: regex::Captures, compiler_name: String) -> Option<CompilerLineDiagnostic> {
-    let file = captures.name("file")?.as_str().trim().to_string();
-    let line_num = captures.name("line")?.as_str().parse().ok()?;
-    let column = captures
-        .name("column")
-        .and_then(|m| m.as_str().parse().ok());
-    let message = captures.name("message")?.as_str().to_string();
-    let code = captures.name("code")?.as_str().to_string();
-    let kind = if code.starts_with('H') {
-        DiagnosticKind::HINT
-    } else if code.starts_with('W') {
-        DiagnosticKind::WARN
-    } else {
-        DiagnosticKind::ERROR
-    };
-    Some(CompilerLineDiagnostic {
-        time: Local::now(),
-        file,
-        line: line_num,
-        column,
-        message,
-        code,
-        kind,
-        compiler_name,
-    })
+/// The labels and label-dependent formats of one output language.  English
+/// is always recognized, since MSBuild and some tools print English labels
+/// in every installation; `Auto` recognizes every language.
+struct LocalizedPatterns {
+    /// Labels in lower case with their severity, longest first.
+    labels: Vec<(String, DiagnosticKind)>,
+    ide: regex::Regex,
+    dcc: regex::Regex,
+    tool_prefix: regex::Regex,
+    dcc_no_position: regex::Regex,
+}
+
+impl LocalizedPatterns {
+    fn new(language: OutputLanguage) -> Self {
+        let mut labels: Vec<(String, DiagnosticKind)> = LABELS
+            .iter()
+            .filter(|labels| {
+                language.is_auto() || labels.language == OutputLanguage::English || labels.language == language
+            })
+            .flat_map(|labels| {
+                [
+                    (labels.fatal, DiagnosticKind::FATAL),
+                    (labels.error, DiagnosticKind::ERROR),
+                    (labels.warning, DiagnosticKind::WARN),
+                    (labels.hint, DiagnosticKind::HINT),
+                ]
+            })
+            .flat_map(|(words, kind)| words.iter().map(move |word| (word.to_lowercase(), kind)))
+            .collect();
+        labels.sort_by_key(|(label, _)| std::cmp::Reverse(label.chars().count()));
+        let alternation: Vec<String> = labels.iter().map(|(label, _)| regex::escape(label)).collect();
+        let label = format!("(?i:{})", alternation.join("|"));
+        let pattern = |template: &str| regex::Regex::new(&template.replace("{label}", &label)).unwrap();
+        LocalizedPatterns {
+            ide: pattern(IDE_REGEX),
+            dcc: pattern(DCC_REGEX),
+            tool_prefix: pattern(TOOL_PREFIX_REGEX),
+            dcc_no_position: pattern(DCC_NO_POSITION_REGEX),
+            labels,
+        }
+    }
+
+    /// Severity named by a label such as "Warnung:" or "fatal error".
+    fn label_kind(&self, label: &str) -> Option<DiagnosticKind> {
+        let label = label.trim().trim_end_matches(':').trim().to_lowercase();
+        self.labels.iter().find(|(known, _)| *known == label).map(|(_, kind)| *kind)
+    }
+
+    /// Severity of a diagnostic.  A fatal label or a Delphi F code makes it
+    /// fatal; otherwise the label wins, so that a warning reported as an
+    /// error stays an error.  Without a known label, Delphi codes tell the
+    /// severity by their letter (E2003, W1000, H2164).
+    fn kind(&self, code: &str, label: &str) -> DiagnosticKind {
+        let by_label = self.label_kind(label);
+        let mut chars = code.chars();
+        let letter = chars.next();
+        let by_code = match chars.as_str().bytes().all(|b| b.is_ascii_digit()) {
+            true => match letter {
+                Some('F') => Some(DiagnosticKind::FATAL),
+                Some('E') => Some(DiagnosticKind::ERROR),
+                Some('W') => Some(DiagnosticKind::WARN),
+                Some('H') => Some(DiagnosticKind::HINT),
+                _ => None,
+            },
+            false => None,
+        };
+        match (by_label, by_code) {
+            (Some(DiagnosticKind::FATAL), _) | (_, Some(DiagnosticKind::FATAL)) => DiagnosticKind::FATAL,
+            (Some(kind), _) | (None, Some(kind)) => kind,
+            _ => DiagnosticKind::ERROR,
+        }
+    }
+
+    fn build_from_captures(&self, captures: regex::Captures, compiler_name: String) -> Option<CompilerLineDiagnostic> {
+        let file = captures.name("file")?.as_str().trim().to_string();
+        let line_num = captures.name("line")?.as_str().parse().ok()?;
+        let column = captures
+            .name("column")
+            .and_then(|m| m.as_str().parse().ok());
+        let message = captures.name("message")?.as_str().to_string();
+        let code = captures.name("code")?.as_str().to_string();
+        let kind = self.kind(&code, captures.name("kind").map_or("", |m| m.as_str()));
+        Some(CompilerLineDiagnostic {
+            time: Local::now(),
+            file,
+            line: line_num,
+            column,
+            message,
+            code,
+            kind,
+            compiler_name,
+            related: Vec::new(),
+        })
+    }
+
+    /// Build a diagnostic without a source position.  The file is the origin
+    /// named by the line, if any; a line without a code is identified by the
+    /// tool that printed it, e.g. "ilink32".
+    fn build_without_position(&self, captures: regex::Captures, compiler_name: String) -> Option<CompilerLineDiagnostic> {
+        let code = captures
+            .name("code")
+            .or_else(|| captures.name("tool"))?
+            .as_str()
+            .to_string();
+        let message = captures.name("message")?.as_str().trim().to_string();
+        if message.is_empty() {
+            return None;
+        }
+        Some(CompilerLineDiagnostic {
+            time: Local::now(),
+            file: captures.name("file").map_or("", |m| m.as_str().trim()).to_string(),
+            line: 0,
+            column: None,
+            kind: self.kind(&code, captures.name("kind").map_or("", |m| m.as_str())),
+            message,
+            code,
+            compiler_name,
+            related: Vec::new(),
+        })
+    }
 }
 
 impl CompilerLineDiagnostic {
-    /// Try to parse a raw compiler output line into a [`CompilerLineDiagnostic`].
+    pub fn to_compile_diagnostic(&self, project_id: usize) -> CompileDiagnostic {
+        CompileDiagnostic {
+            project_id,
+            file: self.file.clone(),
+            line: self.line,
+            column: self.column,
+            code: self.code.clone(),
+            severity: self.kind,
+            message: self.message.clone(),
+            related: self.related.clone(),
+        }
+    }
+
+    /// Whether the diagnostic has a source position.
+    pub fn has_position(&self) -> bool {
+        self.line > 0
+    }
+
+    /// Try to parse a raw compiler output line into a [`CompilerLineDiagnostic`],
+    /// recognizing the labels of every supported language.
+    pub fn from_line(line: &str, compiler_name: String) -> Option<Self> {
+        Self::from_localized_line(line, compiler_name, OutputLanguage::Auto)
+    }
+
+    /// Try to parse a raw compiler output line printed in `language` into a
+    /// [`CompilerLineDiagnostic`].
     ///
-    /// Attempts three formats in order:
+    /// Attempts these formats in order:
     /// 1. Standard MSBuild / dcc32 format
     /// 2. Delphi 2007 Borland.Delphi.Targets MSBuild wrapper
     /// 3. Delphi 2007 indented simple / duplicate format
-    pub fn from_line(line: &str, compiler_name: String) -> Option<Self> {
-        if let Some(captures) = COMPILER_OUTPUT_REGEX.captures(line) {
-            return build_from_captures(captures, compiler_name);
+    /// 4. `[dcc32 Fehler] Unit1.pas(30): E2003 …` IDE-style format
+    /// 5. `Unit1.pas(30) Erreur: E2003 …` command-line dcc32 format
+    /// 6. `[dcc32 Error] E2202 …` and other tool-prefixed lines, without position
+    /// 7. `Fatal: F1026 …` and other dcc32 lines without position
+    /// 8. `MSBUILD : error MSB1009: …` and other MSBuild lines without position
+    pub fn from_localized_line(line: &str, compiler_name: String, language: OutputLanguage) -> Option<Self> {
+        let patterns = &LOCALIZED_PATTERNS[&language];
+        for regex in [&*COMPILER_OUTPUT_REGEX, &DELPHI2007_MSBUILD_OUTPUT_REGEX, &DELPHI2007_SIMPLE_OUTPUT_REGEX, &patterns.ide, &patterns.dcc] {
+            if let Some(captures) = regex.captures(line) {
+                return patterns.build_from_captures(captures, compiler_name);
+            }
         }
-        if let Some(captures) = DELPHI2007_MSBUILD_OUTPUT_REGEX.captures(line) {
-            return build_from_captures(captures, compiler_name);
-        }
-        if let Some(captures) = DELPHI2007_SIMPLE_OUTPUT_REGEX.captures(line) {
-            return build_from_captures(captures, compiler_name);
+        for regex in [&patterns.tool_prefix, &patterns.dcc_no_position, &*MSBUILD_NO_POSITION_OUTPUT_REGEX] {
+            if let Some(captures) = regex.captures(line) {
+                return patterns.build_without_position(captures, compiler_name);
+            }
         }
         None
     }
+
+    /// Parse an indented continuation line naming a location, e.g.
+    /// `  Unit1.pas(10): Related method: procedure Foo(Integer);`.  Only
+    /// meaningful right after a diagnostic.
+    pub fn related_from_line(line: &str) -> Option<RelatedLocation> {
+        let captures = RELATED_LINE_REGEX.captures(line)?;
+        Some(RelatedLocation {
+            file: captures.name("file")?.as_str().trim().to_string(),
+            line: captures.name("line")?.as_str().parse().ok()?,
+            column: captures.name("column").and_then(|m| m.as_str().parse().ok()),
+            message: captures.name("message")?.as_str().trim().to_string(),
+        })
+    }
+}
+
+/// One-character range at a 1-based `line` and `column`; line 0 is the
+/// start of the file.
+fn lsp_range(line: u32, column: Option<u32>) -> Range {
+    let line = line.saturating_sub(1);
+    let character = column.unwrap_or(1).saturating_sub(1);
+    Range {
+        start: Position { line, character },
+        end: Position { line, character: character + 1 },
+    }
 }
 
 impl Into<Diagnostic> for CompilerLineDiagnostic {
     fn into(self) -> Diagnostic {
+        // Locations without a file URL (relative paths) are left out.
+        let related: Vec<DiagnosticRelatedInformation> = self
+            .related
+            .iter()
+            .filter_map(|related| {
+                Some(DiagnosticRelatedInformation {
+                    location: Location {
+                        uri: Url::from_file_path(&related.file).ok()?,
+                        range: lsp_range(related.line, related.column),
+                    },
+                    message: related.message.clone(),
+                })
+            })
+            .collect();
         return Diagnostic {
-            range: Range {
-                start: Position {
-                    line: self.line.saturating_sub(1),
-                    character: self.column.unwrap_or(1).saturating_sub(1),
-                },
-                end: Position {
-                    line: self.line.saturating_sub(1),
-                    character: self.column.unwrap_or(1).saturating_sub(1) + 1,
-                },
-            },
+            range: lsp_range(self.line, self.column),
             severity: match self.kind {
-                DiagnosticKind::ERROR => Some(DiagnosticSeverity::ERROR),
+                DiagnosticKind::FATAL | DiagnosticKind::ERROR => Some(DiagnosticSeverity::ERROR),
                 DiagnosticKind::WARN => Some(DiagnosticSeverity::WARNING),
                 DiagnosticKind::HINT => Some(DiagnosticSeverity::HINT),
             },
             code: Some(NumberOrString::String(self.code.clone())),
+            code_description: DiagnosticExplanation::find(&self.code)
+                .and_then(|explanation| Url::parse(&explanation.docs_url()).ok())
+                .map(|href| CodeDescription { href }),
             source: Some(self.compiler_name.to_string()),
             message: self.message.clone(),
+            related_information: (!related.is_empty()).then_some(related),
             ..Default::default()
         };
     }
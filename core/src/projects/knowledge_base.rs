@@ -0,0 +1,93 @@
+//! Explanations of the Delphi compiler messages by code: what a message
+//! means, what usually causes it and how to fix it.  The catalogue is
+//! embedded from `presets/diagnostic_codes.ron` and shown on hover of a build
+//! diagnostic, by `ddk explain` and by the MCP tool
+//! `delphi_explain_diagnostic`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub(crate) const DIAGNOSTIC_CODES: &str = include_str!("presets/diagnostic_codes.ron");
+
+const DOCWIKI_SEARCH_URL: &str = "https://docwiki.embarcadero.com/RADStudio/en/Special:Search?search=";
+
+lazy_static::lazy_static! {
+    static ref EXPLANATIONS: Vec<DiagnosticExplanation> =
+        ron::from_str(DIAGNOSTIC_CODES).expect("Failed to parse the embedded diagnostic codes");
+}
+
+/// What a compiler message means, e.g. for W1035.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticExplanation {
+    /// E, W, H or F and the message number; X for messages whose severity
+    /// depends on the project's settings.
+    pub code: String,
+    /// The message as the compiler prints it, with "%s" for names.
+    pub title: String,
+    pub explanation: String,
+    pub causes: Vec<String>,
+    pub fixes: Vec<String>,
+}
+
+impl DiagnosticExplanation {
+    /// Explanation of `code`, ignoring case.  The number alone identifies a
+    /// Delphi message, so "H2269" finds X2269 and "E1012" finds X1012.
+    pub fn find(code: &str) -> Option<&'static DiagnosticExplanation> {
+        let code = code.trim();
+        EXPLANATIONS.iter().find(|explanation| explanation.code.eq_ignore_ascii_case(code)).or_else(|| {
+            let number = delphi_code_number(code)?;
+            EXPLANATIONS.iter().find(|explanation| delphi_code_number(&explanation.code) == Some(number))
+        })
+    }
+
+    /// Every explained message, fatal errors first.
+    pub fn all() -> &'static [DiagnosticExplanation] {
+        &EXPLANATIONS
+    }
+
+    /// Search for the code in the RAD Studio documentation.
+    pub fn docs_url(&self) -> String {
+        format!("{DOCWIKI_SEARCH_URL}{}", self.code)
+    }
+
+    /// The explanation as Markdown, for hovers.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("**{}** {}\n\n{}\n", self.code, self.title, self.explanation);
+        for (heading, items) in [("Common causes", &self.causes), ("Fixes", &self.fixes)] {
+            if !items.is_empty() {
+                markdown.push_str(&format!("\n**{heading}**\n\n"));
+                for item in items {
+                    markdown.push_str(&format!("- {item}\n"));
+                }
+            }
+        }
+        markdown.push_str(&format!("\n[RAD Studio documentation]({})\n", self.docs_url()));
+        markdown
+    }
+}
+
+/// Number of a Delphi message code: one of E, W, H, F or X and four digits.
+fn delphi_code_number(code: &str) -> Option<&str> {
+    let (letter, number) = code.split_at_checked(1)?;
+    let delphi = ["E", "W", "H", "F", "X"].iter().any(|prefix| letter.eq_ignore_ascii_case(prefix));
+    (delphi && number.len() == 4 && number.bytes().all(|b| b.is_ascii_digit())).then_some(number)
+}
+
+impl fmt::Display for DiagnosticExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {}", self.code, self.title)?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.explanation)?;
+        for (heading, items) in [("Common causes", &self.causes), ("Fixes", &self.fixes)] {
+            if !items.is_empty() {
+                writeln!(f)?;
+                writeln!(f, "{heading}:")?;
+                for item in items {
+                    writeln!(f, "  - {item}")?;
+                }
+            }
+        }
+        writeln!(f)?;
+        writeln!(f, "Documentation: {}", self.docs_url())
+    }
+}
@@ -6,6 +6,9 @@ mod project;
 mod group_project;
 mod file_watch;
 mod diag;
+mod knowledge_base;
+mod quick_fix;
+mod warning_policy;
 mod compiler;
 
 use anyhow::Result;
@@ -21,6 +24,9 @@ pub use project::*;
 pub use group_project::*;
 pub use file_watch::*;
 pub use diag::*;
+pub use knowledge_base::*;
+pub use quick_fix::*;
+pub use warning_policy::*;
 pub use compiler::*;
 
 pub trait Named {
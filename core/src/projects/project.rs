@@ -64,6 +64,9 @@ pub struct Project {
     /// Per-project build platform override (e.g. "Win32", "Win64").
     /// `None` means use the `.dproj` file default.
     pub active_platform: Option<String>,
+    /// Warning policy of the project, on top of its workspace's.
+    #[serde(skip_serializing_if = "WarningPolicy::is_empty")]
+    pub warning_policy: WarningPolicy,
 }
 
 impl Default for Project {
@@ -79,6 +82,7 @@ impl Default for Project {
             ini: None,
             active_configuration: None,
             active_platform: None,
+            warning_policy: WarningPolicy::default(),
         }
     }
 }
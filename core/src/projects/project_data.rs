@@ -134,10 +134,37 @@ impl ProjectsData {
         Ok(())
     }
 
+    fn validate_dependencies(&self) -> Result<()> {
+        for workspace in &self.workspaces {
+            let linked = |project_id: usize| workspace.project_links.iter().any(|link| link.project_id == project_id);
+            for dependency in &workspace.dependencies {
+                if !linked(dependency.project_id) || !linked(dependency.depends_on) {
+                    anyhow::bail!(
+                        "Workspace '{}' has a dependency between projects {} and {} that are not both part of it",
+                        workspace.name, dependency.project_id, dependency.depends_on
+                    );
+                }
+            }
+            let cyclic = workspace.dependency_graph().cyclic();
+            if !cyclic.is_empty() {
+                let names: Vec<String> = cyclic
+                    .iter()
+                    .map(|&index| {
+                        let project_id = workspace.project_links[index].project_id;
+                        self.get_project(project_id).map_or_else(|| project_id.to_string(), |p| p.name.clone())
+                    })
+                    .collect();
+                anyhow::bail!("Workspace '{}' has a dependency cycle between projects {}", workspace.name, names.join(", "));
+            }
+        }
+        Ok(())
+    }
+
     pub async fn validate(&self) -> Result<()> {
         self.validate_compilers().await?;
         let id_map = self.get_id_map()?;
         self.validate_project_references(&id_map)?;
+        self.validate_dependencies()?;
         let mut workspace_names: HashSet<&String> = HashSet::new();
         for workspace in &self.workspaces {
             if workspace_names.contains(&workspace.name) {
@@ -196,6 +223,7 @@ impl ProjectsData {
                     ini: None,
                     active_configuration: None,
                     active_platform: None,
+                    warning_policy: WarningPolicy::default(),
                 }
             },
             Some(ext) if ext == "dpr" => {
@@ -210,6 +238,7 @@ impl ProjectsData {
                     ini: None,
                     active_configuration: None,
                     active_platform: None,
+                    warning_policy: WarningPolicy::default(),
                 }
             },
             Some(ext) if ext == "dpk" => {
@@ -224,6 +253,7 @@ impl ProjectsData {
                     ini: None,
                     active_configuration: None,
                     active_platform: None,
+                    warning_policy: WarningPolicy::default(),
                 }
             },
             _ => {
@@ -277,6 +307,7 @@ impl ProjectsData {
         if remove_links {
             for workspace in &mut self.workspaces {
                 workspace.project_links.retain(|link| link.project_id != project_id);
+                workspace.prune_dependencies();
             }
             if let Some(group_project) = &mut self.group_project {
                 group_project.project_links.retain(|link| link.project_id != project_id);
@@ -290,6 +321,7 @@ impl ProjectsData {
             if let Some(pos) = workspace.project_links.iter().position(|link| link.id == project_link_id) {
                 project_id = Some(workspace.project_links[pos].project_id);
                 workspace.project_links.remove(pos);
+                workspace.prune_dependencies();
                 break;
             }
         }
@@ -333,6 +365,7 @@ impl ProjectsData {
                     return source_workspace.move_project_link(project_link_id, target_link_id);
                 } else {
                     let link = source_workspace.export_project_link(project_link_id)?;
+                    source_workspace.prune_dependencies();
                     let target_workspace = self.get_workspace_mut(target_workspace_id)
                         .ok_or_else(|| anyhow::anyhow!("Unable to find target workspace"))?;
                     target_workspace.import_project_link(link, target_link_id)?;
@@ -357,6 +390,15 @@ impl ProjectsData {
         return None;
     }
 
+    /// Warning policy of `project` when built from workspace `workspace_id`:
+    /// the workspace's policy refined by the project's.
+    pub fn warning_policy(&self, workspace_id: Option<usize>, project: &Project) -> WarningPolicy {
+        match workspace_id.and_then(|id| self.get_workspace(id)) {
+            Some(workspace) => workspace.warning_policy.with_project(&project.warning_policy),
+            _ => project.warning_policy.clone(),
+        }
+    }
+
     pub fn is_project_link_in_group_project(&self, project_link_id: usize) -> bool {
         if let Some(group_project) = &self.group_project {
             if group_project.project_links.iter().any(|link| link.id == project_link_id) {
@@ -521,6 +563,54 @@ impl ProjectsData {
         return Ok(());
     }
 
+    /// Declare that `project_id` depends on `depends_on` within the
+    /// workspace.  Both projects have to be part of the workspace and the
+    /// dependency must not close a cycle.
+    pub fn add_project_dependency(&mut self, workspace_id: usize, project_id: usize, depends_on: usize) -> Result<()> {
+        if project_id == depends_on {
+            anyhow::bail!("Project {} cannot depend on itself", project_id);
+        }
+        let workspace = match self.get_workspace(workspace_id) {
+            Some(ws) => ws,
+            _ => anyhow::bail!("Workspace with id {} not found", workspace_id),
+        };
+        for id in [project_id, depends_on] {
+            if !workspace.project_links.iter().any(|link| link.project_id == id) {
+                anyhow::bail!("Project with id {} is not part of workspace '{}'", id, workspace.name);
+            }
+        }
+        let dependency = ProjectDependency { project_id, depends_on };
+        if workspace.dependencies.contains(&dependency) {
+            return Ok(());
+        }
+        let mut updated = workspace.clone();
+        updated.dependencies.push(dependency);
+        if updated.transitive_dependencies(depends_on).contains(&project_id) {
+            let name = |id: usize| self.get_project(id).map_or_else(|| id.to_string(), |p| p.name.clone());
+            anyhow::bail!(
+                "Cannot make {} depend on {}: {} already depends on {}",
+                name(project_id), name(depends_on), name(depends_on), name(project_id)
+            );
+        }
+        if let Some(workspace) = self.get_workspace_mut(workspace_id) {
+            *workspace = updated;
+        }
+        return Ok(());
+    }
+
+    pub fn remove_project_dependency(&mut self, workspace_id: usize, project_id: usize, depends_on: usize) -> Result<()> {
+        let workspace = match self.get_workspace_mut(workspace_id) {
+            Some(ws) => ws,
+            _ => anyhow::bail!("Workspace with id {} not found", workspace_id),
+        };
+        let count = workspace.dependencies.len();
+        workspace.dependencies.retain(|d| d.project_id != project_id || d.depends_on != depends_on);
+        if workspace.dependencies.len() == count {
+            anyhow::bail!("Project {} does not depend on project {} in workspace '{}'", project_id, depends_on, workspace.name);
+        }
+        return Ok(());
+    }
+
     pub fn set_group_project(&mut self, groupproj_path: &String) -> Result<()> {
         let path = PathBuf::from(groupproj_path);
         if !path.exists() {
@@ -0,0 +1,644 @@
+//! Quick fixes (`textDocument/codeAction`) for the compiler diagnostics
+//! published during a build:
+//!
+//! * E2003 undeclared identifier: add the unit declaring it to `uses`, found
+//!   among well-known RTL / VCL units and the units of the projects the file
+//!   belongs to.
+//! * H2443 inline function not expanded: add the unit named in the message.
+//! * H2164 unused variable: remove its declaration.
+//! * W1057 implicit string cast: cast the assigned value explicitly.
+//!
+//! The source is read with a small tokenizer that skips comments, compiler
+//! directives and string literals; it does not parse Pascal.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::*;
+
+use crate::files::main_source_unit_paths;
+use crate::state::PROJECTS_DATA;
+use crate::utils::normalize_path;
+
+/// Units declaring identifiers one uses all the time.  Identifiers declared
+/// by more than one of these units (`TList`, `TRect`, `IfThen`, …) are left
+/// out on purpose.
+const KNOWN_UNITS: &[(&str, &[&str])] = &[
+    ("System.SysUtils", &[
+        "Abort", "AnsiLowerCase", "AnsiSameText", "AnsiUpperCase", "BoolToStr", "ChangeFileExt", "CharInSet",
+        "CompareStr", "CompareText", "CreateDir", "Date", "DateTimeToStr", "DateToStr", "DecodeDate", "DecodeTime",
+        "DeleteFile", "DirectoryExists", "EAbort", "EArgumentException", "EConvertError", "EOSError", "EmptyStr",
+        "EncodeDate", "EncodeTime", "Exception", "ExcludeTrailingPathDelimiter", "ExtractFileExt",
+        "ExtractFileName", "ExtractFilePath", "FileAge", "FileExists", "FindClose", "FindFirst", "FindNext",
+        "FloatToStr", "ForceDirectories", "Format", "FormatDateTime", "FormatSettings", "FreeAndNil",
+        "GetCurrentDir", "GetEnvironmentVariable", "IncMonth", "IncludeTrailingPathDelimiter", "IntToHex",
+        "IntToStr", "LastDelimiter", "LowerCase", "Now", "PathDelim", "QuotedStr", "RaiseLastOSError",
+        "RemoveDir", "RenameFile", "SameText", "SetCurrentDir", "Sleep", "StrToBool", "StrToDate",
+        "StrToDateTime", "StrToFloat", "StrToInt", "StrToIntDef", "StringReplace", "Supports",
+        "SysErrorMessage", "TEncoding", "TFormatSettings", "TFunc", "TPredicate", "TProc", "TSearchRec", "Time",
+        "Trim", "TrimLeft", "TrimRight", "TryStrToDate", "TryStrToFloat", "TryStrToInt", "UpperCase", "WrapText",
+        "faAnyFile", "faDirectory", "rfIgnoreCase", "rfReplaceAll",
+    ]),
+    ("System.Classes", &[
+        "FindClass", "RegisterClass", "TBinaryReader", "TBinaryWriter", "TCollection", "TCollectionItem",
+        "TComponent", "TFileStream", "TInterfacedPersistent", "TMemoryStream", "TNotifyEvent", "TPersistent",
+        "TResourceStream", "TShiftState", "TStream", "TStreamReader", "TStreamWriter", "TStringList",
+        "TStringReader", "TStringStream", "TStringWriter", "TStrings", "TThread", "fmCreate", "fmOpenRead",
+        "fmOpenReadWrite", "fmShareDenyWrite",
+    ]),
+    ("System.Math", &[
+        "Ceil", "DivMod", "EnsureRange", "Floor", "InRange", "Infinity", "IsInfinite", "IsNan", "Log10", "Log2",
+        "Max", "MaxIntValue", "Mean", "Min", "MinIntValue", "NaN", "Power", "RoundTo", "Sign", "SimpleRoundTo",
+        "Sum",
+    ]),
+    ("System.StrUtils", &[
+        "ContainsStr", "ContainsText", "DupeString", "EndsStr", "EndsText", "IndexStr", "IndexText", "LeftStr",
+        "MatchStr", "MatchText", "MidStr", "PosEx", "ReplaceStr", "ReplaceText", "ReverseString", "RightStr",
+        "SplitString", "StartsStr", "StartsText",
+    ]),
+    ("System.DateUtils", &[
+        "CompareDate", "DateOf", "DateTimeToUnix", "DateToISO8601", "DayOf", "DaysBetween", "DaysInMonth",
+        "EndOfTheDay", "EndOfTheMonth", "HourOf", "HoursBetween", "ISO8601ToDate", "IncDay", "IncHour",
+        "IncMinute", "IncSecond", "IncYear", "IsToday", "MilliSecondsBetween", "MinuteOf", "MinutesBetween",
+        "MonthOf", "SameDate", "SecondOf", "SecondsBetween", "StartOfTheDay", "StartOfTheMonth", "TimeOf",
+        "UnixToDateTime", "YearOf",
+    ]),
+    ("System.Generics.Collections", &[
+        "TDictionary", "TEnumerable", "TObjectDictionary", "TObjectQueue", "TObjectStack", "TPair", "TQueue",
+        "TStack", "TThreadedQueue",
+    ]),
+    ("System.Generics.Defaults", &["IComparer", "IEqualityComparer", "TComparer", "TDelegatedComparer", "TEqualityComparer"]),
+    ("System.IOUtils", &["TDirectory", "TFile", "TPath"]),
+    ("System.RegularExpressions", &["TGroup", "TMatch", "TMatchCollection", "TRegEx"]),
+    ("System.JSON", &[
+        "TJSONArray", "TJSONBool", "TJSONNull", "TJSONNumber", "TJSONObject", "TJSONPair", "TJSONString",
+        "TJSONValue",
+    ]),
+    ("System.Threading", &["IFuture", "ITask", "TParallel", "TTask", "TThreadPool"]),
+    ("System.SyncObjs", &[
+        "TCountdownEvent", "TCriticalSection", "TEvent", "TInterlocked", "TLightweightEvent", "TMutex",
+        "TSemaphore", "TSpinLock",
+    ]),
+    ("System.Diagnostics", &["TStopwatch"]),
+    ("System.Rtti", &["TRttiContext", "TRttiField", "TRttiMethod", "TRttiProperty", "TRttiType", "TValue"]),
+    ("System.TypInfo", &["GetEnumName", "GetEnumValue", "GetPropInfo", "GetPropValue", "PTypeInfo", "SetPropValue"]),
+    ("System.Variants", &["Null", "Unassigned", "VarArrayCreate", "VarIsClear", "VarIsEmpty", "VarIsNull", "VarToStr", "VarToStrDef"]),
+    ("System.NetEncoding", &["TBase64Encoding", "TNetEncoding", "TURLEncoding"]),
+    ("System.Hash", &["THashBobJenkins", "THashMD5", "THashSHA1", "THashSHA2"]),
+    ("System.Zip", &["TZipFile"]),
+    ("System.TimeSpan", &["TTimeSpan"]),
+    ("System.IniFiles", &["TCustomIniFile", "TIniFile", "TMemIniFile"]),
+    ("System.Win.Registry", &["TRegIniFile", "TRegistry"]),
+    ("System.Win.ComObj", &["CreateComObject", "CreateOleObject", "EOleException", "GetActiveOleObject", "OleCheck"]),
+    ("System.Net.HttpClient", &["IHTTPResponse", "THTTPClient"]),
+    ("System.UITypes", &[
+        "TAlphaColor", "TAlphaColors", "TMsgDlgBtn", "TMsgDlgType", "mbCancel", "mbNo", "mbOK", "mbYes",
+        "mrCancel", "mrNo", "mrOk", "mrYes", "mtConfirmation", "mtError", "mtInformation", "mtWarning",
+    ]),
+    ("System.Types", &["TPointF", "TRectF", "TSizeF"]),
+    ("Winapi.Windows", &[
+        "BOOL", "CloseHandle", "CreateFile", "DWORD", "FindWindow", "FreeLibrary", "GetCurrentProcessId",
+        "GetCurrentThreadId", "GetLastError", "GetModuleHandle", "GetProcAddress", "GetTickCount", "HWND",
+        "INFINITE", "LoadLibrary", "MAX_PATH", "MessageBox", "OutputDebugString", "PostMessage", "SW_HIDE",
+        "SW_SHOW", "SendMessage", "WaitForSingleObject",
+    ]),
+    ("Winapi.Messages", &["WM_CLOSE", "WM_PAINT", "WM_SETTEXT", "WM_USER"]),
+    ("Winapi.ShellAPI", &["SHFileOperation", "ShellExecute", "ShellExecuteEx", "TShellExecuteInfo"]),
+    ("Winapi.ActiveX", &["CoInitialize", "CoInitializeEx", "CoUninitialize"]),
+    ("Data.DB", &["EDatabaseError", "TDataSet", "TDataSource", "TField", "TFieldType", "ftInteger", "ftString"]),
+    ("Xml.XMLDoc", &["LoadXMLDocument", "NewXMLDocument", "TXMLDocument"]),
+    ("Xml.XMLIntf", &["IXMLDocument", "IXMLNode"]),
+    ("Vcl.Dialogs", &[
+        "InputBox", "InputQuery", "MessageDlg", "ShowMessage", "ShowMessageFmt", "TOpenDialog", "TSaveDialog",
+        "TTaskDialog",
+    ]),
+    ("Vcl.Forms", &["Application", "Screen", "TApplication", "TForm", "TFrame", "TScreen"]),
+    ("Vcl.Controls", &["TControl", "TWinControl"]),
+    ("Vcl.StdCtrls", &["TButton", "TCheckBox", "TComboBox", "TEdit", "TGroupBox", "TLabel", "TListBox", "TMemo", "TRadioButton"]),
+    ("Vcl.ExtCtrls", &["TBevel", "TImage", "TPanel", "TRadioGroup", "TShape", "TSplitter", "TTimer"]),
+    ("Vcl.ComCtrls", &[
+        "TDateTimePicker", "TListView", "TPageControl", "TProgressBar", "TRichEdit", "TStatusBar", "TTabSheet",
+        "TTrackBar", "TTreeView",
+    ]),
+    ("Vcl.Graphics", &["TBitmap", "TBrush", "TCanvas", "TFont", "TPen", "TPicture", "clBlack", "clBlue", "clGreen", "clRed", "clWhite", "clYellow"]),
+    ("Vcl.Clipbrd", &["Clipboard"]),
+    ("Vcl.Menus", &["TMainMenu", "TMenuItem", "TPopupMenu"]),
+    ("Vcl.ActnList", &["TAction", "TActionList"]),
+    ("Vcl.Grids", &["TDrawGrid", "TStringGrid"]),
+    ("Vcl.Buttons", &["TBitBtn", "TSpeedButton"]),
+];
+
+/// How many units are offered for an undeclared identifier.
+const MAX_UNIT_SUGGESTIONS: usize = 5;
+
+lazy_static::lazy_static! {
+    static ref QUOTED_REGEX: regex::Regex = regex::Regex::new(r"'([^']+)'").unwrap();
+    /// A variable declaration taking up a whole line, e.g. `  A, B: Integer;`.
+    static ref VARIABLE_LINE_REGEX: regex::Regex = regex::Regex::new(
+        r"(?i)^(?P<indent>\s*)(?P<var>var\s+)?(?P<names>[\w&]+(?:\s*,\s*[\w&]+)*)\s*:\s*[^;=]+?;\s*(?://.*)?$"
+    ).unwrap();
+    /// The start of a declaration in a `var` block.
+    static ref DECLARATION_START_REGEX: regex::Regex =
+        regex::Regex::new(r"^\s*[\w&]+(?:\s*,\s*[\w&]+)*\s*:[^=]").unwrap();
+}
+
+/// Quick fixes for `diagnostics` of the document `uri` with the text
+/// `source`.  Undeclared identifiers are also looked up in the units next to
+/// the document and in the units of the projects it belongs to.
+pub async fn code_actions(uri: &Url, source: &str, diagnostics: &[Diagnostic]) -> Vec<CodeActionOrCommand> {
+    let needs_units = diagnostics.iter().any(|diagnostic| diagnostic_code(diagnostic) == Some("E2003"));
+    let units = match uri.to_file_path() {
+        Ok(file) if needs_units => source_units(&file).await,
+        _ => Vec::new(),
+    };
+    quick_fixes(uri, source, diagnostics, &units)
+        .into_iter()
+        .map(CodeActionOrCommand::CodeAction)
+        .collect()
+}
+
+/// Quick fixes for `diagnostics` of the document `uri` with the text
+/// `source`; undeclared identifiers are looked up in the well-known units and
+/// in the unit files `units`.
+pub fn quick_fixes(uri: &Url, source: &str, diagnostics: &[Diagnostic], units: &[PathBuf]) -> Vec<CodeAction> {
+    let mut actions = Vec::new();
+    for diagnostic in diagnostics {
+        let line = diagnostic.range.start.line;
+        let fixes: Vec<(String, Vec<TextEdit>)> = match diagnostic_code(diagnostic) {
+            Some("E2003") => quoted(&diagnostic.message, 0)
+                .map(|identifier| {
+                    declaring_units(source, identifier, units)
+                        .into_iter()
+                        .filter_map(|unit| add_unit(source, line, &unit).map(|edit| (add_unit_title(&unit), vec![edit])))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Some("H2443") => quoted(&diagnostic.message, 1)
+                .and_then(|unit| add_unit(source, line, unit).map(|edit| (add_unit_title(unit), vec![edit])))
+                .into_iter()
+                .collect(),
+            Some("H2164") => quoted(&diagnostic.message, 0)
+                .and_then(|name| {
+                    remove_variable(source, line, name).map(|edits| (format!("Remove unused variable '{name}'"), edits))
+                })
+                .into_iter()
+                .collect(),
+            Some("W1057") => quoted(&diagnostic.message, 1)
+                .and_then(|target| {
+                    cast_assigned_value(source, line, target)
+                        .map(|edits| (format!("Cast to '{target}' explicitly"), edits))
+                })
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        };
+        let preferred = fixes.len() == 1;
+        for (title, edits) in fixes {
+            actions.push(CodeAction {
+                title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), edits)])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(preferred),
+                ..Default::default()
+            });
+        }
+    }
+    actions
+}
+
+fn diagnostic_code(diagnostic: &Diagnostic) -> Option<&str> {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => Some(code.as_str()),
+        _ => None,
+    }
+}
+
+/// The `index`th name quoted in a compiler message, e.g. 'Foo' of
+/// "Undeclared identifier: 'Foo'".
+fn quoted(message: &str, index: usize) -> Option<&str> {
+    QUOTED_REGEX.captures_iter(message).nth(index).and_then(|captures| captures.get(1)).map(|m| m.as_str())
+}
+
+fn add_unit_title(unit: &str) -> String {
+    format!("Add '{unit}' to the uses clause")
+}
+
+// ---------------------------------------------------------------------------
+// Tokens
+// ---------------------------------------------------------------------------
+
+/// A word (identifier, keyword or number) or a symbol of Pascal source.
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl Token<'_> {
+    fn is(&self, word: &str) -> bool {
+        self.text.eq_ignore_ascii_case(word)
+    }
+}
+
+/// Words and symbols of `source`, without comments, compiler directives and
+/// string literals.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let skip_to = |from: usize, end: &str| source[from..].find(end).map_or(bytes.len(), |i| from + i + end.len());
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80;
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b'{' => i = skip_to(i + 1, "}"),
+            b'(' if bytes.get(i + 1) == Some(&b'*') => i = skip_to(i + 2, "*)"),
+            b'/' if bytes.get(i + 1) == Some(&b'/') => i = skip_to(i, "\n"),
+            b'\'' => {
+                i += 1;
+                while i < bytes.len() {
+                    i += 1;
+                    if bytes[i - 1] == b'\'' {
+                        if bytes.get(i) != Some(&b'\'') {
+                            break;
+                        }
+                        i += 1;
+                    }
+                }
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            b if is_word(b) => {
+                while i < bytes.len() && is_word(bytes[i]) {
+                    i += 1;
+                }
+                tokens.push(Token { text: &source[start..i], start, end: i });
+            }
+            _ => {
+                let pair = source.get(i..i + 2).unwrap_or_default();
+                i += if [":=", "<=", ">=", "<>"].contains(&pair) { 2 } else { 1 };
+                tokens.push(Token { text: &source[start..i], start, end: i });
+            }
+        }
+    }
+    tokens
+}
+
+/// Name of a dotted unit name starting at `tokens[i]`, and the index after it.
+fn dotted_name(tokens: &[Token], mut i: usize) -> (String, usize) {
+    let mut name = tokens[i].text.to_string();
+    while tokens.get(i + 1).is_some_and(|t| t.text == ".") && tokens.get(i + 2).is_some() {
+        name.push('.');
+        name.push_str(tokens[i + 2].text);
+        i += 2;
+    }
+    (name, i + 1)
+}
+
+/// A `uses` clause: its units and the offset of its closing semicolon.
+struct UsesClause {
+    units: Vec<String>,
+    end: usize,
+}
+
+fn uses_clause(tokens: &[Token], uses: usize) -> Option<UsesClause> {
+    let mut units = Vec::new();
+    let mut i = uses + 1;
+    while i < tokens.len() {
+        let (unit, next) = dotted_name(tokens, i);
+        units.push(unit);
+        // Skip `in 'path'` up to the separator.
+        i = next;
+        while i < tokens.len() && ![",", ";"].contains(&tokens[i].text) {
+            i += 1;
+        }
+        match tokens.get(i) {
+            Some(token) if token.text == ";" => return Some(UsesClause { units, end: token.start }),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Every unit in a `uses` clause of `source`, also in inactive conditional
+/// code.
+pub(crate) fn used_unit_names(source: &str) -> Vec<String> {
+    used_units(&tokenize(source))
+}
+
+/// Every unit in a `uses` clause of the source.
+fn used_units(tokens: &[Token]) -> Vec<String> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| token.is("uses"))
+        .filter_map(|(i, _)| uses_clause(tokens, i))
+        .flat_map(|clause| clause.units)
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Positions
+// ---------------------------------------------------------------------------
+
+fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+fn range(source: &str, start: usize, end: usize) -> Range {
+    Range { start: position(source, start), end: position(source, end) }
+}
+
+/// Byte offsets of the 0-based `line`: its start, its end without the line
+/// break, and the start of the next line.
+fn line_bounds(source: &str, line: u32) -> Option<(usize, usize, usize)> {
+    let mut start = 0;
+    for _ in 0..line {
+        start += source[start..].find('\n')? + 1;
+    }
+    let next = source[start..].find('\n').map_or(source.len(), |i| start + i + 1);
+    let end = source[..next].trim_end_matches(['\r', '\n']).len().max(start);
+    Some((start, end, next))
+}
+
+fn line_text(source: &str, line: u32) -> Option<&str> {
+    line_bounds(source, line).map(|(start, end, _)| &source[start..end])
+}
+
+fn line_break(source: &str) -> &'static str {
+    if source.contains("\r\n") { "\r\n" } else { "\n" }
+}
+
+// ---------------------------------------------------------------------------
+// Adding units
+// ---------------------------------------------------------------------------
+
+/// Edit adding `unit` to the uses clause used by the 0-based `line`: the
+/// interface or implementation clause of a unit, the only clause of a
+/// program or library.  None if the unit is used already.
+fn add_unit(source: &str, line: u32, unit: &str) -> Option<TextEdit> {
+    let tokens = tokenize(source);
+    if used_units(&tokens).iter().any(|used| used.eq_ignore_ascii_case(unit)) {
+        return None;
+    }
+    let first = tokens.first()?;
+    let section = if first.is("unit") {
+        let interface = tokens.iter().position(|t| t.is("interface"))?;
+        let used_at = line_bounds(source, line).map_or(0, |(start, ..)| start);
+        match tokens.iter().position(|t| t.is("implementation")) {
+            Some(implementation) if used_at >= tokens[implementation].start => implementation,
+            _ => interface,
+        }
+    } else if first.is("program") || first.is("library") {
+        match tokens.iter().position(|t| t.is("uses")) {
+            Some(uses) => uses - 1,
+            // After the header's semicolon.
+            _ => tokens.iter().position(|t| t.text == ";")?,
+        }
+    } else {
+        return None;
+    };
+    let offset = match tokens.get(section + 1) {
+        Some(token) if token.is("uses") => {
+            let clause = uses_clause(&tokens, section + 1)?;
+            return Some(TextEdit { range: range(source, clause.end, clause.end), new_text: format!(", {unit}") });
+        }
+        _ => tokens[section].end,
+    };
+    let nl = line_break(source);
+    Some(TextEdit { range: range(source, offset, offset), new_text: format!("{nl}{nl}uses{nl}  {unit};") })
+}
+
+/// Units that declare `identifier` and the source does not use yet: the
+/// well-known unit first, then the ones of `units` declaring it in their
+/// interface section.
+fn declaring_units(source: &str, identifier: &str, units: &[PathBuf]) -> Vec<String> {
+    let used = used_units(&tokenize(source));
+    let is_used = |unit: &str| used.iter().any(|u| u.eq_ignore_ascii_case(unit));
+    // Projects written without unit scope names use `SysUtils`, not `System.SysUtils`.
+    let unscoped = used.iter().any(|u| {
+        KNOWN_UNITS.iter().any(|(known, _)| known.split_once('.').is_some_and(|(_, short)| short.eq_ignore_ascii_case(u)))
+    });
+    let firemonkey = used.iter().any(|u| u.to_ascii_lowercase().starts_with("fmx."));
+    let mut found: Vec<String> = Vec::new();
+    if let Some((unit, _)) = KNOWN_UNITS.iter().find(|(unit, identifiers)| {
+        !(firemonkey && unit.starts_with("Vcl.")) && identifiers.iter().any(|known| known.eq_ignore_ascii_case(identifier))
+    }) {
+        let unit = if unscoped { unit.split_once('.').map_or(*unit, |(_, short)| short) } else { unit };
+        if is_used(unit) {
+            return found;
+        }
+        found.push(unit.to_string());
+    }
+    let lowercase = identifier.to_lowercase();
+    for path in units {
+        let Ok(raw) = std::fs::read(path) else { continue };
+        let (content, _) = crate::encoding::decode_source(&raw);
+        if !content.to_lowercase().contains(&lowercase) {
+            continue;
+        }
+        let tokens = tokenize(&content);
+        if let Some(unit) = unit_name(&tokens)
+            && !is_used(&unit)
+            && !found.iter().any(|f| f.eq_ignore_ascii_case(&unit))
+            && declares(&tokens, identifier)
+        {
+            found.push(unit);
+            if found.len() == MAX_UNIT_SUGGESTIONS {
+                break;
+            }
+        }
+    }
+    found
+}
+
+/// Name of the unit from its `unit Name;` header.
+fn unit_name(tokens: &[Token]) -> Option<String> {
+    (tokens.first()?.is("unit") && tokens.len() > 1).then(|| dotted_name(tokens, 1).0)
+}
+
+/// Whether the interface section of a unit declares `identifier` outside of
+/// a class, record or interface type: as a type, constant, variable,
+/// routine or enumeration value.
+fn declares(tokens: &[Token], identifier: &str) -> bool {
+    let Some(start) = tokens.iter().position(|t| t.is("interface")) else {
+        return false;
+    };
+    let end = tokens.iter().position(|t| t.is("implementation")).unwrap_or(tokens.len());
+    let section = &tokens[start + 1..end];
+    let mut bodies = 0usize;
+    let mut brackets = 0usize;
+    let mut enumeration = false;
+    let mut in_uses = false;
+    for (i, token) in section.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|j| section[j]);
+        let next = section.get(i + 1);
+        match token.text {
+            "(" | "[" => {
+                enumeration |= brackets == 0 && token.text == "(" && previous.is_some_and(|p| p.text == "=");
+                brackets += 1;
+                continue;
+            }
+            ")" | "]" => {
+                brackets = brackets.saturating_sub(1);
+                enumeration &= brackets > 0;
+                continue;
+            }
+            _ => {}
+        }
+        if in_uses || token.is("uses") {
+            in_uses = token.text != ";";
+            continue;
+        }
+        if enumeration && brackets == 1 && token.is(identifier) && next.is_some_and(|n| [",", ")", "="].contains(&n.text)) {
+            return true;
+        }
+        if brackets > 0 {
+            continue;
+        }
+        if token.is("end") {
+            bodies = bodies.saturating_sub(1);
+        } else if opens_body(section, i) {
+            bodies += 1;
+        } else if bodies == 0 && token.is(identifier) {
+            let routine = previous.is_some_and(|p| p.is("procedure") || p.is("function"));
+            let generic = next.is_some_and(|n| n.text == "<")
+                && section[i..].iter().position(|t| t.text == ">").is_some_and(|j| section.get(i + j + 1).is_some_and(|t| t.text == "="));
+            if routine || generic || next.is_some_and(|n| ["=", ":", ","].contains(&n.text)) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Whether `tokens[i]` starts the body of a class, record, object or
+/// interface type, which ends with `end`.
+fn opens_body(tokens: &[Token], i: usize) -> bool {
+    let token = tokens[i];
+    let previous = i.checked_sub(1).map(|j| tokens[j]);
+    let after_equals = previous.is_some_and(|p| p.text == "=" || p.is("packed"));
+    if token.is("record") {
+        return after_equals || previous.is_some_and(|p| p.text == ":");
+    }
+    if !["class", "object", "interface", "dispinterface"].iter().any(|word| token.is(word)) || !after_equals {
+        return false;
+    }
+    // `class of TFoo`, forward declarations `TFoo = class;` and bodiless
+    // declarations `EFoo = class(Exception);`.
+    let mut j = i + 1;
+    if tokens.get(j).is_some_and(|t| t.text == "(") {
+        while j < tokens.len() && tokens[j].text != ")" {
+            j += 1;
+        }
+        j += 1;
+    }
+    !tokens.get(i + 1).is_some_and(|t| t.is("of")) && tokens.get(j).is_none_or(|t| t.text != ";")
+}
+
+/// Delphi source units next to `file` and of the projects `file` belongs to.
+async fn source_units(file: &Path) -> Vec<PathBuf> {
+    let file = normalize_path(file);
+    let pascal_units = |dir: &Path| -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pas")))
+            .map(normalize_path)
+            .collect()
+    };
+    let mut units = BTreeSet::new();
+    if let Some(dir) = file.parent() {
+        units.extend(pascal_units(dir));
+    }
+    let projects_data = PROJECTS_DATA.read().await;
+    for project in &projects_data.projects {
+        let directory = normalize_path(&project.directory);
+        if project.directory.is_empty() || !file.starts_with(&directory) {
+            continue;
+        }
+        units.extend(pascal_units(&directory));
+        if let Some(main_source) = project.dpr.as_ref().or(project.dpk.as_ref()) {
+            units.extend(main_source_unit_paths(Path::new(main_source)));
+        }
+    }
+    units.remove(&file);
+    units.into_iter().collect()
+}
+
+// ---------------------------------------------------------------------------
+// Unused variables and implicit casts
+// ---------------------------------------------------------------------------
+
+/// Edits removing variable `name` from its declaration on the 0-based
+/// `line`; with the `var` keyword too when nothing else is declared in it.
+fn remove_variable(source: &str, line: u32, name: &str) -> Option<Vec<TextEdit>> {
+    let (start, end, next) = line_bounds(source, line)?;
+    let captures = VARIABLE_LINE_REGEX.captures(&source[start..end])?;
+    let names = captures.name("names")?;
+    let list: Vec<&str> = names.as_str().split(',').map(str::trim).collect();
+    if !list.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+        return None;
+    }
+    let declares_next = |line| line_text(source, line).is_some_and(|text| DECLARATION_START_REGEX.is_match(text));
+    if list.len() > 1 {
+        let rest: Vec<&str> = list.into_iter().filter(|n| !n.eq_ignore_ascii_case(name)).collect();
+        let names_range = range(source, start + names.start(), start + names.end());
+        return Some(vec![TextEdit { range: names_range, new_text: rest.join(", ") }]);
+    }
+    if captures.name("var").is_some() && declares_next(line + 1) {
+        // `var X: Integer;` followed by more declarations of the block.
+        let indent = captures.name("indent").map_or("", |m| m.as_str());
+        return Some(vec![TextEdit { range: range(source, start, end), new_text: format!("{indent}var") }]);
+    }
+    // A `var` line left without declarations goes too.
+    let from = match line.checked_sub(1) {
+        Some(previous)
+            if line_text(source, previous).is_some_and(|text| text.trim().eq_ignore_ascii_case("var"))
+                && !declares_next(line + 1) =>
+        {
+            line_bounds(source, previous).map_or(start, |(var_start, ..)| var_start)
+        }
+        _ => start,
+    };
+    Some(vec![TextEdit { range: range(source, from, next), new_text: String::new() }])
+}
+
+/// Edits casting the value assigned on the 0-based `line` to `target`, e.g.
+/// `S := string(A);`.
+fn cast_assigned_value(source: &str, line: u32, target: &str) -> Option<Vec<TextEdit>> {
+    let (start, end, _) = line_bounds(source, line)?;
+    let tokens = tokenize(&source[start..end]);
+    let assignment = tokens.iter().position(|t| t.text == ":=")?;
+    let value = tokens.get(assignment + 1)?;
+    let mut depth = 0usize;
+    let mut last = None;
+    for token in &tokens[assignment + 1..] {
+        match token.text {
+            "(" | "[" => depth += 1,
+            ")" | "]" => depth = depth.saturating_sub(1),
+            ";" if depth == 0 => break,
+            _ => {}
+        }
+        last = Some(token);
+    }
+    let last = last?;
+    // Already cast.
+    if value.is(target) && tokens.get(assignment + 2).is_some_and(|t| t.text == "(") {
+        return None;
+    }
+    Some(vec![
+        TextEdit { range: range(source, start + value.start, start + value.start), new_text: format!("{target}(") },
+        TextEdit { range: range(source, start + last.end, start + last.end), new_text: ")".to_string() },
+    ])
+}
@@ -0,0 +1,107 @@
+//! Which warnings and hints a workspace or project tolerates.  The policy is
+//! applied while the build output is parsed: suppressed codes are dropped,
+//! codes treated as errors are reported as errors, and a build that reports
+//! more warnings than allowed fails (see [`WarningPolicy::violations`]).
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::{CompileDiagnostic, DiagnosticKind};
+
+/// Warning policy of a workspace or project.  Only warnings and hints are
+/// affected; errors always stay errors.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarningPolicy {
+    /// Codes of warnings and hints that fail the build, e.g. "W1036".
+    pub errors: Vec<String>,
+    /// Codes of warnings and hints that are ignored, e.g. "H2164".
+    pub suppress: Vec<String>,
+    /// Fail the build when it reports more warnings than this.
+    pub max_warnings: Option<usize>,
+}
+
+fn contains(codes: &[String], code: &str) -> bool {
+    codes.iter().any(|c| c.trim().eq_ignore_ascii_case(code))
+}
+
+impl WarningPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty() && self.suppress.is_empty() && self.max_warnings.is_none()
+    }
+
+    /// The policy of a project in a workspace with this policy: the codes
+    /// of both apply, but a code the project lists overrides how the
+    /// workspace lists it, and the project's `max_warnings` replaces the
+    /// workspace's.
+    pub fn with_project(&self, project: &WarningPolicy) -> WarningPolicy {
+        let inherited = |codes: &[String], overridden: &[String]| -> Vec<String> {
+            codes.iter().filter(|code| !contains(overridden, code)).cloned().collect()
+        };
+        let mut errors = inherited(&self.errors, &project.suppress);
+        errors.extend(project.errors.iter().cloned());
+        let mut suppress = inherited(&self.suppress, &project.errors);
+        suppress.extend(project.suppress.iter().cloned());
+        WarningPolicy { errors, suppress, max_warnings: project.max_warnings.or(self.max_warnings) }
+    }
+
+    /// Severity of a diagnostic with `code` under the policy, `None` when it
+    /// is suppressed.  A code listed both as error and as suppressed is an
+    /// error.
+    pub fn severity(&self, code: &str, severity: DiagnosticKind) -> Option<DiagnosticKind> {
+        if severity.is_error() {
+            Some(severity)
+        } else if contains(&self.errors, code) {
+            Some(DiagnosticKind::ERROR)
+        } else if contains(&self.suppress, code) {
+            None
+        } else {
+            Some(severity)
+        }
+    }
+
+    /// How the diagnostics of a build, with the policy already applied,
+    /// break the policy.
+    pub fn violations(&self, diagnostics: &[CompileDiagnostic]) -> Vec<String> {
+        let mut violations = Vec::new();
+        let mut promoted: Vec<&str> = Vec::new();
+        let mut count = 0;
+        for diagnostic in diagnostics {
+            if diagnostic.severity == DiagnosticKind::ERROR && contains(&self.errors, &diagnostic.code) {
+                count += 1;
+                if !promoted.iter().any(|code| code.eq_ignore_ascii_case(&diagnostic.code)) {
+                    promoted.push(&diagnostic.code);
+                }
+            }
+        }
+        if count > 0 {
+            violations.push(format!("{count} warning(s)/hint(s) treated as errors: {}", promoted.join(", ")));
+        }
+        let warnings = diagnostics.iter().filter(|d| d.severity == DiagnosticKind::WARN).count();
+        if let Some(max_warnings) = self.max_warnings
+            && warnings > max_warnings
+        {
+            violations.push(format!("{warnings} warning(s), more than the {max_warnings} allowed"));
+        }
+        violations
+    }
+}
+
+impl fmt::Display for WarningPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no warning policy");
+        }
+        let mut parts = Vec::new();
+        if !self.errors.is_empty() {
+            parts.push(format!("errors: {}", self.errors.join(", ")));
+        }
+        if !self.suppress.is_empty() {
+            parts.push(format!("suppressed: {}", self.suppress.join(", ")));
+        }
+        if let Some(max_warnings) = self.max_warnings {
+            parts.push(format!("at most {max_warnings} warning(s)"));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
@@ -16,6 +16,20 @@ pub struct Workspace {
     pub active_configuration: Option<String>,
     /// Workspace-level platform override.
     pub active_platform: Option<String>,
+    /// Declared build dependencies between the workspace's projects.
+    pub dependencies: Vec<ProjectDependency>,
+    /// Warning policy of every project built from the workspace; see
+    /// [`WarningPolicy::with_project`].
+    #[serde(skip_serializing_if = "WarningPolicy::is_empty")]
+    pub warning_policy: WarningPolicy,
+}
+
+/// "`project_id` depends on `depends_on`": within its workspace, the
+/// project is built after the one it depends on.
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ProjectDependency {
+    pub project_id: usize,
+    pub depends_on: usize,
 }
 
 impl Default for Workspace {
@@ -28,6 +42,8 @@ impl Default for Workspace {
             sort_rank: LexoRank::default(),
             active_configuration: None,
             active_platform: None,
+            dependencies: Vec::new(),
+            warning_policy: WarningPolicy::default(),
         }
     }
 }
@@ -42,6 +58,8 @@ impl Workspace {
             sort_rank: lexo_rank,
             active_configuration: None,
             active_platform: None,
+            dependencies: Vec::new(),
+            warning_policy: WarningPolicy::default(),
         }
     }
 
@@ -57,6 +75,47 @@ impl Workspace {
                 self.compiler_id).as_str())
             .clone();
     }
+
+    /// Declared dependencies between the linked projects, indexed by link
+    /// position.
+    pub fn dependency_graph(&self) -> BuildGraph {
+        let mut graph = BuildGraph::new(self.project_links.len());
+        let index_of = |project_id: usize| self.project_links.iter().position(|link| link.project_id == project_id);
+        for dependency in &self.dependencies {
+            if let (Some(project), Some(depends_on)) = (index_of(dependency.project_id), index_of(dependency.depends_on)) {
+                graph.add_dependency(project, depends_on);
+            }
+        }
+        graph
+    }
+
+    /// Drop dependencies on or of projects that are no longer linked.
+    pub fn prune_dependencies(&mut self) {
+        let linked: Vec<usize> = self.project_links.iter().map(|link| link.project_id).collect();
+        self.dependencies
+            .retain(|d| linked.contains(&d.project_id) && linked.contains(&d.depends_on));
+    }
+
+    /// Projects `project_id` depends on, directly or indirectly, in link
+    /// order.
+    pub fn transitive_dependencies(&self, project_id: usize) -> Vec<usize> {
+        let mut found = vec![project_id];
+        let mut index = 0;
+        while index < found.len() {
+            let current = found[index];
+            for dependency in self.dependencies.iter().filter(|d| d.project_id == current) {
+                if !found.contains(&dependency.depends_on) {
+                    found.push(dependency.depends_on);
+                }
+            }
+            index += 1;
+        }
+        self.project_links
+            .iter()
+            .map(|link| link.project_id)
+            .filter(|id| *id != project_id && found.contains(id))
+            .collect()
+    }
 }
 
 impl HasLexoRank for Workspace {
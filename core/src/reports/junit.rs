@@ -0,0 +1,98 @@
+//! JUnit XML export of a compilation: one `<testcase>` per project, so CI
+//! dashboards show per-project build status and timing.
+
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::commands::{CompileOutput, ProjectCompileResult};
+
+/// Render `output` as a JUnit XML document.  The test suite is named after
+/// the compiled workspace / group project / project; failed projects carry
+/// their error diagnostics in the `<failure>` body, cancelled and skipped
+/// ones are reported as skipped.
+pub fn to_junit_xml(output: &CompileOutput) -> String {
+    let tests = output.projects.len();
+    let failures = output.projects.iter().filter(|p| !p.success && !p.cancelled && !p.skipped).count();
+    let skipped = output.projects.iter().filter(|p| p.cancelled || p.skipped).count();
+    let time = seconds(output.projects.iter().map(|p| p.duration_ms).sum());
+    let suite = escape(&output.project_name);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    _ = writeln!(
+        xml,
+        "<testsuites name=\"ddk compile\" tests=\"{tests}\" failures=\"{failures}\" errors=\"0\" skipped=\"{skipped}\" time=\"{time}\">"
+    );
+    _ = writeln!(
+        xml,
+        "  <testsuite name=\"{suite}\" tests=\"{tests}\" failures=\"{failures}\" errors=\"0\" skipped=\"{skipped}\" time=\"{time}\">"
+    );
+    for project in &output.projects {
+        write_testcase(&mut xml, &suite, project, output);
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Render `output` with [`to_junit_xml`] and write it to `path`.
+pub fn write_junit(path: &Path, output: &CompileOutput) -> Result<()> {
+    std::fs::write(path, to_junit_xml(output))
+        .with_context(|| format!("Failed to write JUnit report to {}", path.display()))
+}
+
+fn write_testcase(xml: &mut String, suite: &str, project: &ProjectCompileResult, output: &CompileOutput) {
+    let name = escape(&project.project_name);
+    let time = seconds(project.duration_ms);
+    let open = format!("    <testcase classname=\"{suite}\" name=\"{name}\" time=\"{time}\"");
+    if project.cancelled {
+        _ = writeln!(xml, "{open}>\n      <skipped message=\"Compilation was cancelled.\"/>\n    </testcase>");
+        return;
+    }
+    if project.skipped {
+        _ = writeln!(xml, "{open}>\n      <skipped message=\"Not compiled because the compilation stopped before this project.\"/>\n    </testcase>");
+        return;
+    }
+    if project.success {
+        _ = writeln!(xml, "{open}/>");
+        return;
+    }
+    let errors: Vec<String> = output
+        .diagnostics
+        .iter()
+        .filter(|d| d.project_id == project.project_id && d.severity.is_error())
+        .map(|d| d.to_string())
+        .collect();
+    let message = if project.policy_violations.is_empty() {
+        format!("Compilation failed with exit code {} ({} error(s))", project.code, errors.len())
+    } else {
+        format!("Compilation broke the warning policy: {}", project.policy_violations.join("; "))
+    };
+    _ = writeln!(
+        xml,
+        "{open}>\n      <failure message=\"{}\" type=\"CompilationFailed\">{}</failure>\n    </testcase>",
+        escape(&message),
+        escape(&errors.join("\n"))
+    );
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// Escape text for use in XML attribute values and character data.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than tab / newline are not allowed in XML 1.0.
+            c if c.is_control() && c != '\t' && c != '\n' && c != '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,49 @@
+//! Machine-readable build reports (SARIF, JUnit) generated from a
+//! [`CompileOutput`](crate::commands::CompileOutput) for CI systems.
+
+pub mod junit;
+pub mod sarif;
+
+pub use junit::*;
+pub use sarif::*;
+
+use std::path::{Path, PathBuf};
+
+/// Walk up from `start` and return the first directory containing `.git`.
+pub fn find_repository_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Express `file` relative to `root` with `/` separators, or `None` when it
+/// lies outside of `root`.  Compared case-insensitively and with either
+/// separator, since the compiler reports Windows paths.
+pub fn relative_to_root(file: &str, root: &Path) -> Option<String> {
+    let file = file.replace('\\', "/");
+    let root = root.to_string_lossy().replace('\\', "/");
+    let root = root.trim_end_matches('/');
+    if root.is_empty() || file.len() <= root.len() + 1 {
+        return None;
+    }
+    let (head, rest) = file.split_at(root.len());
+    if !head.eq_ignore_ascii_case(root) || !rest.starts_with('/') {
+        return None;
+    }
+    Some(rest[1..].to_string())
+}
+
+/// Percent-encode a `/`-separated path for use as a URI reference.
+pub(crate) fn encode_uri_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
@@ -0,0 +1,118 @@
+//! SARIF 2.1.0 export of compiler diagnostics, as ingested by GitHub code
+//! scanning and most code-review platforms.
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::path::Path;
+
+use super::{encode_uri_path, relative_to_root};
+use crate::commands::CompileOutput;
+use crate::projects::DiagnosticKind;
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Base id that repository-relative artifact locations are resolved against.
+const SRCROOT: &str = "SRCROOT";
+
+/// Build a SARIF log with one run for `output`.  Each distinct Delphi code
+/// (W1000, H2164, E2003, …) becomes a rule; file locations under `root` are
+/// made relative to it, everything else is kept as an absolute `file://` URI.
+pub fn to_sarif(output: &CompileOutput, root: Option<&Path>) -> Value {
+    let mut rules: Vec<Value> = Vec::new();
+    let mut rule_ids: Vec<&str> = Vec::new();
+    let mut results: Vec<Value> = Vec::new();
+    for diagnostic in &output.diagnostics {
+        let level = sarif_level(diagnostic.severity);
+        let rule_index = match rule_ids.iter().position(|id| *id == diagnostic.code) {
+            Some(index) => index,
+            _ => {
+                rule_ids.push(&diagnostic.code);
+                rules.push(json!({
+                    "id": diagnostic.code,
+                    "defaultConfiguration": { "level": level },
+                }));
+                rule_ids.len() - 1
+            }
+        };
+        let mut physical_location = json!({ "artifactLocation": artifact_location(&diagnostic.file, root) });
+        // Diagnostics without a position point at the whole (project) file.
+        if diagnostic.line > 0 {
+            let mut region = json!({ "startLine": diagnostic.line });
+            if let Some(column) = diagnostic.column {
+                region["startColumn"] = json!(column.max(1));
+            }
+            physical_location["region"] = region;
+        }
+        results.push(json!({
+            "ruleId": diagnostic.code,
+            "ruleIndex": rule_index,
+            "level": level,
+            "message": { "text": diagnostic.message },
+            "locations": [{ "physicalLocation": physical_location }],
+        }));
+    }
+
+    let tool_name = if output.compiler_name.is_empty() {
+        "Delphi compiler"
+    } else {
+        output.compiler_name.as_str()
+    };
+    let mut run = json!({
+        "tool": { "driver": { "name": tool_name, "rules": rules } },
+        "invocations": [{
+            "executionSuccessful": !output.cancelled,
+            "exitCode": output.code,
+        }],
+        "results": results,
+    });
+    if let Some(root) = root {
+        let mut root_uri = file_uri(&root.to_string_lossy());
+        if !root_uri.ends_with('/') {
+            root_uri.push('/');
+        }
+        run["originalUriBaseIds"] = json!({ SRCROOT: { "uri": root_uri } });
+    }
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [run],
+    })
+}
+
+/// Serialize `output` with [`to_sarif`] and write it to `path`.
+pub fn write_sarif(path: &Path, output: &CompileOutput, root: Option<&Path>) -> Result<()> {
+    let sarif = serde_json::to_string_pretty(&to_sarif(output, root))?;
+    std::fs::write(path, sarif)
+        .with_context(|| format!("Failed to write SARIF report to {}", path.display()))
+}
+
+fn sarif_level(kind: DiagnosticKind) -> &'static str {
+    match kind {
+        DiagnosticKind::FATAL | DiagnosticKind::ERROR => "error",
+        DiagnosticKind::WARN => "warning",
+        DiagnosticKind::HINT => "note",
+    }
+}
+
+fn artifact_location(file: &str, root: Option<&Path>) -> Value {
+    if let Some(root) = root
+        && let Some(relative) = relative_to_root(file, root)
+    {
+        return json!({ "uri": encode_uri_path(&relative), "uriBaseId": SRCROOT });
+    }
+    json!({ "uri": file_uri(file) })
+}
+
+/// `C:\src\a.pas` → `file:///C:/src/a.pas`, `/src/a.pas` → `file:///src/a.pas`.
+/// Relative paths (unresolved compiler output) stay relative references.
+fn file_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    if path.starts_with('/') {
+        return format!("file://{}", encode_uri_path(&path));
+    }
+    if path.as_bytes().get(1) == Some(&b':') {
+        return format!("file:///{}", encode_uri_path(&path));
+    }
+    encode_uri_path(&path)
+}
@@ -2,7 +2,9 @@
 use std::path::{Path, PathBuf, Component};
 
 mod document;
+mod process;
 pub use document::*;
+pub use process::*;
 
 /// Normalise a path by:
 ///   1. Resolving `.` and `..` segments purely (without touching the filesystem).
@@ -0,0 +1,287 @@
+//! Child processes that are stopped together with everything they spawned.
+//!
+//! Builds go through wrappers (MSBuild, `cmd /c`, shell scripts) that start
+//! the actual compiler as a grandchild.  Killing only the direct child leaves
+//! those running and holding file locks, so every process DDK launches is
+//! started as a [`ProcessTree`]:
+//!
+//! * on Windows the child is started suspended and assigned to a job object
+//!   that kills all of its processes when terminated or closed, so nothing
+//!   it starts escapes the job;
+//! * on Unix the child leads a new process group that is signalled as a whole.
+//!
+//! [`ProcessTree::terminate`] first asks the tree to exit (Ctrl+Break /
+//! `SIGTERM`) and force-kills it once the grace period has passed.
+
+use anyhow::{Context, Result};
+use std::process::ExitStatus;
+use std::time::Duration;
+use tokio::process::{Child, Command};
+
+/// How long a tree gets to exit on its own before it is force-killed.
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+pub struct ProcessTree {
+    child: Child,
+    /// Process id of the root, which is also the process group id on Unix.
+    /// Kept separately because [`Child::id`] is gone once the root is reaped.
+    #[cfg_attr(not(any(unix, windows)), allow(dead_code))]
+    pid: u32,
+    #[cfg(windows)]
+    job: Option<windows::JobObject>,
+    finished: bool,
+}
+
+impl ProcessTree {
+    /// Spawn `command` as the root of a new process tree.
+    pub fn spawn(command: &mut Command) -> Result<Self> {
+        #[cfg(unix)]
+        command.process_group(0);
+        #[cfg(windows)]
+        command.creation_flags(windows::CREATE_NEW_PROCESS_GROUP | windows::CREATE_SUSPENDED);
+
+        let child = command.kill_on_drop(true).spawn()?;
+        let pid = child.id().context("Spawned process has no id")?;
+        #[cfg(windows)]
+        let job = windows::JobObject::new()
+            .and_then(|job| job.assign(&child).map(|_| job))
+            .ok();
+        // Dropping the child kills it, should it stay suspended.
+        #[cfg(windows)]
+        windows::resume(pid)?;
+        Ok(Self {
+            child,
+            pid,
+            #[cfg(windows)]
+            job,
+            finished: false,
+        })
+    }
+
+    /// The root process, e.g. to take its output pipes.
+    pub fn child_mut(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Wait for the root process to exit.
+    pub async fn wait(&mut self) -> Result<ExitStatus> {
+        let status = self.child.wait().await?;
+        self.finished = true;
+        Ok(status)
+    }
+
+    /// Stop the whole tree: ask it to exit, wait up to `grace` for the root
+    /// process, then kill whatever is left.
+    pub async fn terminate(&mut self, grace: Duration) {
+        if !self.finished {
+            self.signal_exit();
+            if let Ok(Ok(_)) = tokio::time::timeout(grace, self.child.wait()).await {
+                self.finished = true;
+            }
+        }
+        // Descendants may outlive the root, so this also runs after a
+        // graceful exit.
+        self.kill_tree();
+        if !self.finished {
+            let _ = self.child.kill().await;
+            self.finished = true;
+        }
+    }
+
+    #[cfg(unix)]
+    fn signal_exit(&self) {
+        unsafe {
+            libc::killpg(self.pid as libc::pid_t, libc::SIGTERM);
+        }
+    }
+
+    #[cfg(windows)]
+    fn signal_exit(&self) {
+        windows::send_ctrl_break(self.pid);
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn signal_exit(&self) {}
+
+    /// Once the root is reaped its id can be reused, so only a group that
+    /// still exists is killed.
+    #[cfg(unix)]
+    fn kill_tree(&mut self) {
+        let group = self.pid as libc::pid_t;
+        unsafe {
+            if libc::killpg(group, 0) == 0 {
+                libc::killpg(group, libc::SIGKILL);
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    fn kill_tree(&mut self) {
+        if let Some(job) = &self.job {
+            job.terminate();
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn kill_tree(&mut self) {}
+}
+
+impl Drop for ProcessTree {
+    /// An abandoned tree is killed, like `kill_on_drop` does for the root.
+    /// On Windows closing the job object takes care of it.
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if !self.finished {
+            self.kill_tree();
+        }
+    }
+}
+
+/// Whether a process with id `pid` is running.
+#[cfg(unix)]
+pub fn process_alive(pid: u32) -> bool {
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+pub fn process_alive(pid: u32) -> bool {
+    windows::process_alive(pid)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(windows)]
+mod windows {
+    use anyhow::Result;
+    use std::ffi::c_void;
+    use tokio::process::Child;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE, STILL_ACTIVE};
+    use windows_sys::Win32::System::Console::{CTRL_BREAK_EVENT, GenerateConsoleCtrlEvent};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, THREADENTRY32, Thread32First, Thread32Next,
+    };
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, TerminateJobObject,
+    };
+
+    pub use windows_sys::Win32::System::Threading::{CREATE_NEW_PROCESS_GROUP, CREATE_SUSPENDED};
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, OpenThread, PROCESS_QUERY_LIMITED_INFORMATION, ResumeThread,
+        THREAD_SUSPEND_RESUME,
+    };
+
+    /// Job object that kills its processes when terminated or closed.
+    pub struct JobObject(isize);
+
+    impl JobObject {
+        pub fn new() -> Result<Self> {
+            let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+            if handle.is_null() {
+                anyhow::bail!("CreateJobObjectW failed: {}", std::io::Error::last_os_error());
+            }
+            let job = Self(handle as isize);
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let ok = unsafe {
+                SetInformationJobObject(
+                    job.handle(),
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const c_void,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                )
+            };
+            if ok == 0 {
+                anyhow::bail!("SetInformationJobObject failed: {}", std::io::Error::last_os_error());
+            }
+            Ok(job)
+        }
+
+        pub fn assign(&self, child: &Child) -> Result<()> {
+            let Some(process) = child.raw_handle() else {
+                anyhow::bail!("Process has already exited");
+            };
+            let ok = unsafe { AssignProcessToJobObject(self.handle(), process as HANDLE) };
+            if ok == 0 {
+                anyhow::bail!("AssignProcessToJobObject failed: {}", std::io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        pub fn terminate(&self) {
+            unsafe {
+                TerminateJobObject(self.handle(), 1);
+            }
+        }
+
+        fn handle(&self) -> HANDLE {
+            self.0 as HANDLE
+        }
+    }
+
+    impl Drop for JobObject {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.handle());
+            }
+        }
+    }
+
+    /// Resume the threads of `pid`, which was created suspended; that is
+    /// just its main thread.
+    pub fn resume(pid: u32) -> Result<()> {
+        let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) };
+        if snapshot == INVALID_HANDLE_VALUE {
+            anyhow::bail!("CreateToolhelp32Snapshot failed: {}", std::io::Error::last_os_error());
+        }
+        let mut entry: THREADENTRY32 = unsafe { std::mem::zeroed() };
+        entry.dwSize = std::mem::size_of::<THREADENTRY32>() as u32;
+        let mut resumed = false;
+        let mut more = unsafe { Thread32First(snapshot, &mut entry) } != 0;
+        while more {
+            if entry.th32OwnerProcessID == pid {
+                let thread = unsafe { OpenThread(THREAD_SUSPEND_RESUME, 0, entry.th32ThreadID) };
+                if !thread.is_null() {
+                    resumed |= unsafe { ResumeThread(thread) } != u32::MAX;
+                    unsafe {
+                        CloseHandle(thread);
+                    }
+                }
+            }
+            more = unsafe { Thread32Next(snapshot, &mut entry) } != 0;
+        }
+        unsafe {
+            CloseHandle(snapshot);
+        }
+        if !resumed {
+            anyhow::bail!("Failed to resume process {pid}: {}", std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Ctrl+Break to the process group of `pid`.  Only reaches processes
+    /// that share our console; everything else is left to the job object.
+    pub fn send_ctrl_break(pid: u32) {
+        unsafe {
+            GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid);
+        }
+    }
+
+    pub fn process_alive(pid: u32) -> bool {
+        let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid) };
+        if process.is_null() {
+            return false;
+        }
+        let mut code = 0u32;
+        let ok = unsafe { GetExitCodeProcess(process, &mut code) };
+        unsafe {
+            CloseHandle(process);
+        }
+        ok != 0 && code == STILL_ACTIVE as u32
+    }
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use ddk_core::commands::CompileOutput;
+use ddk_core::projects::baseline::{BaselineComparison, WarningBaseline, normalize_message};
+use ddk_core::projects::{CompileDiagnostic, DiagnosticKind};
+
+mod common;
+use common::diagnostic;
+
+fn project_dir() -> &'static Path {
+    Path::new(r"C:\Repo\App")
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  normalize_message
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn normalize_collapses_whitespace_and_digits() {
+    assert_eq!(
+        normalize_message("Constant   expression violates subrange bounds at  line 1234"),
+        "Constant expression violates subrange bounds at line #"
+    );
+    assert_eq!(normalize_message("W1000 in 'Unit12.pas'"), "W# in 'Unit#.pas'");
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  WarningBaseline
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn baseline_records_warnings_and_hints_only() {
+    let diagnostics = vec![
+        diagnostic(r"C:\Repo\App\Unit1.pas", 10, "W1000", DiagnosticKind::WARN, "Symbol 'X' is deprecated"),
+        diagnostic(r"C:\Repo\App\Unit1.pas", 20, "W1000", DiagnosticKind::WARN, "Symbol 'X' is deprecated"),
+        diagnostic(r"C:\Repo\App\Unit1.pas", 30, "H2164", DiagnosticKind::HINT, "Variable 'I' is declared but never used"),
+        diagnostic(r"C:\Repo\App\Unit1.pas", 40, "E2003", DiagnosticKind::ERROR, "Undeclared identifier: 'Foo'"),
+    ];
+    let baseline = WarningBaseline::from_diagnostics(&diagnostics, project_dir());
+    assert_eq!(baseline.entries.len(), 2);
+    let w1000 = baseline.entries.iter().find(|e| e.code == "W1000").unwrap();
+    assert_eq!(w1000.file, "unit1.pas");
+    assert_eq!(w1000.count, 2);
+}
+
+#[test]
+fn compare_ignores_line_moves_and_path_case() {
+    let recorded = vec![diagnostic(r"C:\Repo\App\Unit1.pas", 10, "W1000", DiagnosticKind::WARN, "Symbol 'X' is deprecated")];
+    let baseline = WarningBaseline::from_diagnostics(&recorded, project_dir());
+    let current = vec![diagnostic(r"c:\repo\app\UNIT1.PAS", 99, "W1000", DiagnosticKind::WARN, "Symbol 'X' is deprecated")];
+    let comparison = baseline.compare(&current, project_dir());
+    assert!(comparison.new.is_empty());
+    assert_eq!(comparison.known.len(), 1);
+}
+
+#[test]
+fn compare_flags_occurrences_beyond_recorded_count_as_new() {
+    let w = |line| diagnostic(r"C:\Repo\App\Unit1.pas", line, "W1000", DiagnosticKind::WARN, "Symbol 'X' is deprecated");
+    let baseline = WarningBaseline::from_diagnostics(&[w(1)], project_dir());
+    let comparison = baseline.compare(&[w(1), w(2)], project_dir());
+    assert_eq!(comparison.known.len(), 1);
+    assert_eq!(comparison.new.len(), 1);
+    assert_eq!(comparison.new[0].line, 2);
+}
+
+#[test]
+fn compare_flags_new_code_and_skips_errors() {
+    let baseline = WarningBaseline::from_diagnostics(&[], project_dir());
+    let current = vec![
+        diagnostic(r"C:\Repo\App\Unit1.pas", 1, "H2164", DiagnosticKind::HINT, "Variable 'I' is declared but never used"),
+        diagnostic(r"C:\Repo\App\Unit1.pas", 2, "E2003", DiagnosticKind::ERROR, "Undeclared identifier: 'Foo'"),
+    ];
+    let comparison = baseline.compare(&current, project_dir());
+    assert_eq!(comparison.new.len(), 1);
+    assert_eq!(comparison.new[0].code, "H2164");
+    assert!(comparison.known.is_empty());
+}
+
+#[test]
+fn baseline_save_and_load_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("App.ddk-baseline.json");
+    assert_eq!(WarningBaseline::load(&path).unwrap(), None);
+    let recorded = vec![diagnostic(r"C:\Repo\App\Unit1.pas", 10, "W1000", DiagnosticKind::WARN, "Symbol 'X' is deprecated")];
+    let baseline = WarningBaseline::from_diagnostics(&recorded, project_dir());
+    baseline.save(&path).unwrap();
+    assert_eq!(WarningBaseline::load(&path).unwrap(), Some(baseline));
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Display – CompileOutput with new diagnostics
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn compile_output_display_lists_new_diagnostics() {
+    let new = diagnostic(r"C:\Repo\App\Unit1.pas", 7, "W1000", DiagnosticKind::WARN, "Symbol 'X' is deprecated");
+    let output = CompileOutput {
+        project_name: "App".into(),
+        success: false,
+        cancelled: false,
+        code: 0,
+        lines: vec![],
+        compiler_name: "Delphi 12".into(),
+        diagnostics: vec![new.clone()],
+        projects: vec![],
+        baseline: Some(BaselineComparison { new: vec![new], known: vec![] }),
+        matrix: Vec::new(),
+    };
+    let display = format!("{}", output);
+    assert!(display.contains("1 warning(s)/hint(s) not in the baseline"));
+    assert!(display.contains(r"[WARN][W1000] C:\Repo\App\Unit1.pas:7 - Symbol 'X' is deprecated"));
+}
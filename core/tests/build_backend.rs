@@ -0,0 +1,1508 @@
+//! Runs the compile pipeline (banners, progress events, diagnostics,
+//! cancellation) against a scripted backend, so it works without Delphi.
+
+use std::io::Cursor;
+use std::sync::{Arc, Once};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ddk_core::lexorank::LexoRank;
+use ddk_core::projects::*;
+use ddk_core::state::PROJECTS_DATA;
+use ddk_core::utils::FilePath;
+use ddk_core::{CompileProjectParams, CompilerProgress, CompilerProgressParams};
+
+// The compiler state is process-global: one compilation at a time.
+static COMPILE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+static ISOLATE: Once = Once::new();
+
+struct ScriptedBackend {
+    stdout: Vec<&'static str>,
+    exit_code: i32,
+    hang: bool,
+}
+
+struct ScriptedHandle {
+    exit_code: i32,
+    hang: bool,
+}
+
+#[async_trait]
+impl BuildBackend for ScriptedBackend {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    async fn start(&self, _request: &BuildRequest<'_>) -> Result<BuildProcess> {
+        let stdout: String = self.stdout.iter().map(|line| format!("{line}\r\n")).collect();
+        Ok(BuildProcess {
+            stdout: Box::new(Cursor::new(stdout.into_bytes())),
+            stderr: Box::new(Cursor::new(Vec::new())),
+            handle: Box::new(ScriptedHandle { exit_code: self.exit_code, hang: self.hang }),
+        })
+    }
+}
+
+#[async_trait]
+impl BuildHandle for ScriptedHandle {
+    async fn wait(&mut self) -> Result<i32> {
+        if self.hang {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        }
+        Ok(self.exit_code)
+    }
+
+    async fn kill(&mut self) {}
+}
+
+/// Keep the state files of this test process away from the user's config.
+fn isolate_config() {
+    ISOLATE.call_once(|| {
+        let dir = tempfile::tempdir().unwrap().keep();
+        // SAFETY: runs once, before any state is loaded by this process.
+        unsafe { std::env::set_var("XDG_CONFIG_HOME", dir) };
+    });
+}
+
+/// Register a one-project workspace whose `.dpr` exists on disk and return
+/// the compile parameters for it.
+async fn setup_project(dir: &std::path::Path) -> CompileProjectParams {
+    isolate_config();
+    let dpr = dir.join("App.dpr");
+    std::fs::write(&dpr, "program App;\r\nbegin\r\nend.\r\n").unwrap();
+    let dpr = dpr.to_string_lossy().to_string();
+    let mut data = PROJECTS_DATA.write().await;
+    data.projects = vec![Project {
+        id: 1,
+        name: "App".into(),
+        directory: dir.to_string_lossy().to_string(),
+        dpr: Some(dpr.clone()),
+        // An existing exe keeps the compiler from re-discovering (and saving) paths.
+        exe: Some(dpr),
+        ..Default::default()
+    }];
+    data.workspaces = vec![Workspace {
+        id: 2,
+        name: "WS".into(),
+        compiler_id: "12.0".into(),
+        project_links: vec![ProjectLink { id: 3, project_id: 1, sort_rank: LexoRank::default() }],
+        sort_rank: LexoRank::default(),
+        ..Default::default()
+    }];
+    CompileProjectParams::Project {
+        project_id: 1,
+        project_link_id: Some(3),
+        rebuild: false,
+        event_id: "test".into(),
+        with_dependencies: false,
+    }
+}
+
+fn drain(receiver: &mut tokio::sync::broadcast::Receiver<CompilerProgressParams>) -> Vec<CompilerProgressParams> {
+    let mut events = Vec::new();
+    while let Ok(event) = receiver.try_recv() {
+        events.push(event);
+    }
+    events
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Compiler with a scripted backend
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn successful_build_emits_progress_and_diagnostics() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let mut receiver = CompilerProgress::subscribe();
+    let backend = Arc::new(ScriptedBackend {
+        stdout: vec![
+            "Embarcadero Delphi for Win32 compiler",
+            r"C:\Projects\Unit1.pas(42,5): warning W1000: Symbol 'X' is deprecated",
+            "12345 lines, 0.5 seconds",
+        ],
+        exit_code: 0,
+        hang: false,
+    });
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(result.success);
+    assert!(!result.cancelled);
+    assert_eq!(result.code, 0);
+
+    let events = drain(&mut receiver);
+    assert!(matches!(events.first(), Some(CompilerProgressParams::Start { .. })));
+    assert!(matches!(events.last(), Some(CompilerProgressParams::Completed { success: true, .. })));
+    assert!(events.iter().any(|e| matches!(e, CompilerProgressParams::Stdout { line, project_id: Some(1) } if line == "12345 lines, 0.5 seconds")));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        CompilerProgressParams::Stdout { line, project_id: Some(1) } if line.contains(r"[WARN][W1000] C:\Projects\Unit1.pas:42:5")
+    )));
+    let [diagnostic] = result.diagnostics.as_slice() else { panic!("expected one diagnostic") };
+    assert_eq!(diagnostic.project_id, 1);
+    assert_eq!(diagnostic.code, "W1000");
+}
+
+#[tokio::test]
+async fn compile_result_keeps_every_diagnostic() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    // More diagnostics than the progress broadcast holds.
+    let stdout: Vec<&'static str> = (1..=3000)
+        .map(|line| &*format!(r"C:\Projects\Unit1.pas({line},1): warning W1000: Symbol 'X' is deprecated").leak())
+        .collect();
+    let backend = Arc::new(ScriptedBackend { stdout, exit_code: 0, hang: false });
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(result.success);
+    assert_eq!(result.diagnostics.len(), 3000);
+    assert!(result.diagnostics.iter().all(|d| d.project_id == 1 && d.code == "W1000"));
+    assert_eq!(result.diagnostics.last().unwrap().line, 3000);
+}
+
+#[tokio::test]
+async fn failing_build_reports_exit_code() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let backend = Arc::new(ScriptedBackend {
+        stdout: vec![r"C:\Projects\Unit1.pas(3): error E2003: Undeclared identifier: 'Foo'"],
+        exit_code: 2,
+        hang: false,
+    });
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(!result.success);
+    assert!(!result.cancelled);
+    assert_eq!(result.code, 2);
+}
+
+#[tokio::test]
+async fn errors_without_position_are_attached_to_the_project_file() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let backend = Arc::new(ScriptedBackend {
+        stdout: vec![
+            "[dcc32 Error] E2202 Required package 'VclFoo' not found",
+            "Fatal: F1026 File not found: 'Foo.dcu'",
+        ],
+        exit_code: 1,
+        hang: false,
+    });
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.projects[0].diagnostics, DiagnosticCounts { errors: 2, warnings: 0, hints: 0 });
+
+    let project_file = dir.path().join("App.dpr").to_string_lossy().to_string();
+    let summary: Vec<(&str, u32, &str, DiagnosticKind)> = result
+        .diagnostics
+        .iter()
+        .map(|d| (d.file.as_str(), d.line, d.code.as_str(), d.severity))
+        .collect();
+    assert_eq!(summary, vec![
+        (project_file.as_str(), 0, "E2202", DiagnosticKind::ERROR),
+        (project_file.as_str(), 0, "F1026", DiagnosticKind::FATAL),
+    ]);
+}
+
+#[tokio::test]
+async fn continuation_lines_follow_their_diagnostic() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let mut receiver = CompilerProgress::subscribe();
+    let backend = Arc::new(ScriptedBackend {
+        stdout: vec![
+            r"C:\Projects\Unit1.pas(20): error E2251: Ambiguous overloaded call to 'Foo'",
+            r"  C:\Projects\Unit1.pas(10): Related method: procedure Foo(Integer);",
+            "Build FAILED.",
+        ],
+        exit_code: 1,
+        hang: false,
+    });
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    let events = drain(&mut receiver);
+    let [diagnostic] = result.diagnostics.as_slice() else { panic!("expected one diagnostic") };
+    let [related] = diagnostic.related.as_slice() else { panic!("expected one related location") };
+    assert_eq!((related.file.as_str(), related.line), (r"C:\Projects\Unit1.pas", 10));
+    let lines: Vec<&str> = events
+        .iter()
+        .filter_map(|e| match e {
+            CompilerProgressParams::Stdout { line, project_id: Some(1) } => Some(line.as_str()),
+            _ => None,
+        })
+        .collect();
+    let [diagnostic_line, related_line, last] = lines.as_slice() else { panic!("unexpected output {lines:?}") };
+    assert!(diagnostic_line.contains("[ERROR][E2251]"));
+    assert_eq!(*related_line, r"  C:\Projects\Unit1.pas(10): Related method: procedure Foo(Integer);");
+    assert_eq!(*last, "Build FAILED.");
+}
+
+#[tokio::test]
+async fn cancelled_build_stops_backend() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let backend = Arc::new(ScriptedBackend { stdout: vec![], exit_code: 0, hang: true });
+
+    let compiler = Compiler::new_standalone(&params).await.with_backend(backend);
+    let cancel = tokio::spawn(async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        compiler_state::cancel();
+    });
+    let result = tokio::time::timeout(Duration::from_secs(10), compiler.compile())
+        .await
+        .expect("cancellation should stop the build")
+        .unwrap();
+    cancel.await.unwrap();
+    assert!(result.cancelled);
+    assert!(!result.success);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Multi-project compiles
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// Builds every project in `delay`, failing those named in `failing`, and
+/// logs when each build starts and ends.
+#[derive(Default)]
+struct ProjectsBackend {
+    failing: Vec<&'static str>,
+    delay: Duration,
+    log: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+struct ProjectsHandle {
+    name: String,
+    exit_code: i32,
+    delay: Duration,
+    log: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl BuildBackend for ProjectsBackend {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    async fn start(&self, request: &BuildRequest<'_>) -> Result<BuildProcess> {
+        let name = request.project.name.clone();
+        self.log.lock().unwrap().push(format!("start {name}"));
+        let exit_code = if self.failing.contains(&name.as_str()) { 1 } else { 0 };
+        Ok(BuildProcess {
+            stdout: Box::new(Cursor::new(format!("building {name}\r\n").into_bytes())),
+            stderr: Box::new(Cursor::new(Vec::new())),
+            handle: Box::new(ProjectsHandle { name, exit_code, delay: self.delay, log: self.log.clone() }),
+        })
+    }
+}
+
+#[async_trait]
+impl BuildHandle for ProjectsHandle {
+    async fn wait(&mut self) -> Result<i32> {
+        tokio::time::sleep(self.delay).await;
+        self.log.lock().unwrap().push(format!("end {}", self.name));
+        Ok(self.exit_code)
+    }
+
+    async fn kill(&mut self) {}
+}
+
+fn failing(names: &[&'static str]) -> Arc<ProjectsBackend> {
+    Arc::new(ProjectsBackend { failing: names.to_vec(), ..Default::default() })
+}
+
+/// Register a workspace with the projects A, B and C and return the
+/// parameters to compile all of them.
+async fn setup_workspace(dir: &std::path::Path, keep_going: bool, jobs: Option<usize>) -> CompileProjectParams {
+    isolate_config();
+    let mut data = PROJECTS_DATA.write().await;
+    data.projects = ["A", "B", "C"]
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let dpr = dir.join(format!("{name}.dpr"));
+            std::fs::write(&dpr, format!("program {name};\r\nbegin\r\nend.\r\n")).unwrap();
+            let dpr = dpr.to_string_lossy().to_string();
+            Project {
+                id: index + 1,
+                name: name.to_string(),
+                directory: dir.to_string_lossy().to_string(),
+                dpr: Some(dpr.clone()),
+                exe: Some(dpr),
+                ..Default::default()
+            }
+        })
+        .collect();
+    data.workspaces = vec![Workspace {
+        id: 10,
+        name: "WS".into(),
+        compiler_id: "12.0".into(),
+        project_links: (1..=3)
+            .map(|id| ProjectLink { id: 10 + id, project_id: id, sort_rank: LexoRank::default() })
+            .collect(),
+        sort_rank: LexoRank::default(),
+        ..Default::default()
+    }];
+    CompileProjectParams::AllInWorkspace {
+        workspace_id: 10,
+        rebuild: false,
+        event_id: "test".into(),
+        keep_going,
+        jobs,
+    }
+}
+
+fn statuses(result: &CompileResult) -> Vec<(&str, ProjectStatus)> {
+    result.projects.iter().map(|p| (p.project_name.as_str(), p.status)).collect()
+}
+
+#[tokio::test]
+async fn bulk_compile_stops_at_first_failure() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), false, None).await;
+    let backend = failing(&["B"]);
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.code, 1);
+    assert_eq!(statuses(&result), vec![
+        ("A", ProjectStatus::Succeeded),
+        ("B", ProjectStatus::Failed),
+        ("C", ProjectStatus::Skipped),
+    ]);
+}
+
+#[tokio::test]
+async fn keep_going_attempts_every_project() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), true, None).await;
+    let mut receiver = CompilerProgress::subscribe();
+    let backend = failing(&["A"]);
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.code, 1);
+    assert_eq!(statuses(&result), vec![
+        ("A", ProjectStatus::Failed),
+        ("B", ProjectStatus::Succeeded),
+        ("C", ProjectStatus::Succeeded),
+    ]);
+
+    let events = drain(&mut receiver);
+    let Some(CompilerProgressParams::Completed { success, lines, projects, .. }) = events.last() else {
+        panic!("expected a Completed event last");
+    };
+    assert!(!success);
+    assert_eq!(projects, &result.projects);
+    assert!(lines.iter().any(|line| line.contains('A') && line.contains("FAILED")));
+    assert!(lines.iter().any(|line| line.contains("2 of 3 project(s) succeeded, 1 failed")));
+}
+
+#[tokio::test]
+async fn bulk_compile_succeeds_when_every_project_does() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), false, None).await;
+    let backend = failing(&[]);
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(result.success);
+    assert_eq!(result.code, 0);
+    assert!(result.projects.iter().all(|p| p.status == ProjectStatus::Succeeded));
+}
+
+/// Give the projects A, B and C of [`setup_workspace`] a `.dproj` and an
+/// `.exe` written after their sources, so that they are up to date.
+async fn build_outputs(dir: &std::path::Path) {
+    let built = std::time::SystemTime::now() + Duration::from_secs(3600);
+    let mut data = PROJECTS_DATA.write().await;
+    for project in data.projects.iter_mut() {
+        let dproj = dir.join(format!("{}.dproj", project.name));
+        std::fs::write(&dproj, format!(
+            r#"<Project>
+    <PropertyGroup>
+        <MainSource>{}.dpr</MainSource>
+        <Config Condition="'$(Config)'==''">Debug</Config>
+        <Platform Condition="'$(Platform)'==''">Win32</Platform>
+    </PropertyGroup>
+    <ItemGroup>
+        <BuildConfiguration Include="Base"><Key>Base</Key></BuildConfiguration>
+        <BuildConfiguration Include="Debug"><Key>Cfg_1</Key><CfgParent>Base</CfgParent></BuildConfiguration>
+    </ItemGroup>
+</Project>"#,
+            project.name
+        )).unwrap();
+        project.dproj = Some(dproj.to_string_lossy().to_string());
+        let exe = dir.join(format!("{}.exe", project.name));
+        std::fs::write(&exe, "").unwrap();
+        std::fs::File::options().write(true).open(&exe).unwrap().set_modified(built).unwrap();
+    }
+}
+
+/// Rebuild every project of `params`, so that the build history knows what
+/// their outputs were built with.
+async fn rebuild_workspace(params: &CompileProjectParams) {
+    let CompileProjectParams::AllInWorkspace { workspace_id, event_id, keep_going, jobs, .. } = params.clone() else {
+        unreachable!()
+    };
+    let rebuild = CompileProjectParams::AllInWorkspace { workspace_id, rebuild: true, event_id, keep_going, jobs };
+    let result = Compiler::new_standalone(&rebuild).await.with_backend(failing(&[])).compile().await.unwrap();
+    assert!(result.projects.iter().all(|p| p.status == ProjectStatus::Succeeded));
+}
+
+#[tokio::test]
+async fn bulk_compile_skips_projects_that_are_up_to_date() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), false, None).await;
+    build_outputs(dir.path()).await;
+    rebuild_workspace(&params).await;
+    let mut receiver = CompilerProgress::subscribe();
+    let backend = failing(&[]);
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend.clone()).compile().await.unwrap();
+    assert!(result.success);
+    assert!(result.projects.iter().all(|p| p.status == ProjectStatus::UpToDate));
+    assert!(backend.log.lock().unwrap().is_empty());
+    let events = drain(&mut receiver);
+    let Some(CompilerProgressParams::Completed { lines, .. }) = events.last() else {
+        panic!("expected a Completed event last");
+    };
+    assert!(lines.iter().any(|line| line.contains('B') && line.contains("UP TO DATE")));
+    assert!(lines.iter().any(|line| line.contains("3 of 3 project(s) succeeded (3 up to date)")));
+
+    // A changed source only rebuilds its project.
+    let changed = std::time::SystemTime::now() + Duration::from_secs(7200);
+    std::fs::File::options().write(true).open(dir.path().join("B.dpr")).unwrap().set_modified(changed).unwrap();
+    let result = Compiler::new_standalone(&params).await.with_backend(backend.clone()).compile().await.unwrap();
+    assert_eq!(statuses(&result), vec![
+        ("A", ProjectStatus::UpToDate),
+        ("B", ProjectStatus::Succeeded),
+        ("C", ProjectStatus::UpToDate),
+    ]);
+    assert_eq!(*backend.log.lock().unwrap(), vec!["start B".to_string(), "end B".to_string()]);
+}
+
+#[tokio::test]
+async fn rebuild_and_rebuilt_dependencies_ignore_up_to_date_outputs() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), false, None).await;
+    build_outputs(dir.path()).await;
+    rebuild_workspace(&params).await;
+
+    // A depends on C, so A is built again when C is.
+    declare_a_depends_on_c(dir.path()).await;
+    build_outputs(dir.path()).await;
+    let changed = std::time::SystemTime::now() + Duration::from_secs(7200);
+    std::fs::File::options().write(true).open(dir.path().join("C.dpr")).unwrap().set_modified(changed).unwrap();
+    let result = Compiler::new_standalone(&params).await.with_backend(failing(&[])).compile().await.unwrap();
+    let mut statuses = statuses(&result);
+    statuses.sort_by_key(|(name, _)| *name);
+    assert_eq!(statuses, vec![
+        ("A", ProjectStatus::Succeeded),
+        ("B", ProjectStatus::UpToDate),
+        ("C", ProjectStatus::Succeeded),
+    ]);
+}
+
+#[tokio::test]
+async fn outputs_of_another_compiler_are_not_up_to_date() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), false, None).await;
+    build_outputs(dir.path()).await;
+    rebuild_workspace(&params).await;
+    let result = Compiler::new_standalone(&params).await.with_backend(failing(&[])).compile().await.unwrap();
+    assert!(result.projects.iter().all(|p| p.status == ProjectStatus::UpToDate));
+
+    PROJECTS_DATA.write().await.workspaces[0].compiler_id = "11.0".into();
+    let backend = failing(&[]);
+    let result = Compiler::new_standalone(&params).await.with_backend(backend.clone()).compile().await.unwrap();
+    assert!(result.projects.iter().all(|p| p.status == ProjectStatus::Succeeded));
+    assert_eq!(backend.log.lock().unwrap().len(), 6);
+
+    // Built with the new compiler, the outputs are up to date again.
+    let result = Compiler::new_standalone(&params).await.with_backend(failing(&[])).compile().await.unwrap();
+    assert!(result.projects.iter().all(|p| p.status == ProjectStatus::UpToDate));
+}
+
+/// Declare in a group project that project A depends on project C.  The
+/// projects get (empty) `.dproj` files so they can be matched.
+async fn declare_a_depends_on_c(dir: &std::path::Path) {
+    let groupproj = dir.join("All.groupproj");
+    std::fs::write(
+        &groupproj,
+        r#"<Project><ItemGroup><Projects Include="A.dproj"><Dependencies>C.dproj</Dependencies></Projects></ItemGroup></Project>"#,
+    )
+    .unwrap();
+    let mut data = PROJECTS_DATA.write().await;
+    for project in &mut data.projects {
+        let dproj = dir.join(format!("{}.dproj", project.name));
+        std::fs::write(&dproj, "<Project/>").unwrap();
+        project.dproj = Some(dproj.to_string_lossy().to_string());
+    }
+    data.group_project = Some(GroupProject { path: groupproj.to_string_lossy().to_string(), ..Default::default() });
+}
+
+fn position(log: &[String], entry: &str) -> usize {
+    log.iter().position(|e| e == entry).unwrap_or_else(|| panic!("{entry} not in {log:?}"))
+}
+
+#[tokio::test]
+async fn parallel_compile_builds_independent_projects_at_once() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), false, Some(3)).await;
+    let mut receiver = CompilerProgress::subscribe();
+    let backend = Arc::new(ProjectsBackend { delay: Duration::from_millis(300), ..Default::default() });
+    let log = backend.log.clone();
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(result.success);
+    let log = log.lock().unwrap().clone();
+    assert!(position(&log, "start C") < position(&log, "end A"), "not parallel: {log:?}");
+
+    let events = drain(&mut receiver);
+    for (name, id) in [("A", 1), ("B", 2), ("C", 3)] {
+        assert!(events.iter().any(|e| matches!(
+            e,
+            CompilerProgressParams::Stdout { line, project_id: Some(project_id) }
+                if *line == format!("building {name}") && *project_id == id
+        )));
+    }
+}
+
+#[tokio::test]
+async fn parallel_compile_waits_for_dependencies() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), false, Some(3)).await;
+    declare_a_depends_on_c(dir.path()).await;
+    let backend = Arc::new(ProjectsBackend { delay: Duration::from_millis(200), ..Default::default() });
+    let log = backend.log.clone();
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    PROJECTS_DATA.write().await.group_project = None;
+    assert!(result.success);
+    let log = log.lock().unwrap().clone();
+    assert!(position(&log, "end C") < position(&log, "start A"), "A started before C: {log:?}");
+    assert!(position(&log, "start C") < position(&log, "end B"), "B and C not parallel: {log:?}");
+    assert_eq!(statuses(&result), vec![
+        ("B", ProjectStatus::Succeeded),
+        ("C", ProjectStatus::Succeeded),
+        ("A", ProjectStatus::Succeeded),
+    ]);
+}
+
+#[tokio::test]
+async fn keep_going_skips_projects_whose_dependency_failed() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), true, Some(2)).await;
+    declare_a_depends_on_c(dir.path()).await;
+
+    let result = Compiler::new_standalone(&params).await.with_backend(failing(&["C"])).compile().await.unwrap();
+    PROJECTS_DATA.write().await.group_project = None;
+    assert!(!result.success);
+    assert_eq!(statuses(&result), vec![
+        ("B", ProjectStatus::Succeeded),
+        ("C", ProjectStatus::Failed),
+        ("A", ProjectStatus::Skipped),
+    ]);
+}
+
+#[tokio::test]
+async fn serial_compile_follows_dependencies() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), false, None).await;
+    declare_a_depends_on_c(dir.path()).await;
+    let backend = failing(&[]);
+    let log = backend.log.clone();
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    PROJECTS_DATA.write().await.group_project = None;
+    assert!(result.success);
+    assert_eq!(*log.lock().unwrap(), vec!["start B", "end B", "start C", "end C", "start A", "end A"]);
+}
+
+#[tokio::test]
+async fn serial_compile_follows_workspace_dependencies() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), false, None).await;
+    PROJECTS_DATA.write().await.add_project_dependency(10, 1, 3).unwrap();
+    let backend = failing(&[]);
+    let log = backend.log.clone();
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(result.success);
+    assert_eq!(*log.lock().unwrap(), vec!["start B", "end B", "start C", "end C", "start A", "end A"]);
+}
+
+#[tokio::test]
+async fn with_dependencies_builds_declared_dependencies_first() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    setup_workspace(dir.path(), false, None).await;
+    {
+        let mut data = PROJECTS_DATA.write().await;
+        data.add_project_dependency(10, 1, 3).unwrap();
+        data.add_project_dependency(10, 3, 2).unwrap();
+    }
+    let params = |with_dependencies| CompileProjectParams::Project {
+        project_id: 1,
+        project_link_id: Some(11),
+        rebuild: false,
+        event_id: "test".into(),
+        with_dependencies,
+    };
+
+    let backend = failing(&[]);
+    let log = backend.log.clone();
+    let result = Compiler::new_standalone(&params(true)).await.with_backend(backend).compile().await.unwrap();
+    assert!(result.success);
+    assert_eq!(*log.lock().unwrap(), vec!["start B", "end B", "start C", "end C", "start A", "end A"]);
+    assert_eq!(statuses(&result), vec![
+        ("B", ProjectStatus::Succeeded),
+        ("C", ProjectStatus::Succeeded),
+        ("A", ProjectStatus::Succeeded),
+    ]);
+
+    let backend = failing(&["C"]);
+    let log = backend.log.clone();
+    let result = Compiler::new_standalone(&params(true)).await.with_backend(backend).compile().await.unwrap();
+    assert!(!result.success);
+    assert!(!log.lock().unwrap().contains(&"start A".to_string()));
+
+    let backend = failing(&[]);
+    let log = backend.log.clone();
+    Compiler::new_standalone(&params(false)).await.with_backend(backend).compile().await.unwrap();
+    assert_eq!(*log.lock().unwrap(), vec!["start A", "end A"]);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Build queue
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// Parameters to compile one project of the workspace of `setup_workspace`.
+fn single_project(project_id: usize) -> CompileProjectParams {
+    CompileProjectParams::Project {
+        project_id,
+        project_link_id: Some(10 + project_id),
+        rebuild: false,
+        event_id: "test".into(),
+        with_dependencies: false,
+    }
+}
+
+fn job_state(job_id: queue::JobId) -> Option<queue::JobState> {
+    queue::jobs().iter().find(|job| job.id == job_id).map(|job| job.state)
+}
+
+#[tokio::test]
+async fn concurrent_compiles_are_queued() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    setup_workspace(dir.path(), false, None).await;
+    let backend = Arc::new(ProjectsBackend { delay: Duration::from_millis(200), ..Default::default() });
+    let log = backend.log.clone();
+    let mut receiver = CompilerProgress::subscribe();
+    let second_job = queue::next_job_id();
+    let first = Compiler::new_standalone(&single_project(1)).await.with_backend(backend.clone());
+    let second = Compiler::new_standalone(&single_project(2)).await.with_backend(backend).with_job_id(second_job);
+
+    let (first, second) = tokio::join!(first.compile(), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        second.compile().await
+    });
+    assert!(first.unwrap().success);
+    let second = second.unwrap();
+    assert!(second.success);
+    assert_eq!(second.job_id, second_job);
+    assert_eq!(*log.lock().unwrap(), vec!["start A", "end A", "start B", "end B"]);
+    assert!(drain(&mut receiver).contains(&CompilerProgressParams::Queued { job_id: second_job, jobs_ahead: 1 }));
+    assert_eq!(job_state(second_job), Some(queue::JobState::Succeeded));
+}
+
+#[tokio::test]
+async fn queued_compile_can_be_cancelled_before_it_starts() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    setup_workspace(dir.path(), false, None).await;
+    let backend = Arc::new(ProjectsBackend { delay: Duration::from_millis(300), ..Default::default() });
+    let log = backend.log.clone();
+    let second_job = queue::next_job_id();
+    let first = Compiler::new_standalone(&single_project(1)).await.with_backend(backend.clone());
+    let second = Compiler::new_standalone(&single_project(2)).await.with_backend(backend).with_job_id(second_job);
+
+    let (first, second, _) = tokio::join!(
+        first.compile(),
+        async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            second.compile().await
+        },
+        async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert_eq!(job_state(second_job), Some(queue::JobState::Queued));
+            queue::cancel(second_job).unwrap();
+        }
+    );
+    assert!(first.unwrap().success);
+    let second = second.unwrap();
+    assert!(second.cancelled);
+    assert!(second.projects.is_empty());
+    assert_eq!(*log.lock().unwrap(), vec!["start A", "end A"]);
+    assert_eq!(job_state(second_job), Some(queue::JobState::Cancelled));
+    assert!(queue::cancel(second_job).is_err());
+}
+
+#[tokio::test]
+async fn cancelling_the_running_job_starts_the_next_one() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let hanging = Arc::new(ScriptedBackend { stdout: vec![], exit_code: 0, hang: true });
+    let quick = Arc::new(ScriptedBackend { stdout: vec![], exit_code: 0, hang: false });
+    let first_job = queue::next_job_id();
+    let first = Compiler::new_standalone(&params).await.with_backend(hanging).with_job_id(first_job);
+    let second = Compiler::new_standalone(&params).await.with_backend(quick);
+
+    let run = async {
+        tokio::join!(
+            first.compile(),
+            async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                second.compile().await
+            },
+            async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                queue::cancel(first_job).unwrap();
+            }
+        )
+    };
+    let (first, second, _) = tokio::time::timeout(Duration::from_secs(10), run)
+        .await
+        .expect("cancellation should stop the running job");
+    assert!(first.unwrap().cancelled);
+    let second = second.unwrap();
+    assert!(second.success && !second.cancelled);
+    assert_eq!(job_state(first_job), Some(queue::JobState::Cancelled));
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Build lock
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// Take the build lock the way another DDK process would.
+fn lock_as_other_process() -> build_lock::BuildLock {
+    let holder = build_lock::BuildLockHolder {
+        program: "ddk-mcp-server".into(),
+        ..build_lock::BuildLockHolder::current("Compiling Project Other".into())
+    };
+    build_lock::try_acquire(&holder).unwrap().expect("build lock should be free")
+}
+
+#[tokio::test]
+async fn no_wait_fails_while_another_process_builds() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let other = lock_as_other_process();
+    assert_eq!(build_lock::current_holder().unwrap().description, "Compiling Project Other");
+
+    let backend = failing(&[]);
+    let log = backend.log.clone();
+    let error = Compiler::new_standalone(&params)
+        .await
+        .with_backend(backend)
+        .with_lock_policy(build_lock::LockPolicy::Fail)
+        .compile()
+        .await
+        .unwrap_err()
+        .to_string();
+    drop(other);
+    assert!(error.contains("'Compiling Project Other' in ddk-mcp-server"), "{error}");
+    assert!(log.lock().unwrap().is_empty());
+    assert_eq!(build_lock::current_holder(), None);
+}
+
+#[tokio::test]
+async fn compile_waits_for_another_process_to_finish() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let mut receiver = CompilerProgress::subscribe();
+    let other = lock_as_other_process();
+    let backend = failing(&[]);
+    let log = backend.log.clone();
+    let compiler = Compiler::new_standalone(&params).await.with_backend(backend);
+
+    let (result, _) = tokio::join!(compiler.compile(), async {
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(log.lock().unwrap().is_empty(), "built while the lock was held");
+        drop(other);
+    });
+    assert!(result.unwrap().success);
+    assert_eq!(*log.lock().unwrap(), vec!["start App", "end App"]);
+    assert!(drain(&mut receiver).iter().any(|e| matches!(
+        e,
+        CompilerProgressParams::Stdout { line, .. }
+            if line.starts_with("Waiting for the build 'Compiling Project Other' in ddk-mcp-server")
+    )));
+}
+
+#[tokio::test]
+async fn cancel_request_reaches_the_building_process() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let backend = Arc::new(ScriptedBackend { stdout: vec![], exit_code: 0, hang: true });
+    let compiler = Compiler::new_standalone(&params).await.with_backend(backend);
+
+    let run = async {
+        tokio::join!(compiler.compile(), async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            build_lock::request_cancel().unwrap()
+        })
+    };
+    let (result, holder) = tokio::time::timeout(Duration::from_secs(10), run)
+        .await
+        .expect("the cancel request should stop the build");
+    assert_eq!(holder.description, "Compiling Project App");
+    assert!(result.unwrap().cancelled);
+    assert_eq!(build_lock::current_holder(), None);
+    assert!(build_lock::request_cancel().is_err());
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Build history
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn compile_is_recorded_in_the_build_history() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let backend = Arc::new(ScriptedBackend {
+        stdout: vec![
+            r"C:\Projects\Unit1.pas(42,5): warning W1000: Symbol 'X' is deprecated",
+            r"C:\Projects\Unit1.pas(50): warning W1036: Variable 'I' might not have been initialized",
+            r"C:\Projects\Unit2.pas(7): hint H2164: Variable 'Y' is declared but never used",
+        ],
+        exit_code: 0,
+        hang: false,
+    });
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    let counts = DiagnosticCounts { errors: 0, warnings: 2, hints: 1 };
+    assert_eq!(result.projects[0].diagnostics, counts);
+
+    let build = history::get(result.history_id.expect("the build should be recorded")).unwrap();
+    assert_eq!(build.description, "Compiling Project App");
+    assert_eq!(build.compiler_key, "12.0");
+    assert_eq!(build.compiler_name, result.compiler_name);
+    assert!(build.success && !build.cancelled && !build.rebuild);
+    assert_eq!(build.code, 0);
+    assert!(build.finished_at >= build.started_at);
+    assert_eq!(build.diagnostics(), counts);
+    let [project] = build.projects.as_slice() else { panic!("expected one project") };
+    assert_eq!((project.project_id, project.project_name.as_str()), (1, "App"));
+    assert_eq!((project.config.as_str(), project.platform.as_str()), ("Debug", "Win32"));
+    assert_eq!(project.status, ProjectStatus::Succeeded);
+}
+
+#[tokio::test]
+async fn build_history_query_filters_by_project_and_outcome() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_workspace(dir.path(), true, None).await;
+    let compile = async |failing_projects: &[&'static str]| {
+        let compiler = Compiler::new_standalone(&params).await.with_backend(failing(failing_projects));
+        compiler.compile().await.unwrap().history_id.unwrap()
+    };
+    let b_failed = compile(&["B"]).await;
+    let green = compile(&[]).await;
+
+    let query = |project_id, success| history::HistoryQuery {
+        project_id: Some(project_id),
+        success: Some(success),
+        limit: Some(1),
+        ..Default::default()
+    };
+    let ids = |query: history::HistoryQuery| history::query(&query).iter().map(|b| b.id).collect::<Vec<_>>();
+    assert_eq!(ids(query(2, false)), vec![b_failed]);
+    assert_eq!(ids(query(2, true)), vec![green]);
+    // Project A succeeded in both builds; the most recent one comes first.
+    assert_eq!(ids(history::HistoryQuery { limit: Some(2), ..query(1, true) }), vec![green, b_failed]);
+    assert_eq!(history::get(b_failed).unwrap().status_str(), "failed");
+    assert!(ids(history::HistoryQuery { compiler_key: Some("none".into()), ..Default::default() }).is_empty());
+}
+
+#[tokio::test]
+async fn build_output_is_saved_and_replays_to_the_same_diagnostics() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let stdout = vec![
+        "Embarcadero Delphi for Win32 compiler",
+        "",
+        r"  C:\Delphi\VSS\SomeUnit",
+        r"C:\Projects\Unit1.pas(42,5): warning W1000: Symbol 'X' is deprecated",
+        r"C:\Projects\Unit1.pas(3): error E2003: Undeclared identifier: 'Foo'",
+    ];
+    let raw: String = stdout.iter().map(|line| format!("{line}\r\n")).collect();
+    let backend = Arc::new(ScriptedBackend { stdout, exit_code: 1, hang: false });
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    let build = history::get(result.history_id.unwrap()).unwrap();
+    let log = build.projects[0].log.clone().expect("the build output should be saved");
+    assert_eq!(std::path::PathBuf::from(&log), history::log_path(build.id, 1));
+    // Saved as written, including the lines the live view leaves out.
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), raw);
+
+    assert_eq!(result.diagnostics.len(), 2);
+    assert_eq!(parse_build_log(std::path::Path::new(&log), 1, dir.path(), None, WarningPolicy::default(), OutputLanguage::Auto).await.unwrap(), result.diagnostics);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Last-build diagnostics
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn last_build_diagnostics_are_kept_and_queried() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let backend = Arc::new(ScriptedBackend {
+        stdout: vec![
+            r"C:\Projects\Unit1.pas(42,5): warning W1000: Symbol 'X' is deprecated",
+            r"C:\Projects\Unit1.pas(50): warning W1036: Variable 'I' might not have been initialized",
+            r"C:\Projects\Unit2.pas(7): hint H2164: Variable 'Y' is declared but never used",
+        ],
+        exit_code: 0,
+        hang: false,
+    });
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+
+    let last = last_diagnostics::get(1).expect("the diagnostics should be kept");
+    assert_eq!(Some(last.build_id), result.history_id);
+    assert_eq!(last.project_name, "App");
+    assert_eq!(last.counts(), DiagnosticCounts { errors: 0, warnings: 2, hints: 1 });
+
+    let codes = |query: last_diagnostics::DiagnosticsQuery| -> Vec<String> {
+        let projects = last_diagnostics::query(&query);
+        projects.iter().flat_map(|p| p.diagnostics.iter().map(|d| d.code.clone())).collect()
+    };
+    let query = last_diagnostics::DiagnosticsQuery { project_id: Some(1), ..Default::default() };
+    assert_eq!(codes(query.clone()), vec!["W1000", "W1036", "H2164"]);
+    assert_eq!(codes(last_diagnostics::DiagnosticsQuery { severity: Some(DiagnosticKind::HINT), ..query.clone() }), vec!["H2164"]);
+    assert_eq!(codes(last_diagnostics::DiagnosticsQuery { code: Some("w1036".into()), ..query.clone() }), vec!["W1036"]);
+    assert_eq!(codes(last_diagnostics::DiagnosticsQuery { file: Some("unit1.PAS".into()), ..query.clone() }), vec!["W1000", "W1036"]);
+    assert!(last_diagnostics::query(&last_diagnostics::DiagnosticsQuery { project_id: Some(99), ..Default::default() }).is_empty());
+
+    // The next build of the project replaces them.
+    let backend = Arc::new(ScriptedBackend { stdout: vec![], exit_code: 0, hang: false });
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    let last = last_diagnostics::get(1).unwrap();
+    assert_eq!(Some(last.build_id), result.history_id);
+    assert!(last.diagnostics.is_empty());
+}
+
+#[tokio::test]
+async fn last_build_diagnostics_follow_other_processes() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let _ = setup_project(dir.path()).await;
+    let stored = |build_id, code: &str| last_diagnostics::ProjectDiagnostics {
+        project_id: 42,
+        project_name: "Other".into(),
+        build_id,
+        finished_at: 0,
+        diagnostics: vec![CompileDiagnostic {
+            project_id: 42,
+            file: "Unit1.pas".into(),
+            line: 1,
+            column: None,
+            code: code.into(),
+            severity: DiagnosticKind::WARN,
+            message: "Message".into(),
+            related: vec![],
+        }],
+    };
+    last_diagnostics::store(stored(1, "W1000")).unwrap();
+    assert_eq!(last_diagnostics::get(42).unwrap().build_id, 1);
+
+    // Another process writes the file behind this one's back.
+    let path = <ProjectsData as FilePath>::get_file_path().with_file_name("diagnostics").join("42.ron");
+    let newer = ron::ser::to_string_pretty(&stored(2, "W1036-longer"), Default::default()).unwrap();
+    std::fs::write(&path, newer).unwrap();
+    let last = last_diagnostics::get(42).unwrap();
+    assert_eq!((last.build_id, last.diagnostics[0].code.as_str()), (2, "W1036-longer"));
+}
+
+#[tokio::test]
+async fn last_build_diagnostics_at_a_line() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let _ = setup_project(dir.path()).await;
+    let unit = dir.path().join("Unit1.pas");
+    let diagnostic = |line, code: &str| CompileDiagnostic {
+        project_id: 42,
+        file: unit.to_string_lossy().to_string(),
+        line,
+        column: None,
+        code: code.into(),
+        severity: DiagnosticKind::WARN,
+        message: "Message".into(),
+        related: vec![],
+    };
+    last_diagnostics::store(last_diagnostics::ProjectDiagnostics {
+        project_id: 42,
+        project_name: "Other".into(),
+        build_id: 1,
+        finished_at: 0,
+        diagnostics: vec![diagnostic(3, "W1035"), diagnostic(3, "H2077"), diagnostic(4, "W1036")],
+    })
+    .unwrap();
+
+    let codes = |file: &std::path::Path, line| -> Vec<String> {
+        last_diagnostics::at(file, line).into_iter().map(|d| d.code).collect()
+    };
+    assert_eq!(codes(&unit, 3), vec!["W1035", "H2077"]);
+    // Editors may spell the path with another letter case than the compiler.
+    let upper = dir.path().join("UNIT1.PAS");
+    assert_eq!(codes(&upper, 4), vec!["W1036"]);
+    assert!(codes(&unit, 5).is_empty());
+}
+
+#[tokio::test]
+async fn warning_policy_promotes_and_suppresses_codes() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    PROJECTS_DATA.write().await.workspaces[0].warning_policy = WarningPolicy {
+        errors: vec!["W1036".into()],
+        suppress: vec!["H2164".into()],
+        max_warnings: None,
+    };
+    let mut receiver = CompilerProgress::subscribe();
+    let backend = Arc::new(ScriptedBackend {
+        stdout: vec![
+            r"C:\Projects\Unit1.pas(3): warning W1036: Variable 'I' might not have been initialized",
+            r"C:\Projects\Unit1.pas(4): hint H2164: Variable 'J' is declared but never used in 'Run'",
+            r"C:\Projects\Unit1.pas(5): warning W1000: Symbol 'X' is deprecated",
+        ],
+        exit_code: 0,
+        hang: false,
+    });
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.code, 0);
+    let outcome = &result.projects[0];
+    assert_eq!(outcome.status, ProjectStatus::Failed);
+    assert_eq!(outcome.diagnostics, DiagnosticCounts { errors: 1, warnings: 1, hints: 0 });
+    assert_eq!(outcome.policy_violations, vec!["1 warning(s)/hint(s) treated as errors: W1036"]);
+
+    let events = drain(&mut receiver);
+    let codes: Vec<(String, DiagnosticKind)> =
+        result.diagnostics.iter().map(|diagnostic| (diagnostic.code.clone(), diagnostic.severity)).collect();
+    assert_eq!(codes, vec![("W1036".into(), DiagnosticKind::ERROR), ("W1000".into(), DiagnosticKind::WARN)]);
+    assert!(!events.iter().any(|e| matches!(e, CompilerProgressParams::Stdout { line, .. } if line.contains("H2164"))));
+    let Some(CompilerProgressParams::Completed { success, lines, .. }) = events.last() else {
+        panic!("expected a Completed event last");
+    };
+    assert!(!success);
+    assert!(lines.iter().any(|line| line.contains("Warning policy: 1 warning(s)/hint(s) treated as errors: W1036")));
+    assert!(lines.iter().any(|line| line.contains("FAILED")));
+
+    // The saved log replays under the same policy.
+    let log = history::log_path(result.history_id.unwrap(), 1);
+    let parsed = ddk_core::commands::cmd_parse_build_log(log, Some(1), None, OutputLanguage::Auto).await.unwrap();
+    assert_eq!(parsed.diagnostics, result.diagnostics);
+}
+
+#[tokio::test]
+async fn warning_policy_limits_warnings_per_project() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_project(dir.path()).await;
+    let stdout = vec![
+        r"C:\Projects\Unit1.pas(3): warning W1000: Symbol 'X' is deprecated",
+        r"C:\Projects\Unit1.pas(4): warning W1000: Symbol 'Y' is deprecated",
+    ];
+    let compile = async |max_warnings| {
+        PROJECTS_DATA.write().await.projects[0].warning_policy.max_warnings = max_warnings;
+        let backend = Arc::new(ScriptedBackend { stdout: stdout.clone(), exit_code: 0, hang: false });
+        Compiler::new_standalone(&params).await.with_backend(backend).compile().await.unwrap()
+    };
+
+    let result = compile(Some(2)).await;
+    assert!(result.success);
+    assert!(result.projects[0].policy_violations.is_empty());
+
+    // The project's limit replaces the workspace's.
+    PROJECTS_DATA.write().await.workspaces[0].warning_policy.max_warnings = Some(0);
+    let result = compile(Some(1)).await;
+    assert!(!result.success);
+    assert_eq!(result.projects[0].policy_violations, vec!["2 warning(s), more than the 1 allowed"]);
+    let result = compile(None).await;
+    assert_eq!(result.projects[0].policy_violations, vec!["2 warning(s), more than the 0 allowed"]);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Backend helpers
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// Builds every project at once, writing the `.exe` next to its project
+/// file, fails the combinations listed in `failing` (e.g. "Release Win64
+/// 11.0"), and logs each build as "<project> <config> <platform> <compiler>".
+#[derive(Default)]
+struct MatrixBackend {
+    failing: Vec<&'static str>,
+    log: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl BuildBackend for MatrixBackend {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    async fn start(&self, request: &BuildRequest<'_>) -> Result<BuildProcess> {
+        let version = request.configuration.product_name.split(' ').nth(1).unwrap_or_default();
+        let build = format!("{} {} {} {version}", request.project.name, request.config, request.platform);
+        let failing = self.failing.iter().any(|combination| build.ends_with(combination));
+        self.log.lock().unwrap().push(build);
+        let exe = request.project_file.with_extension("exe");
+        std::fs::write(&exe, "").unwrap();
+        let built = std::time::SystemTime::now() + Duration::from_secs(3600);
+        std::fs::File::options().write(true).open(&exe).unwrap().set_modified(built).unwrap();
+        Ok(BuildProcess {
+            stdout: Box::new(Cursor::new(Vec::new())),
+            stderr: Box::new(Cursor::new(Vec::new())),
+            handle: Box::new(ProjectsHandle {
+                name: request.project.name.clone(),
+                exit_code: if failing { 1 } else { 0 },
+                delay: Duration::ZERO,
+                log: Arc::default(),
+            }),
+        })
+    }
+}
+
+/// A `.dproj` with the configurations Debug and Release and the enabled
+/// platforms Win32 and Win64.
+const MATRIX_DPROJ: &str = r#"<Project xmlns="http://schemas.microsoft.com/developer/msbuild/2003">
+    <PropertyGroup>
+        <MainSource>App.dpr</MainSource>
+        <Config Condition="'$(Config)'==''">Debug</Config>
+        <Platform Condition="'$(Platform)'==''">Win32</Platform>
+    </PropertyGroup>
+    <ItemGroup>
+        <BuildConfiguration Include="Base"><Key>Base</Key></BuildConfiguration>
+        <BuildConfiguration Include="Debug"><Key>Cfg_1</Key><CfgParent>Base</CfgParent></BuildConfiguration>
+        <BuildConfiguration Include="Release"><Key>Cfg_2</Key><CfgParent>Base</CfgParent></BuildConfiguration>
+    </ItemGroup>
+    <ProjectExtensions>
+        <BorlandProject>
+            <Platforms>
+                <Platform value="Win32">True</Platform>
+                <Platform value="Win64">True</Platform>
+                <Platform value="Linux64">False</Platform>
+            </Platforms>
+        </BorlandProject>
+    </ProjectExtensions>
+</Project>"#;
+
+/// Give the project of [`setup_project`] the `.dproj` [`MATRIX_DPROJ`] and
+/// return the parameters to build it as a matrix.
+async fn setup_matrix(
+    dir: &std::path::Path,
+    configurations: &[&str],
+    platforms: &[&str],
+    compilers: &[&str],
+    keep_going: bool,
+) -> CompileProjectParams {
+    setup_project(dir).await;
+    let dproj = dir.join("App.dproj");
+    std::fs::write(&dproj, MATRIX_DPROJ).unwrap();
+    PROJECTS_DATA.write().await.projects[0].dproj = Some(dproj.to_string_lossy().to_string());
+    let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+    CompileProjectParams::Matrix {
+        project_id: Some(1),
+        workspace_id: None,
+        configurations: strings(configurations),
+        platforms: strings(platforms),
+        compilers: strings(compilers),
+        rebuild: false,
+        event_id: "test".into(),
+        keep_going,
+        jobs: None,
+    }
+}
+
+#[tokio::test]
+async fn matrix_builds_every_combination() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_matrix(dir.path(), &[], &[], &["11.0", "12.0"], false).await;
+    let mut receiver = CompilerProgress::subscribe();
+    let backend = Arc::new(MatrixBackend::default());
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend.clone()).compile().await.unwrap();
+    assert!(result.success);
+    assert_eq!(*backend.log.lock().unwrap(), vec![
+        "App Debug Win32 11.0",
+        "App Debug Win64 11.0",
+        "App Release Win32 11.0",
+        "App Release Win64 11.0",
+        "App Debug Win32 12.0",
+        "App Debug Win64 12.0",
+        "App Release Win32 12.0",
+        "App Release Win64 12.0",
+    ]);
+    assert_eq!(result.matrix.len(), 8);
+    assert_eq!(result.projects.len(), 8);
+    assert!(result.matrix.iter().all(|cell| cell.status == ProjectStatus::Succeeded && cell.history_id.is_some()));
+    // Each combination is recorded with its own configuration and compiler.
+    let last = history::get(result.matrix[7].history_id.unwrap()).unwrap();
+    assert_eq!(last.compiler_key, "12.0");
+    assert_eq!((last.projects[0].config.as_str(), last.projects[0].platform.as_str()), ("Release", "Win64"));
+    // The saved settings are not changed.
+    assert_eq!(PROJECTS_DATA.read().await.projects[0].active_configuration, None);
+
+    let events = drain(&mut receiver);
+    assert_eq!(events.iter().filter(|e| matches!(e, CompilerProgressParams::Start { .. })).count(), 1);
+    let Some(CompilerProgressParams::Completed { lines, .. }) = events.last() else {
+        panic!("expected a Completed event last");
+    };
+    assert!(lines.iter().any(|line| line.contains("Config") && line.contains("11.0") && line.contains("12.0")));
+    assert!(lines.iter().any(|line| line.contains("Release") && line.contains("Win64") && line.matches("OK").count() == 2));
+    assert!(lines.iter().any(|line| line.contains("8 of 8 combination(s) succeeded, 0 failed, 0 skipped.")));
+}
+
+#[tokio::test]
+async fn matrix_stops_at_failing_combination_unless_keep_going() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let backend = Arc::new(MatrixBackend { failing: vec!["Release Win64 11.0"], ..Default::default() });
+    let params = setup_matrix(dir.path(), &["release"], &["Win32", "Win64"], &["11.0", "12.0"], false).await;
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend.clone()).compile().await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.code, 1);
+    let statuses: Vec<_> = result.matrix.iter().map(|cell| (cell.platform.as_str(), cell.compiler_key.as_str(), cell.status)).collect();
+    assert_eq!(statuses, vec![
+        ("Win32", "11.0", ProjectStatus::Succeeded),
+        ("Win64", "11.0", ProjectStatus::Failed),
+        ("Win32", "12.0", ProjectStatus::Skipped),
+        ("Win64", "12.0", ProjectStatus::Skipped),
+    ]);
+    assert_eq!(backend.log.lock().unwrap().len(), 2);
+
+    let params = setup_matrix(dir.path(), &["Release"], &["Win32", "Win64"], &["11.0", "12.0"], true).await;
+    let result = Compiler::new_standalone(&params).await.with_backend(backend.clone()).compile().await.unwrap();
+    assert!(!result.success);
+    let failed: Vec<String> = result.matrix.iter().filter(|cell| cell.status == ProjectStatus::Failed).map(|cell| cell.to_string()).collect();
+    assert_eq!(failed, vec!["Release | Win64 | Delphi 11.0 Alexandria"]);
+    assert_eq!(backend.log.lock().unwrap().len(), 6);
+}
+
+#[tokio::test]
+async fn workspace_matrix_builds_up_to_date_projects_with_every_compiler() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    setup_workspace(dir.path(), false, None).await;
+    build_outputs(dir.path()).await;
+    let params = CompileProjectParams::Matrix {
+        project_id: None,
+        workspace_id: Some(10),
+        configurations: vec!["Debug".into()],
+        platforms: vec!["Win32".into()],
+        compilers: vec!["11.0".into(), "12.0".into()],
+        rebuild: false,
+        event_id: "test".into(),
+        keep_going: false,
+        jobs: None,
+    };
+    let backend = Arc::new(MatrixBackend::default());
+
+    let result = Compiler::new_standalone(&params).await.with_backend(backend.clone()).compile().await.unwrap();
+    assert!(result.success);
+    assert!(result.projects.iter().all(|p| p.status == ProjectStatus::Succeeded));
+    assert_eq!(*backend.log.lock().unwrap(), vec![
+        "A Debug Win32 11.0",
+        "B Debug Win32 11.0",
+        "C Debug Win32 11.0",
+        "A Debug Win32 12.0",
+        "B Debug Win32 12.0",
+        "C Debug Win32 12.0",
+    ]);
+}
+
+#[tokio::test]
+async fn matrix_rejects_unknown_values() {
+    let _guard = COMPILE_LOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let params = setup_matrix(dir.path(), &["Profile"], &[], &[], false).await;
+    let error = Compiler::new_standalone(&params).await.compile().await.unwrap_err().to_string();
+    assert_eq!(error, "Unknown configuration \"Profile\". Available: Debug, Release");
+    let params = setup_matrix(dir.path(), &[], &["Win64"], &["99.0"], false).await;
+    let error = Compiler::new_standalone(&params).await.compile().await.unwrap_err().to_string();
+    assert!(error.starts_with("Unknown compiler key: \"99.0\""), "{error}");
+}
+
+fn configuration(backend: BuildBackendKind) -> CompilerConfiguration {
+    CompilerConfiguration {
+        condition: "VER360".into(),
+        product_name: "Delphi 12".into(),
+        product_version: 23,
+        package_version: 290,
+        compiler_version: 36,
+        installation_path: r"C:\Delphi\23.0".into(),
+        build_arguments: vec![],
+        backend,
+        msbuild_path: None,
+        env_script: None,
+        extra_env: Default::default(),
+        output_language: Default::default(),
+    }
+}
+
+#[test]
+fn custom_command_expands_placeholders() {
+    let project = Project { id: 1, name: "App".into(), ..Default::default() };
+    let configuration = configuration(BuildBackendKind::Custom {
+        program: "build.cmd".into(),
+        args: vec!["{project_file}".into(), "{config}|{platform}|{target}".into()],
+    });
+    let request = BuildRequest {
+        project: &project,
+        project_file: std::path::PathBuf::from("/src/App.dproj"),
+        configuration: &configuration,
+        config: "Release".into(),
+        platform: "Win64".into(),
+        rebuild: true,
+    };
+    let backend = CustomCommandBackend { program: "build.cmd".into(), args: vec![] };
+    assert_eq!(backend.expand("{project_file}", &request), "/src/App.dproj");
+    assert_eq!(backend.expand("{project_dir}", &request), "/src");
+    assert_eq!(backend.expand("{config}|{platform}|{target}", &request), "Release|Win64|Build");
+    assert_eq!(backend.expand("{installation_path}", &request), r"C:\Delphi\23.0");
+}
+
+#[tokio::test]
+async fn msbuild_without_environment_names_the_script_and_bds() {
+    let project = Project { id: 1, name: "App".into(), ..Default::default() };
+    let mut configuration = configuration(BuildBackendKind::MsBuild);
+    configuration.env_script = Some("/missing/env.bat".into());
+    let request = BuildRequest {
+        project: &project,
+        project_file: std::path::PathBuf::from("/src/App.dproj"),
+        configuration: &configuration,
+        config: "Debug".into(),
+        platform: "Win32".into(),
+        rebuild: false,
+    };
+    let Err(error) = MsBuildBackend.start(&request).await else { panic!("expected an error") };
+    assert_eq!(
+        error.to_string(),
+        "Cannot find environment script at path: /missing/env.bat, or set BDS in extra_env"
+    );
+}
+
+#[test]
+fn dcc_name_per_platform() {
+    assert_eq!(dcc_name("Win32"), Some("dcc32"));
+    assert_eq!(dcc_name("Win64"), Some("dcc64"));
+    assert_eq!(dcc_name("OSX64"), Some("dccosx64"));
+    assert_eq!(dcc_name("Amiga"), None);
+}
+
+const DCC_DPROJ: &str = r#"<Project xmlns="http://schemas.microsoft.com/developer/msbuild/2003">
+    <PropertyGroup>
+        <MainSource>App.dpr</MainSource>
+        <Config Condition="'$(Config)'==''">Debug</Config>
+        <Platform Condition="'$(Platform)'==''">Win32</Platform>
+    </PropertyGroup>
+    <PropertyGroup Condition="'$(Base)'!=''">
+        <DCC_Define>APP;$(DCC_Define)</DCC_Define>
+        <DCC_UnitSearchPath>..\lib;..\shared;$(DCC_UnitSearchPath)</DCC_UnitSearchPath>
+        <DCC_Namespace>System;Vcl;Winapi;$(DCC_Namespace)</DCC_Namespace>
+        <DCC_DcuOutput>.\$(Platform)\$(Config)\dcu</DCC_DcuOutput>
+        <DCC_ExeOutput>.\$(Platform)\$(Config)</DCC_ExeOutput>
+        <DCC_Hints>false</DCC_Hints>
+        <DCC_SYMBOL_PLATFORM>false</DCC_SYMBOL_PLATFORM>
+        <DCC_UNSAFE_CAST>true</DCC_UNSAFE_CAST>
+    </PropertyGroup>
+    <PropertyGroup Condition="'$(Cfg_1)'!=''">
+        <DCC_Define>DEBUG;$(DCC_Define)</DCC_Define>
+        <DCC_IMPLICIT_STRING_CAST>error</DCC_IMPLICIT_STRING_CAST>
+    </PropertyGroup>
+    <ItemGroup>
+        <BuildConfiguration Include="Base">
+            <Key>Base</Key>
+        </BuildConfiguration>
+        <BuildConfiguration Include="Debug">
+            <Key>Cfg_1</Key>
+            <CfgParent>Base</CfgParent>
+        </BuildConfiguration>
+        <BuildConfiguration Include="Release">
+            <Key>Cfg_2</Key>
+            <CfgParent>Base</CfgParent>
+        </BuildConfiguration>
+    </ItemGroup>
+</Project>"#;
+
+#[test]
+fn dcc_arguments_follow_dproj_settings() {
+    let dproj = dproj_rs::Dproj::parse(DCC_DPROJ).unwrap();
+    let group = dproj.active_property_group_for("Debug", "Win64").unwrap();
+    let args = dcc_arguments(&group, std::path::Path::new("App.dpr"), false);
+    assert_eq!(args, vec![
+        "-M",
+        "-DDEBUG;APP",
+        r"-U..\lib;..\shared",
+        r"-I..\lib;..\shared",
+        r"-R..\lib;..\shared",
+        r"-O..\lib;..\shared",
+        "-NSSystem;Vcl;Winapi",
+        r"-NU.\Win64\Debug\dcu",
+        r"-E.\Win64\Debug",
+        "-H-",
+        "-W+UNSAFE_CAST",
+        "-W-SYMBOL_PLATFORM",
+        "-W^IMPLICIT_STRING_CAST",
+        "App.dpr",
+    ]);
+}
+
+#[test]
+fn dcc_arguments_without_settings() {
+    let dproj = dproj_rs::Dproj::parse(DCC_DPROJ).unwrap();
+    let group = dproj.active_property_group_for("Release", "Win32").unwrap();
+    let args = dcc_arguments(&group, std::path::Path::new("App.dpr"), true);
+    assert_eq!(args.first().map(String::as_str), Some("-B"));
+    assert!(args.contains(&"-DAPP".to_string()));
+    assert!(!args.iter().any(|arg| arg.starts_with("-W^")));
+}
+
+#[test]
+fn backend_for_follows_configuration() {
+    assert_eq!(backend_for(&configuration(BuildBackendKind::MsBuild)).name(), "MSBuild");
+    assert_eq!(backend_for(&configuration(BuildBackendKind::Dcc)).name(), "DCC");
+    let custom = BuildBackendKind::Custom { program: "make".into(), args: vec![] };
+    assert_eq!(backend_for(&configuration(custom)).name(), "custom command");
+}
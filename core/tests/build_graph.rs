@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use ddk_core::files::groupproj::parse_groupproj_dependencies;
+use ddk_core::projects::*;
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Helpers
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// A `.dproj` building `main_source`, with `properties` in the base
+/// configuration and `items` in an extra item group.
+fn dproj(main_source: &str, properties: &str, items: &str) -> String {
+    format!(
+        r#"<Project xmlns="http://schemas.microsoft.com/developer/msbuild/2003">
+    <PropertyGroup>
+        <MainSource>{main_source}</MainSource>
+        <Config Condition="'$(Config)'==''">Debug</Config>
+        <Platform Condition="'$(Platform)'==''">Win32</Platform>
+    </PropertyGroup>
+    <PropertyGroup Condition="'$(Base)'!=''">
+        {properties}
+    </PropertyGroup>
+    <ItemGroup>
+        <DelphiCompile Include="$(MainSource)">
+            <MainSource>MainSource</MainSource>
+        </DelphiCompile>
+        {items}
+    </ItemGroup>
+    <ItemGroup>
+        <BuildConfiguration Include="Base">
+            <Key>Base</Key>
+        </BuildConfiguration>
+        <BuildConfiguration Include="Debug">
+            <Key>Cfg_1</Key>
+            <CfgParent>Base</CfgParent>
+        </BuildConfiguration>
+    </ItemGroup>
+</Project>"#
+    )
+}
+
+/// Write `content` to `dir/<name>.dproj` and load the references of the
+/// project for Debug / Win64.
+fn references(dir: &Path, name: &str, content: &str) -> Option<ProjectReferences> {
+    let path = dir.join(format!("{name}.dproj"));
+    std::fs::write(&path, content).unwrap();
+    let project = Project {
+        id: 1,
+        name: name.into(),
+        dproj: Some(path.to_string_lossy().to_string()),
+        ..Default::default()
+    };
+    ProjectReferences::load(&project, "Debug", "Win64", HashMap::new())
+}
+
+fn graph(len: usize, edges: &[(usize, usize)]) -> BuildGraph {
+    let mut graph = BuildGraph::new(len);
+    for &(project, dependency) in edges {
+        graph.add_dependency(project, dependency);
+    }
+    graph
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  ProjectReferences
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn package_outputs_are_resolved_per_platform() {
+    let dir = tempfile::tempdir().unwrap();
+    let core = references(
+        dir.path(),
+        "Core",
+        &dproj("Core.dpk", r"<DCC_DcpOutput>..\dcp\$(Platform)</DCC_DcpOutput><DCC_BplOutput>..\bpl</DCC_BplOutput>", ""),
+    )
+    .unwrap();
+    assert_eq!(core.package.as_deref(), Some("core"));
+    let parent = dir.path().parent().unwrap();
+    assert!(core.output_dirs.contains(&path_key(parent, "dcp/win64")));
+    assert!(core.output_dirs.contains(&path_key(parent, "bpl")));
+}
+
+#[test]
+fn required_package_makes_a_dependency() {
+    let dir = tempfile::tempdir().unwrap();
+    let core = references(dir.path(), "Core", &dproj("Core.dpk", "", "")).unwrap();
+    let tools = references(
+        dir.path(),
+        "Tools",
+        &dproj("Tools.dpk", "", r#"<DCCReference Include="rtl.dcp"/><DCCReference Include="Core.dcp"/>"#),
+    )
+    .unwrap();
+    assert!(tools.depends_on(&core));
+    assert!(!core.depends_on(&tools));
+}
+
+#[test]
+fn runtime_packages_only_count_when_enabled() {
+    let dir = tempfile::tempdir().unwrap();
+    let core = references(dir.path(), "Core", &dproj("Core.dpk", "", "")).unwrap();
+    let linked = references(
+        dir.path(),
+        "App",
+        &dproj("App.dpr", "<UsePackages>true</UsePackages><DCC_UsePackage>rtl;vcl;Core</DCC_UsePackage>", ""),
+    )
+    .unwrap();
+    let static_app = references(
+        dir.path(),
+        "Static",
+        &dproj("Static.dpr", "<DCC_UsePackage>rtl;vcl;Core</DCC_UsePackage>", ""),
+    )
+    .unwrap();
+    assert!(linked.depends_on(&core));
+    assert!(!static_app.depends_on(&core));
+}
+
+#[test]
+fn search_path_on_a_package_output_makes_a_dependency() {
+    let dir = tempfile::tempdir().unwrap();
+    let core = references(dir.path(), "Core", &dproj("Core.dpk", r"<DCC_DcuOutput>.\dcu\$(Platform)</DCC_DcuOutput>", ""))
+        .unwrap();
+    let app = references(
+        dir.path(),
+        "App",
+        &dproj("App.dpr", r"<DCC_UnitSearchPath>src;.\DCU\Win64\;$(DCC_UnitSearchPath)</DCC_UnitSearchPath>", ""),
+    )
+    .unwrap();
+    let other = references(dir.path(), "Other", &dproj("Other.dpr", r"<DCC_UnitSearchPath>.\dcu\Win32</DCC_UnitSearchPath>", ""))
+        .unwrap();
+    assert!(app.depends_on(&core));
+    assert!(!other.depends_on(&core));
+}
+
+#[test]
+fn applications_are_never_dependencies() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = references(dir.path(), "App", &dproj("App.dpr", r"<DCC_DcuOutput>dcu</DCC_DcuOutput>", "")).unwrap();
+    let other = references(dir.path(), "Other", &dproj("Other.dpr", r"<DCC_UnitSearchPath>dcu</DCC_UnitSearchPath>", ""))
+        .unwrap();
+    assert_eq!(app.package, None);
+    assert!(!other.depends_on(&app));
+}
+
+#[test]
+fn projects_without_dproj_have_no_references() {
+    let project = Project { id: 1, name: "Old".into(), dpr: Some("Old.dpr".into()), ..Default::default() };
+    assert_eq!(ProjectReferences::load(&project, "Debug", "Win32", HashMap::new()), None);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  BuildGraph
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn infer_links_consumers_to_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = references(dir.path(), "App", &dproj("App.dpr", "", r#"<DCCReference Include="Core.dcp"/>"#));
+    let core = references(dir.path(), "Core", &dproj("Core.dpk", "", ""));
+    let graph = BuildGraph::infer(&[app, None, core]);
+    assert_eq!(graph.dependencies(0).iter().copied().collect::<Vec<_>>(), vec![2]);
+    assert!(graph.dependencies(1).is_empty());
+    assert!(graph.dependencies(2).is_empty());
+}
+
+#[test]
+fn order_builds_dependencies_first_and_keeps_list_order_otherwise() {
+    assert_eq!(graph(4, &[]).order(), vec![0, 1, 2, 3]);
+    assert_eq!(graph(4, &[(0, 2)]).order(), vec![1, 2, 0, 3]);
+    assert_eq!(graph(4, &[(0, 3), (3, 1)]).order(), vec![1, 2, 3, 0]);
+}
+
+#[test]
+fn cycles_are_reported_and_built_in_list_order() {
+    let graph = graph(4, &[(0, 1), (1, 0), (3, 0)]);
+    assert_eq!(graph.cyclic(), vec![0, 1]);
+    assert_eq!(graph.order(), vec![2, 0, 1, 3]);
+}
+
+#[test]
+fn self_dependencies_are_ignored() {
+    let graph = graph(2, &[(0, 0)]);
+    assert!(graph.dependencies(0).is_empty());
+    assert!(graph.cyclic().is_empty());
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Group project dependencies
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn groupproj_dependencies_are_parsed() {
+    let dir = tempfile::tempdir().unwrap();
+    let groupproj = dir.path().join("All.groupproj");
+    std::fs::write(
+        &groupproj,
+        r#"<Project xmlns="http://schemas.microsoft.com/developer/msbuild/2003">
+    <ItemGroup>
+        <Projects Include="Core/Core.dproj">
+            <Dependencies/>
+        </Projects>
+        <Projects Include="App/App.dproj">
+            <Dependencies>Core/Core.dproj; Tools/Tools.dproj</Dependencies>
+        </Projects>
+    </ItemGroup>
+</Project>"#,
+    )
+    .unwrap();
+    let dependencies = parse_groupproj_dependencies(groupproj).unwrap();
+    assert_eq!(dependencies, vec![(
+        dir.path().join("App/App.dproj"),
+        vec![dir.path().join("Core/Core.dproj"), dir.path().join("Tools/Tools.dproj")],
+    )]);
+}
@@ -1,7 +1,10 @@
 use ddk_core::commands::*;
 use ddk_core::projects::*;
+use ddk_core::projects::matrix::MatrixCell;
 use ddk_core::lexorank::LexoRank;
 
+mod common;
+
 // ═══════════════════════════════════════════════════════════════════════════════
 //  find_project_link_id
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -119,7 +122,9 @@ fn project_list_display_with_workspace() {
                 name: "Proj".into(),
                 directory: "dir".into(),
                 dproj: None,
+                exe: None,
                 active: true,
+                depends_on: vec![3, 4],
             }],
         }],
         group_project: None,
@@ -130,6 +135,7 @@ fn project_list_display_with_workspace() {
     assert!(display.contains("12.0"));
     assert!(display.contains("*")); // active marker
     assert!(display.contains("Proj"));
+    assert!(display.contains("depends on: 3, 4"));
 }
 
 #[test]
@@ -160,6 +166,11 @@ fn compile_output_display_success() {
         cancelled: false,
         code: 0,
         lines: vec![],
+        compiler_name: "Delphi 12".into(),
+        diagnostics: vec![],
+        projects: vec![],
+        baseline: None,
+        matrix: Vec::new(),
     };
     let display = format!("{}", output);
     assert!(display.contains("compiled successfully"));
@@ -173,6 +184,11 @@ fn compile_output_display_failure() {
         cancelled: false,
         code: 1,
         lines: vec!["error line".into()],
+        compiler_name: "Delphi 12".into(),
+        diagnostics: vec![],
+        projects: vec![],
+        baseline: None,
+        matrix: Vec::new(),
     };
     let display = format!("{}", output);
     assert!(display.contains("finished with errors"));
@@ -188,11 +204,118 @@ fn compile_output_display_cancelled() {
         cancelled: true,
         code: -1,
         lines: vec![],
+        compiler_name: "Delphi 12".into(),
+        diagnostics: vec![],
+        projects: vec![],
+        baseline: None,
+        matrix: Vec::new(),
     };
     let display = format!("{}", output);
     assert!(display.contains("cancelled"));
 }
 
+fn diagnostic(project_id: usize, code: &str, severity: DiagnosticKind) -> CompileDiagnostic {
+    CompileDiagnostic { project_id, ..common::diagnostic(r"C:\Projects\Unit1.pas", 1, code, severity, "message") }
+}
+
+#[test]
+fn project_compile_result_counts_own_diagnostics() {
+    let diagnostics = vec![
+        diagnostic(1, "E2003", DiagnosticKind::ERROR),
+        diagnostic(1, "W1000", DiagnosticKind::WARN),
+        diagnostic(1, "W1057", DiagnosticKind::WARN),
+        diagnostic(2, "H2164", DiagnosticKind::HINT),
+    ];
+    let result = ProjectCompileResult::new(1, "MyProject".into(), false, false, 1, &diagnostics);
+    assert_eq!((result.errors, result.warnings, result.hints), (1, 2, 0));
+}
+
+#[test]
+fn compile_output_json_includes_structured_diagnostics() {
+    let diagnostics = vec![diagnostic(1, "W1000", DiagnosticKind::WARN)];
+    let output = CompileOutput {
+        project_name: "MyProject".into(),
+        success: true,
+        cancelled: false,
+        code: 0,
+        lines: vec![],
+        compiler_name: "Delphi 12".into(),
+        projects: vec![ProjectCompileResult::new(1, "MyProject".into(), true, false, 0, &diagnostics)],
+        diagnostics,
+        baseline: None,
+        matrix: Vec::new(),
+    };
+    let json = serde_json::to_value(&output).unwrap();
+    assert_eq!(json["diagnostics"][0]["code"], "W1000");
+    assert_eq!(json["diagnostics"][0]["severity"], "warn");
+    assert_eq!(json["projects"][0]["project_id"], 1);
+    assert_eq!(json["projects"][0]["warnings"], 1);
+}
+
+#[test]
+fn compile_output_reports_warning_policy_violations() {
+    let mut project = ProjectCompileResult::new(1, "MyProject".into(), false, false, 0, &[]);
+    project.policy_violations = vec!["3 warning(s), more than the 2 allowed".into()];
+    let output = CompileOutput {
+        project_name: "MyProject".into(),
+        success: false,
+        cancelled: false,
+        code: 0,
+        lines: vec![],
+        compiler_name: "Delphi 12".into(),
+        projects: vec![project],
+        diagnostics: vec![],
+        baseline: None,
+        matrix: Vec::new(),
+    };
+    assert_eq!(
+        output.to_string(),
+        "Compilation of \"MyProject\" broke the warning policy:\nMyProject: 3 warning(s), more than the 2 allowed"
+    );
+    let json = serde_json::to_value(&output).unwrap();
+    assert_eq!(json["projects"][0]["policy_violations"][0], "3 warning(s), more than the 2 allowed");
+}
+
+#[test]
+fn compile_output_lists_failed_matrix_combinations() {
+    let cell = |platform: &str, status, code| MatrixCell {
+        configuration: "Release".into(),
+        platform: platform.into(),
+        compiler_key: "11.0".into(),
+        compiler_name: "Delphi 11.0 Alexandria".into(),
+        status,
+        code,
+        duration_ms: 0,
+        projects: vec![],
+        history_id: None,
+    };
+    let mut output = CompileOutput {
+        project_name: "MyProject".into(),
+        success: false,
+        cancelled: false,
+        code: 1,
+        lines: vec![],
+        compiler_name: "Delphi 11.0 Alexandria".into(),
+        projects: vec![],
+        diagnostics: vec![],
+        baseline: None,
+        matrix: vec![
+            cell("Win32", ProjectStatus::Succeeded, 0),
+            cell("Win64", ProjectStatus::Failed, 1),
+            cell("Linux64", ProjectStatus::Skipped, -1),
+        ],
+    };
+    assert_eq!(
+        output.to_string(),
+        "Build matrix of \"MyProject\" failed for 1 of 3 combination(s), 1 skipped:\nRelease | Win64 | Delphi 11.0 Alexandria (exit code 1)"
+    );
+    let json = serde_json::to_value(&output).unwrap();
+    assert_eq!(json["matrix"][1]["status"], "failed");
+    output.matrix.truncate(1);
+    output.success = true;
+    assert_eq!(output.to_string(), "Build matrix of \"MyProject\" succeeded for all 1 combination(s).");
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 //  Display – EnvironmentInfo
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -247,6 +370,152 @@ fn select_project_result_display() {
     assert!(display.contains("42"));
 }
 
+#[test]
+fn build_queue_display() {
+    assert_eq!(format!("{}", BuildQueueResult { jobs: vec![], lock_holder: None }), "No build jobs.\n");
+    let job = |id, state, started_at, finished_at| queue::BuildJobInfo {
+        id,
+        description: "Compiling Project App".into(),
+        state,
+        pid: 4242,
+        program: "ddk-server".into(),
+        queued_at: 1_700_000_000_000,
+        started_at,
+        finished_at,
+    };
+    let result = BuildQueueResult {
+        jobs: vec![
+            job(1, queue::JobState::Succeeded, Some(1_700_000_000_000), Some(1_700_000_002_500)),
+            job(2, queue::JobState::Queued, None, None),
+        ],
+        lock_holder: Some(build_lock::BuildLockHolder {
+            pid: 7,
+            program: "ddk-mcp-server".into(),
+            description: "Compiling Workspace Main".into(),
+            started_at: 1_700_000_000_000,
+        }),
+    };
+    let display = format!("{}", result);
+    let lines: Vec<&str> = display.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("Building: 'Compiling Workspace Main' in ddk-mcp-server (pid 7) since "));
+    assert!(lines[1].starts_with("#1    succeeded"));
+    assert!(lines[1].contains("Compiling Project App") && lines[1].ends_with("(ddk-server 4242, 2.5s)"));
+    assert!(lines[2].starts_with("#2    queued   "));
+    assert!(lines[2].ends_with("(ddk-server 4242)"));
+}
+
+#[test]
+fn build_history_display() {
+    let empty = BuildHistoryResult { builds: vec![], project_id: None };
+    assert_eq!(format!("{}", empty), "No builds recorded.\n");
+    let project = |project_id, project_name: &str, status, errors| history::ProjectRecord {
+        project_id,
+        project_name: project_name.into(),
+        config: "Release".into(),
+        platform: "Win64".into(),
+        status,
+        code: if status == ProjectStatus::Failed { 2 } else { 0 },
+        duration_ms: 1_500,
+        diagnostics: DiagnosticCounts { errors, warnings: 3, hints: 1 },
+        log: (project_id == 2).then(|| "/config/ddk/build-logs/7/2.log".into()),
+    };
+    let build = history::BuildRecord {
+        id: 7,
+        description: "Compiling Workspace Main".into(),
+        started_at: 1_700_000_000_000,
+        finished_at: 1_700_000_004_000,
+        compiler_key: "23.0".into(),
+        compiler_name: "Delphi 12 Athens".into(),
+        rebuild: true,
+        success: false,
+        cancelled: false,
+        code: 2,
+        program: "ddk".into(),
+        projects: vec![
+            project(1, "Core", ProjectStatus::Succeeded, 0),
+            project(2, "App", ProjectStatus::Failed, 4),
+        ],
+    };
+
+    let list = format!("{}", BuildHistoryResult { builds: vec![build.clone()], project_id: None });
+    assert!(list.starts_with("#7     "), "{list}");
+    assert!(list.contains("failed        4.0s  Delphi 12 Athens (23.0)  Compiling Workspace Main  [4E 6W 2H]"), "{list}");
+    let list = format!("{}", BuildHistoryResult { builds: vec![build.clone()], project_id: Some(1) });
+    assert!(list.contains("succeeded     1.5s  Delphi 12 Athens (23.0)  Compiling Workspace Main  Release/Win64  [0E 3W 1H]"), "{list}");
+
+    let details = format!("{}", build);
+    let lines: Vec<&str> = details.lines().collect();
+    assert_eq!(lines[0], "Build #7: Compiling Workspace Main");
+    assert!(lines[1].ends_with("(4.0s)"));
+    assert_eq!(lines[2], "  Compiler:    Delphi 12 Athens (23.0)");
+    assert_eq!(lines[3], "  Action:      Rebuild");
+    assert_eq!(lines[4], "  Result:      failed (exit code 2)");
+    assert_eq!(lines[6], "  Diagnostics: 4 error(s), 6 warning(s), 2 hint(s)");
+    assert!(lines[9].starts_with("    [2] App "));
+    assert!(lines[9].contains("Release/Win64    failed        1.5s  4 error(s), 3 warning(s), 1 hint(s)"), "{details}");
+    assert_eq!(lines[10], "        log: /config/ddk/build-logs/7/2.log");
+    assert_eq!(lines.len(), 11);
+}
+
+#[test]
+fn parsed_build_log_display_and_report() {
+    let diagnostic = |line, code: &str, severity| CompileDiagnostic {
+        project_id: 0,
+        file: r"C:\Projects\Unit1.pas".into(),
+        line,
+        column: None,
+        code: code.into(),
+        severity,
+        message: "Message".into(),
+        related: vec![],
+    };
+    let parsed = ParsedBuildLog {
+        log_file: "ci.log".into(),
+        diagnostics: vec![diagnostic(3, "E2003", DiagnosticKind::ERROR), diagnostic(9, "H2164", DiagnosticKind::HINT)],
+        counts: DiagnosticCounts { errors: 1, warnings: 0, hints: 1 },
+    };
+    assert_eq!(
+        format!("{}", parsed),
+        "[ERROR][E2003] C:\\Projects\\Unit1.pas:3 - Message\n\
+         [HINT][H2164] C:\\Projects\\Unit1.pas:9 - Message\n\
+         ci.log: 1 error(s), 0 warning(s), 1 hint(s).\n"
+    );
+    let output = parsed.to_compile_output();
+    assert!(!output.success);
+    assert_eq!(output.diagnostics, parsed.diagnostics);
+}
+
+#[test]
+fn last_diagnostics_display() {
+    assert_eq!(
+        format!("{}", LastDiagnosticsResult { projects: vec![] }),
+        "No diagnostics recorded yet; compile a project first.\n"
+    );
+    let project = last_diagnostics::ProjectDiagnostics {
+        project_id: 1,
+        project_name: "App".into(),
+        build_id: 7,
+        finished_at: 0,
+        diagnostics: vec![CompileDiagnostic {
+            project_id: 1,
+            file: r"C:\Projects\Unit1.pas".into(),
+            line: 50,
+            column: None,
+            code: "W1036".into(),
+            severity: DiagnosticKind::WARN,
+            message: "Variable 'I' might not have been initialized".into(),
+            related: vec![],
+        }],
+    };
+    let display = format!("{}", LastDiagnosticsResult { projects: vec![project] });
+    let lines: Vec<&str> = display.lines().collect();
+    assert!(lines[0].starts_with("App (build #7, "), "{display}");
+    assert!(lines[0].ends_with("): 0 error(s), 1 warning(s), 0 hint(s)"), "{display}");
+    assert_eq!(lines[1], "  [WARN][W1036] C:\\Projects\\Unit1.pas:50 - Variable 'I' might not have been initialized");
+    assert_eq!(lines.len(), 2);
+}
+
 #[test]
 fn compiler_summary_display() {
     let summary = CompilerSummary {
@@ -255,19 +524,52 @@ fn compiler_summary_display() {
         product_version: 29,
         compiler_version: 36,
         installation_path: r"C:\Delphi".into(),
+        backend: BuildBackendKind::MsBuild,
+        msbuild_path: None,
+        env_script: None,
+        extra_env: Default::default(),
+        output_language: Default::default(),
     };
     let display = format!("{}", summary);
     assert!(display.contains("12.0"));
     assert!(display.contains("Delphi 12"));
+    assert!(!display.contains("MSBuild:"));
+}
+
+#[test]
+fn compiler_summary_display_shows_environment() {
+    let summary = CompilerSummary {
+        key: "12.0".into(),
+        product_name: "Delphi 12".into(),
+        product_version: 29,
+        compiler_version: 36,
+        installation_path: r"C:\Delphi".into(),
+        backend: BuildBackendKind::MsBuild,
+        msbuild_path: Some(r"C:\BuildTools\MSBuild.exe".into()),
+        env_script: Some(r"C:\ci\env.bat".into()),
+        extra_env: [("BDS".to_string(), r"C:\Delphi".to_string())].into(),
+        output_language: OutputLanguage::German,
+    };
+    let display = format!("{}", summary);
+    assert!(display.contains("Output language: German"));
+    assert!(display.contains(r"MSBuild: C:\BuildTools\MSBuild.exe"));
+    assert!(display.contains(r"Environment script: C:\ci\env.bat"));
+    assert!(display.contains(r"BDS=C:\Delphi"));
 }
 
 #[test]
 fn format_file_result_display() {
     let result = FormatFileResult {
         file_path: "test.pas".into(),
+        encoding: ddk_core::encoding::SourceEncoding {
+            label: "utf-8".into(),
+            bom: true,
+            line_ending: ddk_core::encoding::LineEnding::Crlf,
+        },
     };
     let display = format!("{}", result);
     assert!(display.contains("Formatted: test.pas"));
+    assert!(display.contains("utf-8 with BOM, CRLF"));
 }
 
 #[test]
@@ -280,3 +582,121 @@ fn set_compiler_result_display() {
     assert!(display.contains("Delphi 12"));
     assert!(display.contains("12.0"));
 }
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  cmd_explain_diagnostic
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn explain_diagnostic_by_code() {
+    let explanation = cmd_explain_diagnostic("e2003").await.unwrap();
+    assert_eq!(explanation.code, "E2003");
+    assert_eq!(explanation.title, "Undeclared identifier: '%s'");
+
+    let error = cmd_explain_diagnostic("E9999").await.unwrap_err().to_string();
+    assert!(error.starts_with("No explanation for 'E9999'; known codes: F1026, "), "{error}");
+    assert!(error.contains("W1035"));
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  cmd_encoding_report / cmd_encoding_convert
+// ═══════════════════════════════════════════════════════════════════════════════
+
+fn convert_to(to: &str, bom: bool, dry_run: bool) -> EncodingConvertOptions {
+    EncodingConvertOptions { to: to.into(), bom, line_ending: None, dry_run }
+}
+
+fn target(path: &std::path::Path) -> SourceTarget {
+    SourceTarget::Path(path.to_string_lossy().to_string())
+}
+
+#[tokio::test]
+async fn encoding_convert_refuses_unmappable_characters_and_writes_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let cyrillic = dir.path().join("A.pas");
+    let latin = dir.path().join("B.pas");
+    std::fs::write(&cyrillic, "// Ж\r\n").unwrap();
+    std::fs::write(&latin, "// café\r\n").unwrap();
+
+    let result = cmd_encoding_convert(target(dir.path()), convert_to("windows-1252", false, false)).await.unwrap();
+    let statuses: Vec<&ConvertStatus> = result.files.iter().map(|file| &file.status).collect();
+    assert!(matches!(statuses[0], ConvertStatus::Refused { reason } if reason.contains("line 1: 'Ж'")));
+    assert_eq!(statuses[1], &ConvertStatus::WouldConvert);
+    assert_eq!(std::fs::read(&cyrillic).unwrap(), "// Ж\r\n".as_bytes());
+    assert_eq!(std::fs::read(&latin).unwrap(), "// café\r\n".as_bytes());
+}
+
+#[tokio::test]
+async fn encoding_convert_dry_run_leaves_files_untouched() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("Unit1.pas");
+    std::fs::write(&file, b"// caf\xe9\r\n").unwrap();
+
+    let result = cmd_encoding_convert(target(&file), convert_to("utf-8", true, true)).await.unwrap();
+    assert!(result.dry_run);
+    assert_eq!(result.files[0].status, ConvertStatus::WouldConvert);
+    assert_eq!(std::fs::read(&file).unwrap(), b"// caf\xe9\r\n");
+}
+
+#[tokio::test]
+async fn encoding_convert_keeps_files_already_in_the_target_encoding() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("Unit1.pas");
+    std::fs::write(&file, "// café\r\n").unwrap();
+
+    let result = cmd_encoding_convert(target(&file), convert_to("UTF8", false, false)).await.unwrap();
+    assert_eq!(result.files[0].status, ConvertStatus::Unchanged);
+    assert_eq!(std::fs::read(&file).unwrap(), "// café\r\n".as_bytes());
+}
+
+#[tokio::test]
+async fn encoding_convert_round_trips_windows_1252_through_utf8_with_bom() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("Unit1.pas");
+    let original: &[u8] = b"unit Unit1;\r\n// caf\xe9\r\nend.\r\n";
+    std::fs::write(&file, original).unwrap();
+
+    let result = cmd_encoding_convert(target(&file), convert_to("utf-8", true, false)).await.unwrap();
+    assert_eq!(result.files[0].status, ConvertStatus::Converted);
+    assert_eq!(std::fs::read(&file).unwrap(), "\u{feff}unit Unit1;\r\n// café\r\nend.\r\n".as_bytes());
+    let report = cmd_encoding_report(target(&file)).await.unwrap();
+    assert_eq!(report.files[0].encoding.to_string(), "utf-8 with BOM, CRLF");
+
+    let result = cmd_encoding_convert(target(&file), convert_to("windows-1252", false, false)).await.unwrap();
+    assert_eq!(result.files[0].status, ConvertStatus::Converted);
+    assert_eq!(std::fs::read(&file).unwrap(), original);
+}
+
+#[tokio::test]
+async fn encoding_convert_writes_utf16_with_its_bom() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("Unit1.pas");
+    std::fs::write(&file, "// Ж\r\n").unwrap();
+
+    let result = cmd_encoding_convert(target(&file), convert_to("utf-16", true, false)).await.unwrap();
+    assert_eq!(result.files[0].to.label, "utf-16le");
+    let expected: Vec<u8> = [0xFF, 0xFE].into_iter().chain("// Ж\r\n".encode_utf16().flat_map(u16::to_le_bytes)).collect();
+    assert_eq!(std::fs::read(&file).unwrap(), expected);
+    let report = cmd_encoding_report(target(&file)).await.unwrap();
+    assert_eq!(report.files[0].encoding.to_string(), "utf-16le with BOM, CRLF");
+
+    // encoding_rs would write these as UTF-8.
+    for label in ["unicode", "ucs-2"] {
+        let error = cmd_encoding_convert(target(&file), convert_to(label, true, false)).await.unwrap_err();
+        assert!(error.to_string().contains(label), "{error}");
+    }
+    assert_eq!(std::fs::read(&file).unwrap(), expected);
+}
+
+#[tokio::test]
+async fn format_file_rejects_an_unknown_ansi_fallback() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("Unit1.pas");
+    std::fs::write(&file, b"// caf\xe9\r\n").unwrap();
+
+    let path = file.to_string_lossy().to_string();
+    let error = cmd_format_file(path, None, Some("not-an-encoding")).await.unwrap_err();
+    assert!(error.to_string().contains("not-an-encoding"), "{error}");
+    assert_eq!(ddk_core::encoding::ansi_fallback(), "ansi");
+    assert_eq!(std::fs::read(&file).unwrap(), b"// caf\xe9\r\n");
+}
@@ -0,0 +1,15 @@
+use ddk_core::projects::{CompileDiagnostic, DiagnosticKind};
+
+/// A diagnostic of the first project without a column or related locations.
+pub fn diagnostic(file: &str, line: u32, code: &str, severity: DiagnosticKind, message: &str) -> CompileDiagnostic {
+    CompileDiagnostic {
+        project_id: 1,
+        file: file.into(),
+        line,
+        column: None,
+        code: code.into(),
+        severity,
+        message: message.into(),
+        related: vec![],
+    }
+}
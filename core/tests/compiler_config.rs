@@ -1,4 +1,6 @@
-use ddk_core::projects::{CompilerConfiguration, PartialCompilerConfiguration, sanitize_build_arguments};
+use std::collections::HashMap;
+
+use ddk_core::projects::{BuildBackendKind, CompilerConfiguration, CompilerConfigurations, OutputLanguage, PartialCompilerConfiguration, sanitize_build_arguments};
 
 // ═══════════════════════════════════════════════════════════════════════════════
 //  CompilerConfiguration::update
@@ -13,6 +15,11 @@ fn sample_config() -> CompilerConfiguration {
         compiler_version: 36,
         installation_path: r"C:\Delphi\12.0".to_string(),
         build_arguments: vec!["/t:Build".to_string()],
+        backend: BuildBackendKind::MsBuild,
+        msbuild_path: None,
+        env_script: None,
+        extra_env: HashMap::new(),
+        output_language: OutputLanguage::Auto,
     }
 }
 
@@ -28,6 +35,11 @@ fn update_all_none_changes_nothing() {
         compiler_version: None,
         installation_path: None,
         build_arguments: None,
+        backend: None,
+        msbuild_path: None,
+        env_script: None,
+        extra_env: None,
+        output_language: None,
     };
     config.update(&partial);
     assert_eq!(config, original);
@@ -44,6 +56,11 @@ fn update_all_some_applies_all() {
         compiler_version: Some(38),
         installation_path: Some(r"C:\Delphi\14.0".to_string()),
         build_arguments: Some(vec!["/t:Rebuild".to_string()]),
+        backend: Some(BuildBackendKind::Dcc),
+        msbuild_path: Some(r"C:\BuildTools\MSBuild.exe".to_string()),
+        env_script: Some(r"C:\ci\env.bat".to_string()),
+        extra_env: Some(HashMap::from([("BDS".to_string(), r"C:\Delphi\14.0".to_string())])),
+        output_language: Some(OutputLanguage::German),
     };
     config.update(&partial);
     assert_eq!(config.condition, "14.0");
@@ -53,6 +70,11 @@ fn update_all_some_applies_all() {
     assert_eq!(config.compiler_version, 38);
     assert_eq!(config.installation_path, r"C:\Delphi\14.0");
     assert_eq!(config.build_arguments, vec!["/t:Rebuild"]);
+    assert_eq!(config.backend, BuildBackendKind::Dcc);
+    assert_eq!(config.msbuild_path.as_deref(), Some(r"C:\BuildTools\MSBuild.exe"));
+    assert_eq!(config.env_script.as_deref(), Some(r"C:\ci\env.bat"));
+    assert_eq!(config.extra_env.get("BDS").map(String::as_str), Some(r"C:\Delphi\14.0"));
+    assert_eq!(config.output_language, OutputLanguage::German);
 }
 
 #[test]
@@ -66,12 +88,119 @@ fn update_partial_fields() {
         compiler_version: None,
         installation_path: None,
         build_arguments: None,
+        backend: None,
+        msbuild_path: None,
+        env_script: None,
+        extra_env: None,
+        output_language: None,
     };
     config.update(&partial);
     assert_eq!(config.product_name, "New Name");
     assert_eq!(config.condition, "12.0"); // unchanged
 }
 
+#[test]
+fn update_empty_paths_reset_to_default() {
+    let mut config = sample_config();
+    config.msbuild_path = Some(r"C:\BuildTools\MSBuild.exe".to_string());
+    config.env_script = Some(r"C:\ci\env.bat".to_string());
+    let partial = PartialCompilerConfiguration {
+        condition: None,
+        product_name: None,
+        product_version: None,
+        package_version: None,
+        compiler_version: None,
+        installation_path: None,
+        build_arguments: None,
+        backend: None,
+        msbuild_path: Some(String::new()),
+        env_script: Some(" ".to_string()),
+        extra_env: None,
+        output_language: None,
+    };
+    config.update(&partial);
+    assert_eq!(config.msbuild_path, None);
+    assert_eq!(config.env_script, None);
+}
+
+#[test]
+fn env_script_path_defaults_to_rsvars() {
+    let mut config = sample_config();
+    assert!(config.env_script_path().ends_with("rsvars.bat"));
+    config.env_script = Some("env.bat".to_string());
+    assert_eq!(config.env_script_path(), std::path::PathBuf::from("env.bat"));
+}
+
+#[test]
+fn old_configurations_deserialize_without_environment_fields() {
+    let ron = r#"{ "12.0": (
+        condition: "VER360",
+        product_name: "Delphi 12",
+        product_version: 23,
+        package_version: 290,
+        compiler_version: 36,
+        installation_path: "C:\\Delphi",
+        build_arguments: [],
+    ) }"#;
+    let configs: CompilerConfigurations = ron::from_str(ron).unwrap();
+    let config = configs.get("12.0").unwrap();
+    assert_eq!(config.msbuild_path, None);
+    assert_eq!(config.env_script, None);
+    assert!(config.extra_env.is_empty());
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  CompilerConfiguration::validate
+// ═══════════════════════════════════════════════════════════════════════════════
+
+fn installed_config(dir: &std::path::Path) -> CompilerConfiguration {
+    std::fs::create_dir_all(dir.join("bin")).unwrap();
+    CompilerConfiguration {
+        installation_path: dir.to_string_lossy().to_string(),
+        ..sample_config()
+    }
+}
+
+#[test]
+fn validate_msbuild_requires_environment() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = installed_config(dir.path());
+    let error = config.validate("12.0").unwrap_err().to_string();
+    assert!(error.contains("rsvars.bat not found"), "{error}");
+
+    config.extra_env.insert("BDS".to_string(), dir.path().to_string_lossy().to_string());
+    config.validate("12.0").unwrap();
+}
+
+#[test]
+fn validate_accepts_custom_env_script() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("env.bat");
+    std::fs::write(&script, "@SET BDS=C:\\Delphi\r\n").unwrap();
+    let mut config = installed_config(dir.path());
+    config.env_script = Some(script.to_string_lossy().to_string());
+    config.validate("12.0").unwrap();
+}
+
+#[test]
+fn validate_rejects_missing_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = installed_config(dir.path());
+    config.backend = BuildBackendKind::Dcc;
+    config.validate("12.0").unwrap();
+
+    config.env_script = Some(dir.path().join("missing.bat").to_string_lossy().to_string());
+    assert!(config.validate("12.0").unwrap_err().to_string().contains("Environment script not found"));
+
+    config.env_script = None;
+    config.msbuild_path = Some(dir.path().join("MSBuild.exe").to_string_lossy().to_string());
+    assert!(config.validate("12.0").unwrap_err().to_string().contains("MSBuild not found"));
+
+    config.msbuild_path = None;
+    config.extra_env.insert("A=B".to_string(), "C".to_string());
+    assert!(config.validate("12.0").unwrap_err().to_string().contains("Invalid environment variable name"));
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 //  sanitize_build_arguments
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -61,6 +61,25 @@ fn reset_clears_cancelled() {
     assert!(!compiler_state::is_cancelled());
 }
 
+#[test]
+fn cancel_job_only_cancels_the_running_job() {
+    compiler_state::reset();
+    compiler_state::start_job(7);
+    assert!(!compiler_state::cancel_job(6));
+    assert!(!compiler_state::is_cancelled());
+    assert!(compiler_state::cancel_job(7));
+    assert!(compiler_state::is_cancelled());
+}
+
+#[test]
+fn cancel_job_after_reset_does_nothing() {
+    compiler_state::reset();
+    compiler_state::start_job(7);
+    compiler_state::reset();
+    assert!(!compiler_state::cancel_job(7));
+    assert!(!compiler_state::is_cancelled());
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 //  success / code
 // ═══════════════════════════════════════════════════════════════════════════════
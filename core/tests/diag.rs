@@ -1,4 +1,7 @@
-use ddk_core::projects::CompilerLineDiagnostic;
+use ddk_core::projects::{
+    CompilerLineDiagnostic, DiagnosticCounts, DiagnosticExplanation, DiagnosticKind, OutputLanguage, WarningPolicy,
+    parse_build_log,
+};
 
 // ═══════════════════════════════════════════════════════════════════════════════
 //  CompilerLineDiagnostic::from_line – valid inputs
@@ -43,8 +46,8 @@ fn parses_fatal_error() {
     let line = r"Unit3.pas(1): fatal F2039: Could not create output file";
     let diag = CompilerLineDiagnostic::from_line(line, "dcc32".into()).unwrap();
     assert_eq!(diag.code, "F2039");
-    // Fatal errors start with 'F', which falls through to ERROR
-    assert!(format!("{}", diag.kind) == "ERROR");
+    assert_eq!(diag.kind, DiagnosticKind::FATAL);
+    assert!(diag.kind.is_error());
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -95,6 +98,204 @@ fn rejects_random_text() {
     assert!(CompilerLineDiagnostic::from_line("Something completely different", "dcc32".into()).is_none());
 }
 
+// ═══════════════════════════════════════════════════════════════════════════════
+//  CompilerLineDiagnostic::from_line – diagnostics without a source position
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// (file, line, code, kind, message) of a parsed line.
+fn parsed(line: &str) -> (String, u32, String, DiagnosticKind, String) {
+    let diag = CompilerLineDiagnostic::from_line(line, "dcc32".into()).expect(line);
+    (diag.file, diag.line, diag.code, diag.kind, diag.message)
+}
+
+#[test]
+fn parses_dcc_fatal_without_position() {
+    assert_eq!(
+        parsed("Fatal: F1026 File not found: 'Foo.dcu'"),
+        (String::new(), 0, "F1026".into(), DiagnosticKind::FATAL, "File not found: 'Foo.dcu'".into())
+    );
+    assert_eq!(
+        parsed("Error: E2202 Required package 'VclFoo' not found"),
+        (String::new(), 0, "E2202".into(), DiagnosticKind::ERROR, "Required package 'VclFoo' not found".into())
+    );
+}
+
+#[test]
+fn parses_tool_prefixed_lines_without_position() {
+    assert_eq!(
+        parsed("[dcc32 Error] E2202 Required package 'VclFoo' not found"),
+        (String::new(), 0, "E2202".into(), DiagnosticKind::ERROR, "Required package 'VclFoo' not found".into())
+    );
+    assert_eq!(
+        parsed("[dcc64 Fatal Error] F2613 Unit 'Foo' not found."),
+        (String::new(), 0, "F2613".into(), DiagnosticKind::FATAL, "Unit 'Foo' not found.".into())
+    );
+    // Linker errors have no code; the tool stands in for it.
+    assert_eq!(
+        parsed(r"[ilink32 Error] Error: Unresolved external 'Foo' referenced from C:\OBJ\UNIT1.OBJ"),
+        (String::new(), 0, "ilink32".into(), DiagnosticKind::ERROR, r"Unresolved external 'Foo' referenced from C:\OBJ\UNIT1.OBJ".into())
+    );
+    assert_eq!(parsed("[ilink32 Fatal] Unable to open file 'FOO.OBJ'").3, DiagnosticKind::FATAL);
+}
+
+#[test]
+fn parses_msbuild_errors() {
+    // With a position in an MSBuild targets file.
+    assert_eq!(
+        parsed(r#"C:\Studio\bin\CodeGear.Delphi.Targets(384,5): error MSB4018: The "DCC" task failed unexpectedly. [C:\Projects\App.dproj]"#),
+        (r"C:\Studio\bin\CodeGear.Delphi.Targets".into(), 384, "MSB4018".into(), DiagnosticKind::ERROR, r#"The "DCC" task failed unexpectedly."#.into())
+    );
+    assert_eq!(
+        parsed(r#"C:\Windows\Microsoft.NET\Framework\v4.0.30319\Microsoft.Common.targets(4712,5): error MSB3073: The command "copy App.exe \\server" exited with code 1."#).2,
+        "MSB3073"
+    );
+    // Without a position, the severity comes from the label.
+    assert_eq!(
+        parsed("MSBUILD : error MSB1009: Project file does not exist."),
+        ("MSBUILD".into(), 0, "MSB1009".into(), DiagnosticKind::ERROR, "Project file does not exist.".into())
+    );
+    assert_eq!(
+        parsed(r"C:\Windows\Microsoft.NET\Framework\v4.0.30319\Microsoft.Common.targets : warning MSB3270: Mismatch [C:\Projects\App.dproj]"),
+        (r"C:\Windows\Microsoft.NET\Framework\v4.0.30319\Microsoft.Common.targets".into(), 0, "MSB3270".into(), DiagnosticKind::WARN, "Mismatch".into())
+    );
+    assert_eq!(parsed("error MSB4025: The project file could not be loaded.").3, DiagnosticKind::ERROR);
+}
+
+#[test]
+fn rejects_build_summary_lines() {
+    for line in ["Build FAILED.", "    0 Warning(s)", "    1 Error(s)", "Error: something went wrong", "[Info] Done"] {
+        assert!(CompilerLineDiagnostic::from_line(line, "dcc32".into()).is_none(), "{line}");
+    }
+}
+
+#[test]
+fn fatal_errors_count_as_errors() {
+    let mut counts = DiagnosticCounts::default();
+    counts.add(DiagnosticKind::FATAL);
+    counts.add(DiagnosticKind::ERROR);
+    assert_eq!(counts, DiagnosticCounts { errors: 2, warnings: 0, hints: 0 });
+    assert_eq!("Fatal".parse::<DiagnosticKind>().unwrap(), DiagnosticKind::FATAL);
+    assert_eq!(serde_json::to_string(&DiagnosticKind::FATAL).unwrap(), r#""fatal""#);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  CompilerLineDiagnostic::from_localized_line – localized labels
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// (file, line, code, kind) of a line parsed in `language`.
+fn localized(line: &str, language: OutputLanguage) -> Option<(String, u32, String, DiagnosticKind)> {
+    let diag = CompilerLineDiagnostic::from_localized_line(line, "dcc32".into(), language)?;
+    Some((diag.file, diag.line, diag.code, diag.kind))
+}
+
+#[test]
+fn parses_german_labels() {
+    let german = |line| localized(line, OutputLanguage::German).expect(line);
+    assert_eq!(
+        german("[dcc32 Fehler] Unit1.pas(30): E2003 Undeklarierter Bezeichner: 'x'"),
+        ("Unit1.pas".into(), 30, "E2003".into(), DiagnosticKind::ERROR)
+    );
+    assert_eq!(
+        german("Unit1.pas(12) Warnung: W1036 Variable 'I' ist möglicherweise nicht initialisiert worden"),
+        ("Unit1.pas".into(), 12, "W1036".into(), DiagnosticKind::WARN)
+    );
+    assert_eq!(
+        german("[dcc32 Hinweis] Unit1.pas(9,3): H2164 Variable 'Y' wurde deklariert, aber in 'Foo' nicht verwendet"),
+        ("Unit1.pas".into(), 9, "H2164".into(), DiagnosticKind::HINT)
+    );
+    assert_eq!(
+        german("[dcc32 Schwerwiegender Fehler] F2613 Unit 'Foo' nicht gefunden."),
+        (String::new(), 0, "F2613".into(), DiagnosticKind::FATAL)
+    );
+    assert_eq!(
+        german("Schwerwiegend: F1026 Datei nicht gefunden: 'Foo.dcu'"),
+        (String::new(), 0, "F1026".into(), DiagnosticKind::FATAL)
+    );
+}
+
+#[test]
+fn parses_french_and_japanese_labels() {
+    let french = |line| localized(line, OutputLanguage::French).expect(line);
+    assert_eq!(
+        french("[dcc32 Erreur fatale] F1026 Fichier introuvable : 'Foo.dcu'"),
+        (String::new(), 0, "F1026".into(), DiagnosticKind::FATAL)
+    );
+    assert_eq!(
+        french("Unit1.pas(7) Avertissement: W1000 Le symbole 'X' est déconseillé"),
+        ("Unit1.pas".into(), 7, "W1000".into(), DiagnosticKind::WARN)
+    );
+    assert_eq!(french("[dcc32 Conseil] Unit1.pas(9): H2164 Variable 'Y' déclarée mais jamais utilisée").3, DiagnosticKind::HINT);
+
+    let japanese = |line| localized(line, OutputLanguage::Japanese).expect(line);
+    assert_eq!(
+        japanese("[dcc32 エラー] Unit1.pas(30): E2003 未定義の識別子: 'x'"),
+        ("Unit1.pas".into(), 30, "E2003".into(), DiagnosticKind::ERROR)
+    );
+    assert_eq!(
+        japanese("[dcc32 致命的エラー] F1026 ファイルが見つかりません: 'Foo.dcu'"),
+        (String::new(), 0, "F1026".into(), DiagnosticKind::FATAL)
+    );
+    assert_eq!(japanese("[dcc32 警告] Unit1.pas(5): W1036 変数 'I' は初期化されていない可能性があります").3, DiagnosticKind::WARN);
+    assert_eq!(japanese("[dcc32 ヒント] Unit1.pas(5): H2164 変数 'Y' は宣言されましたが使用されていません").3, DiagnosticKind::HINT);
+}
+
+#[test]
+fn labels_of_other_languages_need_auto_detection() {
+    let line = "Fehler: E2202 Package 'VclFoo' wird benötigt, wurde aber nicht gefunden";
+    assert!(localized(line, OutputLanguage::French).is_none());
+    assert!(localized(line, OutputLanguage::German).is_some());
+    assert!(localized(line, OutputLanguage::Auto).is_some());
+    // English labels are recognized in every language.
+    assert!(localized("[dcc32 Error] E2202 Required package 'VclFoo' not found", OutputLanguage::Japanese).is_some());
+}
+
+#[test]
+fn label_decides_severity_over_code_letter() {
+    // A warning promoted to an error (-W^) is an error.
+    assert_eq!(
+        localized("[dcc32 Fehler] Unit1.pas(12): W1036 Variable 'I' ist möglicherweise nicht initialisiert worden", OutputLanguage::Auto).unwrap().3,
+        DiagnosticKind::ERROR
+    );
+    // A fatal label or an F code is fatal either way.
+    assert_eq!(localized("Unit1.pas(1): error F2039: Could not create output file", OutputLanguage::Auto).unwrap().3, DiagnosticKind::FATAL);
+    assert_eq!(localized("[dcc32 Schwerwiegender Fehler] Unit1.pas(1): E2029 ')' erwartet", OutputLanguage::Auto).unwrap().3, DiagnosticKind::FATAL);
+    // An unknown label leaves it to the code.
+    assert_eq!(localized("Unit1.pas(1): Uyarı W1000: eski", OutputLanguage::Auto).unwrap().3, DiagnosticKind::WARN);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Continuation lines – related locations
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn parses_related_location_lines() {
+    let related = CompilerLineDiagnostic::related_from_line("  Unit1.pas(10): Related method: procedure Foo(Integer);").unwrap();
+    assert_eq!((related.file.as_str(), related.line, related.column), ("Unit1.pas", 10, None));
+    assert_eq!(related.message, "Related method: procedure Foo(Integer);");
+    let related = CompilerLineDiagnostic::related_from_line(r"   C:\Src\Unit2.pas(4,7) Verwandte Methode: procedure Foo(Int64);").unwrap();
+    assert_eq!((related.file.as_str(), related.line, related.column), (r"C:\Src\Unit2.pas", 4, Some(7)));
+    assert!(CompilerLineDiagnostic::related_from_line("Unit1.pas(10): Related method").is_none());
+}
+
+#[test]
+fn lsp_diagnostic_carries_related_information() {
+    use tower_lsp::lsp_types::Diagnostic;
+
+    let dir = tempfile::tempdir().unwrap();
+    let unit = dir.path().join("Unit1.pas");
+    let mut diag = CompilerLineDiagnostic::from_line("Unit1.pas(20): error E2251: Ambiguous overloaded call to 'Foo'", "dcc".into()).unwrap();
+    let line = format!("  {}(10): Related method: procedure Foo(Integer);", unit.display());
+    diag.related.push(CompilerLineDiagnostic::related_from_line(&line).unwrap());
+    // Relative paths have no URL and are left out.
+    diag.related.push(CompilerLineDiagnostic::related_from_line("  Unit2.pas(11): Related method: procedure Foo(Int64);").unwrap());
+    let lsp_diag: Diagnostic = diag.into();
+    let related = lsp_diag.related_information.expect("related information");
+    assert_eq!(related.len(), 1);
+    assert_eq!(related[0].location.uri.to_file_path().unwrap(), unit);
+    assert_eq!(related[0].location.range.start.line, 9);
+    assert_eq!(related[0].message, "Related method: procedure Foo(Integer);");
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 //  Severity classification by code prefix
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -145,6 +346,16 @@ fn display_without_column() {
     assert!(!display.contains("file.pas:10:"));
 }
 
+#[test]
+fn display_without_position() {
+    let mut diag = CompilerLineDiagnostic::from_line("Fatal: F1026 File not found: 'Foo.dcu'", "dcc32".into()).unwrap();
+    diag.file = r"C:\Projects\App.dproj".into();
+    let display = format!("{}", diag);
+    assert!(display.ends_with(r"[FATAL][F1026] C:\Projects\App.dproj - File not found: 'Foo.dcu'"), "{display}");
+    let structured = diag.to_compile_diagnostic(1);
+    assert_eq!(structured.to_string(), r"[FATAL][F1026] C:\Projects\App.dproj - File not found: 'Foo.dcu'");
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 //  Into<Diagnostic> – LSP conversion
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -177,6 +388,11 @@ fn lsp_diagnostic_severity_mapping() {
     let hint_line = r"file.pas(1): hint H1000: hint";
     let hint_diag: Diagnostic = CompilerLineDiagnostic::from_line(hint_line, "dcc".into()).unwrap().into();
     assert_eq!(hint_diag.severity, Some(DiagnosticSeverity::HINT));
+
+    let fatal_line = "Fatal: F1026 File not found: 'Foo.dcu'";
+    let fatal_diag: Diagnostic = CompilerLineDiagnostic::from_line(fatal_line, "dcc".into()).unwrap().into();
+    assert_eq!(fatal_diag.severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!((fatal_diag.range.start.line, fatal_diag.range.start.character), (0, 0));
 }
 
 #[test]
@@ -199,3 +415,201 @@ fn lsp_diagnostic_source_is_compiler_name() {
     let lsp_diag: Diagnostic = diag.into();
     assert_eq!(lsp_diag.source, Some("my-compiler".to_string()));
 }
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  CompilerLineDiagnostic::to_compile_diagnostic
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn compile_diagnostic_carries_fields_and_project() {
+    let line = r"C:\Projects\Unit1.pas(42,5): warning W1000: Symbol 'X' is deprecated";
+    let diag = CompilerLineDiagnostic::from_line(line, "dcc32".into()).unwrap();
+    let structured = diag.to_compile_diagnostic(7);
+    assert_eq!(structured.project_id, 7);
+    assert_eq!(structured.file, r"C:\Projects\Unit1.pas");
+    assert_eq!(structured.line, 42);
+    assert_eq!(structured.column, Some(5));
+    assert_eq!(structured.code, "W1000");
+    assert_eq!(structured.severity, DiagnosticKind::WARN);
+    assert_eq!(structured.message, "Symbol 'X' is deprecated");
+}
+
+#[test]
+fn compile_diagnostic_serializes_lowercase_severity() {
+    let line = r"Unit1.pas(3): error E2003: Undeclared identifier: 'Foo'";
+    let diag = CompilerLineDiagnostic::from_line(line, "dcc32".into()).unwrap();
+    let json = serde_json::to_value(diag.to_compile_diagnostic(1)).unwrap();
+    assert_eq!(json["severity"], "error");
+    assert_eq!(json["line"], 3);
+    assert!(json["column"].is_null());
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  parse_build_log – saved logs replayed offline
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn parses_saved_log_like_live_output() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("Unit1.pas"), "unit Unit1;").unwrap();
+    let log = dir.path().join("build.log");
+    let content = [
+        "Embarcadero Delphi for Win32 compiler version 36.0",
+        "",
+        r"  C:\Delphi\VSS\SomeUnit",
+        r"Unit1.pas(10,3): warning W1000: Symbol 'X' is deprecated [C:\Projects\App.dproj]",
+        r"C:\WINDOWS\Microsoft.NET\Framework\v2.0.50727\Borland.Delphi.Targets : warning : C:\Delphi\VSS\Msg.pas(107) Warnung: W1036 Variable 'A' ist moeglicherweise nicht initialisiert worden [c:\Delphi\VSS\be.dproj]",
+        r"  C:\Delphi\VSS\Msg.pas(107) Warnung: W1036 Variable 'A' ist moeglicherweise nicht initialisiert worden",
+        r"Unit2.pas(3): error E2003: Undeclared identifier: 'Foo'",
+    ]
+    .join("\r\n");
+    std::fs::write(&log, content).unwrap();
+
+    let diagnostics = parse_build_log(&log, 7, dir.path(), None, WarningPolicy::default(), OutputLanguage::Auto).await.unwrap();
+    let summary: Vec<(String, u32, &str, DiagnosticKind)> = diagnostics
+        .iter()
+        .map(|d| (d.file.clone(), d.line, d.code.as_str(), d.severity))
+        .collect();
+    assert_eq!(summary, vec![
+        // Resolved against the project directory because it exists there.
+        (dir.path().join("Unit1.pas").to_string_lossy().to_string(), 10, "W1000", DiagnosticKind::WARN),
+        // The Delphi 2007 duplicate is reported once.
+        (r"C:\Delphi\VSS\Msg.pas".to_string(), 107, "W1036", DiagnosticKind::WARN),
+        // Last line without a line break; kept relative since it is missing.
+        ("Unit2.pas".to_string(), 3, "E2003", DiagnosticKind::ERROR),
+    ]);
+    assert!(diagnostics.iter().all(|d| d.project_id == 7));
+}
+
+#[tokio::test]
+async fn attaches_diagnostics_without_position_to_the_project_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let project_file = dir.path().join("App.dproj");
+    std::fs::write(&project_file, "<Project/>").unwrap();
+    let log = dir.path().join("build.log");
+    let content = [
+        "[dcc32 Error] E2202 Required package 'VclFoo' not found",
+        "[dcc32 Error] E2202 Required package 'VclBar' not found",
+        "Fatal: F1026 File not found: 'Foo.dcu'",
+        "MSBUILD : error MSB1009: Project file does not exist.",
+    ]
+    .join("\r\n");
+    std::fs::write(&log, content).unwrap();
+
+    let diagnostics = parse_build_log(&log, 1, dir.path(), Some(&project_file), WarningPolicy::default(), OutputLanguage::Auto).await.unwrap();
+    let summary: Vec<(&str, u32, &str, DiagnosticKind)> = diagnostics
+        .iter()
+        .map(|d| (d.file.as_str(), d.line, d.code.as_str(), d.severity))
+        .collect();
+    let file = project_file.to_str().unwrap();
+    assert_eq!(summary, vec![
+        // Same code without position, but different messages: both are kept.
+        (file, 0, "E2202", DiagnosticKind::ERROR),
+        (file, 0, "E2202", DiagnosticKind::ERROR),
+        (file, 0, "F1026", DiagnosticKind::FATAL),
+        (file, 0, "MSB1009", DiagnosticKind::ERROR),
+    ]);
+
+    // Without a project file, they keep what the line names.
+    let diagnostics = parse_build_log(&log, 1, dir.path(), None, WarningPolicy::default(), OutputLanguage::Auto).await.unwrap();
+    assert_eq!(diagnostics[2].file, "");
+    assert_eq!(diagnostics[3].file, "MSBUILD");
+}
+
+#[tokio::test]
+async fn continuation_lines_become_related_locations() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("Unit1.pas"), "unit Unit1;").unwrap();
+    let log = dir.path().join("build.log");
+    let content = [
+        "[dcc32 Fehler] Unit1.pas(20): E2251 Mehrdeutiger überladener Aufruf von 'Foo'",
+        "  Unit1.pas(10): Verwandte Methode: procedure Foo(Integer);",
+        "  Unit1.pas(11): Verwandte Methode: procedure Foo(Int64);",
+        "[dcc32 Warnung] Unit1.pas(30): W1036 Variable 'I' ist möglicherweise nicht initialisiert worden",
+        "Erfolg",
+    ]
+    .join("\r\n");
+    std::fs::write(&log, content).unwrap();
+
+    let diagnostics = parse_build_log(&log, 1, dir.path(), None, WarningPolicy::default(), OutputLanguage::German).await.unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    let unit = dir.path().join("Unit1.pas").to_string_lossy().to_string();
+    let related: Vec<(&str, u32, &str)> = diagnostics[0]
+        .related
+        .iter()
+        .map(|r| (r.file.as_str(), r.line, r.message.as_str()))
+        .collect();
+    assert_eq!(related, vec![
+        (unit.as_str(), 10, "Verwandte Methode: procedure Foo(Integer);"),
+        (unit.as_str(), 11, "Verwandte Methode: procedure Foo(Int64);"),
+    ]);
+    assert!(diagnostics[1].related.is_empty());
+    assert_eq!(diagnostics[1].severity, DiagnosticKind::WARN);
+}
+
+#[tokio::test]
+async fn parse_build_log_fails_for_missing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let error = parse_build_log(&dir.path().join("missing.log"), 0, dir.path(), None, WarningPolicy::default(), OutputLanguage::Auto).await.unwrap_err();
+    assert!(error.to_string().starts_with("Failed to open build log"));
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Diagnostic knowledge base
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn every_explanation_is_complete() {
+    let all = DiagnosticExplanation::all();
+    assert!(all.len() > 50);
+    let mut codes = std::collections::HashSet::new();
+    for explanation in all {
+        assert!(codes.insert(explanation.code[1..].to_string()), "{} is explained twice", explanation.code);
+        assert_eq!(explanation.code.len(), 5, "{}", explanation.code);
+        assert!("EWHFX".contains(&explanation.code[..1]), "{}", explanation.code);
+        assert!(!explanation.title.is_empty() && !explanation.explanation.is_empty(), "{}", explanation.code);
+        assert!(!explanation.causes.is_empty() && !explanation.fixes.is_empty(), "{}", explanation.code);
+    }
+}
+
+#[test]
+fn finds_explanations_ignoring_case_and_by_number() {
+    let explanation = DiagnosticExplanation::find(" w1035 ").unwrap();
+    assert_eq!(explanation.code, "W1035");
+    assert_eq!(explanation.title, "Return value of function '%s' might be undefined");
+    // X messages are reported with the severity the project gives them.
+    assert_eq!(DiagnosticExplanation::find("H2269").unwrap().code, "X2269");
+    assert_eq!(DiagnosticExplanation::find("W1012").unwrap().code, "X1012");
+    assert!(DiagnosticExplanation::find("E9999").is_none());
+    assert!(DiagnosticExplanation::find("MSB3073").is_none());
+    assert!(DiagnosticExplanation::find("").is_none());
+}
+
+#[test]
+fn explanation_renders_as_text_and_markdown() {
+    let explanation = DiagnosticExplanation::find("W1036").unwrap();
+    let text = explanation.to_string();
+    assert!(text.starts_with("W1036: Variable '%s' might not have been initialized\n\n"));
+    assert!(text.contains("\nCommon causes:\n  - "));
+    assert!(text.contains("\nFixes:\n  - "));
+    assert!(text.ends_with(&format!("Documentation: {}\n", explanation.docs_url())));
+
+    let markdown = explanation.to_markdown();
+    assert!(markdown.starts_with("**W1036** Variable '%s' might not have been initialized\n\n"));
+    assert!(markdown.contains("\n**Common causes**\n\n- "));
+    assert!(markdown.contains(&format!("[RAD Studio documentation]({})", explanation.docs_url())));
+}
+
+#[test]
+fn lsp_diagnostic_links_explained_codes() {
+    use tower_lsp::lsp_types::Diagnostic;
+
+    let diag = CompilerLineDiagnostic::from_line("Unit1.pas(20): warning W1035: Return value of function 'Foo' might be undefined", "dcc".into()).unwrap();
+    let lsp_diag: Diagnostic = diag.into();
+    let href = lsp_diag.code_description.expect("code description").href;
+    assert_eq!(href.as_str(), DiagnosticExplanation::find("W1035").unwrap().docs_url());
+
+    let diag = CompilerLineDiagnostic::from_line("Unit1.pas(20): error E9999: Made up", "dcc".into()).unwrap();
+    let lsp_diag: Diagnostic = diag.into();
+    assert!(lsp_diag.code_description.is_none());
+}
@@ -1,4 +1,8 @@
-use ddk_core::encoding::{decode_bytes, encode_string};
+use ddk_core::encoding::{
+    LineEnding, SourceEncoding, ansi_fallback, decode_bytes, decode_source_as, detect_line_ending,
+    detect_source_encoding, encode_source, encode_string, first_unmappable_char, is_known_label,
+    set_ansi_fallback, source_encoding_for_label,
+};
 
 // ═══════════════════════════════════════════════════════════════════════════════
 //  decode_bytes – UTF-8
@@ -210,3 +214,192 @@ fn encode_oem_ascii() {
     let result = encode_string("Hello", "oem");
     assert_eq!(result, b"Hello");
 }
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  encode_string – UTF-16
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn encode_utf16le() {
+    assert_eq!(encode_string("Aä", "utf-16le"), vec![0x41, 0x00, 0xE4, 0x00]);
+}
+
+#[test]
+fn encode_utf16be() {
+    assert_eq!(encode_string("Aä", "utf-16be"), vec![0x00, 0x41, 0x00, 0xE4]);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  detect_source_encoding – BOM sniffing
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn detect_utf8_bom() {
+    let enc = detect_source_encoding(b"\xEF\xBB\xBFunit A;\r\n", "windows-1252");
+    assert_eq!(enc.label, "utf-8");
+    assert!(enc.bom);
+    assert_eq!(enc.line_ending, LineEnding::Crlf);
+}
+
+#[test]
+fn detect_utf16le_bom() {
+    let enc = detect_source_encoding(&[0xFF, 0xFE, 0x41, 0x00, 0x0A, 0x00], "windows-1252");
+    assert_eq!(enc.label, "utf-16le");
+    assert!(enc.bom);
+    assert_eq!(enc.line_ending, LineEnding::Lf);
+}
+
+#[test]
+fn detect_utf16be_bom() {
+    let enc = detect_source_encoding(&[0xFE, 0xFF, 0x00, 0x41], "windows-1252");
+    assert_eq!(enc.label, "utf-16be");
+    assert!(enc.bom);
+}
+
+#[test]
+fn detect_utf32le_bom_wins_over_utf16le() {
+    let enc = detect_source_encoding(&[0xFF, 0xFE, 0x00, 0x00, 0x41, 0x00, 0x00, 0x00], "windows-1252");
+    assert_eq!(enc.label, "utf-32le");
+    assert!(enc.bom);
+}
+
+#[test]
+fn detect_utf32be_bom() {
+    let enc = detect_source_encoding(&[0x00, 0x00, 0xFE, 0xFF, 0x00, 0x00, 0x00, 0x41], "windows-1252");
+    assert_eq!(enc.label, "utf-32be");
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  detect_source_encoding – no BOM
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn detect_valid_utf8_without_bom() {
+    let enc = detect_source_encoding("// Grüße\n".as_bytes(), "windows-1252");
+    assert_eq!(enc.label, "utf-8");
+    assert!(!enc.bom);
+    assert_eq!(enc.line_ending, LineEnding::Lf);
+}
+
+#[test]
+fn detect_invalid_utf8_uses_fallback() {
+    // "Grüße" in windows-1252 is not valid UTF-8.
+    let enc = detect_source_encoding(&[0x47, 0x72, 0xFC, 0xDF, 0x65], "Windows-1252");
+    assert_eq!(enc.label, "windows-1252");
+    assert!(!enc.bom);
+}
+
+#[test]
+fn explicit_label_still_detects_bom() {
+    let enc = source_encoding_for_label(b"\xEF\xBB\xBFa\nb", "UTF8");
+    assert_eq!(enc.label, "utf-8");
+    assert!(enc.bom);
+    assert_eq!(enc.line_ending, LineEnding::Lf);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  detect_line_ending
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn line_ending_crlf() {
+    assert_eq!(detect_line_ending("a\r\nb\r\n"), LineEnding::Crlf);
+}
+
+#[test]
+fn line_ending_lf() {
+    assert_eq!(detect_line_ending("a\nb\n"), LineEnding::Lf);
+}
+
+#[test]
+fn line_ending_mixed() {
+    assert_eq!(detect_line_ending("a\r\nb\n"), LineEnding::Mixed);
+}
+
+#[test]
+fn line_ending_none_defaults_to_crlf() {
+    assert_eq!(detect_line_ending("single line"), LineEnding::Crlf);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  decode_source_as / encode_source – round trip
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn decode_source_strips_bom() {
+    let enc = detect_source_encoding(b"\xEF\xBB\xBFabc", "windows-1252");
+    assert_eq!(decode_source_as(b"\xEF\xBB\xBFabc", &enc), "abc");
+}
+
+#[test]
+fn encode_source_restores_bom_and_crlf() {
+    let enc = SourceEncoding {
+        label: "utf-8".into(),
+        bom: true,
+        line_ending: LineEnding::Crlf,
+    };
+    // Formatter output may arrive with LF and its own BOM.
+    let out = encode_source("\u{feff}a\nb\r\n", &enc);
+    assert_eq!(out, b"\xEF\xBB\xBFa\r\nb\r\n");
+}
+
+#[test]
+fn encode_source_restores_lf() {
+    let enc = SourceEncoding {
+        label: "utf-8".into(),
+        bom: false,
+        line_ending: LineEnding::Lf,
+    };
+    assert_eq!(encode_source("a\r\nb\r\n", &enc), b"a\nb\n");
+}
+
+#[test]
+fn roundtrip_windows_1252_source_keeps_umlauts() {
+    let raw: &[u8] = &[0x2F, 0x2F, 0x20, 0xE4, 0xF6, 0xFC, 0x0D, 0x0A];
+    let enc = detect_source_encoding(raw, "windows-1252");
+    let text = decode_source_as(raw, &enc);
+    assert_eq!(text, "// äöü\r\n");
+    assert_eq!(encode_source(&text, &enc), raw);
+}
+
+#[test]
+fn roundtrip_utf16le_source_with_bom() {
+    let mut raw = vec![0xFF, 0xFE];
+    raw.extend(encode_string("ä\r\n", "utf-16le"));
+    let enc = detect_source_encoding(&raw, "windows-1252");
+    let text = decode_source_as(&raw, &enc);
+    assert_eq!(text, "ä\r\n");
+    assert_eq!(encode_source(&text, &enc), raw);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Conversion checks
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn known_labels() {
+    assert!(is_known_label("UTF-8"));
+    assert!(is_known_label("windows-1252"));
+    assert!(is_known_label("ansi"));
+    assert!(!is_known_label("not-an-encoding"));
+}
+
+#[test]
+fn unmappable_char_reports_line() {
+    assert_eq!(first_unmappable_char("a\n// äöü\n// Ж\n", "windows-1252"), Some((3, 'Ж')));
+}
+
+#[test]
+fn unmappable_char_none_for_representable_text() {
+    assert_eq!(first_unmappable_char("// äöü\n", "windows-1252"), None);
+    assert_eq!(first_unmappable_char("// Ж\n", "utf-8"), None);
+}
+
+#[test]
+fn unknown_ansi_fallback_is_rejected() {
+    set_ansi_fallback("Windows-1250").unwrap();
+    assert_eq!(ansi_fallback(), "windows-1250");
+    let error = set_ansi_fallback("not-an-encoding").unwrap_err();
+    assert!(error.to_string().contains("not-an-encoding"));
+    assert_eq!(ansi_fallback(), "windows-1250");
+}
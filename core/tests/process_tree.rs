@@ -0,0 +1,86 @@
+#![cfg(unix)]
+
+use std::time::{Duration, Instant};
+
+use ddk_core::utils::ProcessTree;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  Helpers
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// Start `script` under `sh`; the script prints the pid of a background
+/// grandchild as its first line.
+async fn spawn_with_grandchild(script: &str) -> (ProcessTree, i32) {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(script).stdout(std::process::Stdio::piped());
+    let mut tree = ProcessTree::spawn(&mut command).unwrap();
+    let stdout = tree.child_mut().stdout.take().unwrap();
+    let mut lines = BufReader::new(stdout).lines();
+    let pid = lines.next_line().await.unwrap().unwrap().trim().parse().unwrap();
+    (tree, pid)
+}
+
+/// Whether `pid` is still running (zombies count as gone).
+fn is_running(pid: i32) -> bool {
+    match std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+        Ok(stat) => !stat.rsplit(')').next().unwrap_or("").trim_start().starts_with('Z'),
+        Err(_) => unsafe { libc::kill(pid, 0) == 0 },
+    }
+}
+
+async fn wait_until_gone(pid: i32) -> bool {
+    for _ in 0..50 {
+        if !is_running(pid) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    false
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  ProcessTree
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn wait_returns_exit_status() {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg("exit 3");
+    let mut tree = ProcessTree::spawn(&mut command).unwrap();
+    assert_eq!(tree.wait().await.unwrap().code(), Some(3));
+}
+
+#[tokio::test]
+async fn terminate_stops_grandchildren() {
+    let (mut tree, grandchild) = spawn_with_grandchild("sleep 30 & echo $!; wait").await;
+    assert!(is_running(grandchild));
+    tree.terminate(Duration::from_secs(2)).await;
+    assert!(wait_until_gone(grandchild).await, "grandchild {grandchild} survived");
+}
+
+#[tokio::test]
+async fn terminate_lets_the_tree_exit_within_the_grace_period() {
+    let (mut tree, grandchild) = spawn_with_grandchild("trap 'exit 0' TERM; sleep 30 & echo $!; wait").await;
+    let started = Instant::now();
+    tree.terminate(Duration::from_secs(10)).await;
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(wait_until_gone(grandchild).await);
+}
+
+#[tokio::test]
+async fn terminate_force_kills_after_the_grace_period() {
+    let (mut tree, grandchild) = spawn_with_grandchild("trap '' TERM; sleep 30 & echo $!; wait").await;
+    let started = Instant::now();
+    tree.terminate(Duration::from_millis(300)).await;
+    assert!(started.elapsed() >= Duration::from_millis(300));
+    assert!(wait_until_gone(grandchild).await, "grandchild {grandchild} survived");
+}
+
+#[tokio::test]
+async fn dropping_an_unfinished_tree_kills_it() {
+    let (tree, grandchild) = spawn_with_grandchild("sleep 30 & echo $!; wait").await;
+    drop(tree);
+    assert!(wait_until_gone(grandchild).await, "grandchild {grandchild} survived");
+}
@@ -381,3 +381,75 @@ fn projects_of_group_project_returns_linked_projects() {
     let projects = data.projects_of_group_project(gp);
     assert_eq!(projects.len(), 2);
 }
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  project dependencies
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// `sample_data` with Gamma also linked into WS-A.
+fn sample_data_with_three_linked() -> ProjectsData {
+    let mut data = sample_data();
+    data.get_workspace_mut(4).unwrap().project_links.push(make_link(11, 3, "1|m"));
+    data
+}
+
+#[test]
+fn add_project_dependency_records_it_once() {
+    let mut data = sample_data_with_three_linked();
+    data.add_project_dependency(4, 1, 2).unwrap();
+    data.add_project_dependency(4, 1, 2).unwrap();
+    let ws = data.get_workspace(4).unwrap();
+    assert_eq!(ws.dependencies, vec![ProjectDependency { project_id: 1, depends_on: 2 }]);
+}
+
+#[test]
+fn add_project_dependency_requires_both_projects_in_workspace() {
+    let mut data = sample_data();
+    assert!(data.add_project_dependency(4, 1, 3).is_err());
+    assert!(data.add_project_dependency(4, 1, 1).is_err());
+    assert!(data.add_project_dependency(99, 1, 2).is_err());
+}
+
+#[test]
+fn add_project_dependency_rejects_cycles() {
+    let mut data = sample_data_with_three_linked();
+    data.add_project_dependency(4, 1, 2).unwrap();
+    data.add_project_dependency(4, 2, 3).unwrap();
+    let err = data.add_project_dependency(4, 3, 1).unwrap_err().to_string();
+    assert!(err.contains("Alpha") && err.contains("Gamma"), "{err}");
+    assert_eq!(data.get_workspace(4).unwrap().dependencies.len(), 2);
+}
+
+#[test]
+fn transitive_dependencies_follow_link_order() {
+    let mut data = sample_data_with_three_linked();
+    data.add_project_dependency(4, 3, 2).unwrap();
+    data.add_project_dependency(4, 2, 1).unwrap();
+    let ws = data.get_workspace(4).unwrap();
+    assert_eq!(ws.transitive_dependencies(3), vec![1, 2]);
+    assert!(ws.transitive_dependencies(1).is_empty());
+    assert_eq!(ws.dependency_graph().order(), vec![0, 1, 2]);
+}
+
+#[test]
+fn remove_project_dependency_fails_when_missing() {
+    let mut data = sample_data_with_three_linked();
+    data.add_project_dependency(4, 1, 2).unwrap();
+    data.remove_project_dependency(4, 1, 2).unwrap();
+    assert!(data.get_workspace(4).unwrap().dependencies.is_empty());
+    assert!(data.remove_project_dependency(4, 1, 2).is_err());
+}
+
+#[test]
+fn removing_a_link_prunes_its_dependencies() {
+    let mut data = sample_data_with_three_linked();
+    data.add_project_dependency(4, 1, 2).unwrap();
+    data.add_project_dependency(4, 3, 1).unwrap();
+    data.remove_project_link(6);
+    assert_eq!(
+        data.get_workspace(4).unwrap().dependencies,
+        vec![ProjectDependency { project_id: 3, depends_on: 1 }]
+    );
+    data.remove_project(3, true);
+    assert!(data.get_workspace(4).unwrap().dependencies.is_empty());
+}
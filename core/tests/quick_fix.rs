@@ -0,0 +1,276 @@
+use std::path::PathBuf;
+
+use ddk_core::projects::quick_fixes;
+use tower_lsp::lsp_types::*;
+
+fn uri() -> Url {
+    Url::parse("file:///C:/Projects/Unit1.pas").unwrap()
+}
+
+/// A diagnostic as published during a build, on the 1-based `line`.
+fn diagnostic(line: u32, code: &str, message: &str) -> Diagnostic {
+    Diagnostic {
+        range: Range::new(Position::new(line - 1, 0), Position::new(line - 1, 1)),
+        code: Some(NumberOrString::String(code.into())),
+        message: message.into(),
+        ..Default::default()
+    }
+}
+
+fn offset(source: &str, position: Position) -> usize {
+    let line_start: usize = source.split_inclusive('\n').take(position.line as usize).map(str::len).sum();
+    line_start + position.character as usize
+}
+
+/// Titles of the fixes and the source after applying each of them.
+fn fixes(source: &str, diagnostic: Diagnostic, units: &[PathBuf]) -> Vec<(String, String)> {
+    quick_fixes(&uri(), source, &[diagnostic], units)
+        .into_iter()
+        .map(|action| {
+            let mut edits = action.edit.unwrap().changes.unwrap().remove(&uri()).unwrap();
+            edits.sort_by_key(|edit| std::cmp::Reverse(offset(source, edit.range.start)));
+            let mut fixed = source.to_string();
+            for edit in edits {
+                fixed.replace_range(offset(source, edit.range.start)..offset(source, edit.range.end), &edit.new_text);
+            }
+            (action.title, fixed)
+        })
+        .collect()
+}
+
+fn fixed(source: &str, diagnostic: Diagnostic) -> String {
+    let mut fixes = fixes(source, diagnostic, &[]);
+    assert_eq!(fixes.len(), 1, "{fixes:?}");
+    fixes.remove(0).1
+}
+
+const UNIT: &str = "unit Unit1;
+
+interface
+
+uses
+  System.Classes;
+
+type
+  TForm1 = class
+    procedure Run;
+  end;
+
+implementation
+
+uses
+  System.Math;
+
+procedure TForm1.Run;
+begin
+  ShowMessage(IntToStr(Max(1, 2)));
+end;
+
+end.
+";
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  E2003 / H2443 – add a unit to the uses clause
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn adds_well_known_unit_to_implementation_uses() {
+    let (title, source) = fixes(UNIT, diagnostic(20, "E2003", "Undeclared identifier: 'IntToStr'"), &[]).remove(0);
+    assert_eq!(title, "Add 'System.SysUtils' to the uses clause");
+    assert!(source.contains("uses\n  System.Math, System.SysUtils;\n"), "{source}");
+    assert!(source.contains("uses\n  System.Classes;\n"));
+}
+
+#[test]
+fn adds_unit_to_interface_uses_for_interface_code() {
+    let source = fixed(UNIT, diagnostic(9, "E2003", "Undeclared identifier: 'TStopwatch'"));
+    assert!(source.contains("uses\n  System.Classes, System.Diagnostics;\n"), "{source}");
+}
+
+#[test]
+fn creates_missing_uses_clause() {
+    let unit = "unit Unit1;\r\n\r\ninterface\r\n\r\nimplementation\r\n\r\nprocedure Run;\r\nbegin\r\n  ShowMessage('x');\r\nend;\r\n\r\nend.\r\n";
+    let source = fixed(unit, diagnostic(9, "E2003", "Undeclared identifier: 'ShowMessage'"));
+    assert!(source.contains("implementation\r\n\r\nuses\r\n  Vcl.Dialogs;\r\n\r\nprocedure Run;"), "{source}");
+}
+
+#[test]
+fn adds_unit_to_program_uses() {
+    let program = "program App;\n\nuses\n  Vcl.Forms,\n  Main in 'Main.pas' {Form1};\n\nbegin\n  Writeln(DateToStr(Now));\nend.\n";
+    let source = fixed(program, diagnostic(8, "E2003", "Undeclared identifier: 'DateToStr'"));
+    assert!(source.contains("  Main in 'Main.pas' {Form1}, System.SysUtils;\n"), "{source}");
+
+    let program = "program App;\n\nbegin\n  Sleep(10);\nend.\n";
+    let source = fixed(program, diagnostic(4, "E2003", "Undeclared identifier: 'Sleep'"));
+    assert!(source.starts_with("program App;\n\nuses\n  System.SysUtils;\n\nbegin"), "{source}");
+}
+
+#[test]
+fn follows_unscoped_unit_names() {
+    let unit = UNIT.replace("System.Classes", "Classes").replace("System.Math", "Math");
+    let source = fixed(&unit, diagnostic(20, "E2003", "Undeclared identifier: 'ContainsText'"));
+    assert!(source.contains("uses\n  Math, StrUtils;\n"), "{source}");
+}
+
+#[test]
+fn skips_units_already_used_and_unknown_identifiers() {
+    assert!(fixes(UNIT, diagnostic(20, "E2003", "Undeclared identifier: 'Floor'"), &[]).is_empty());
+    assert!(fixes(UNIT, diagnostic(20, "E2003", "Undeclared identifier: 'Frobnicate'"), &[]).is_empty());
+}
+
+#[test]
+fn ignores_uses_in_comments_and_strings() {
+    let unit = "unit Unit1;\n\ninterface\n\n{ uses System.SysUtils; }\n// uses System.SysUtils;\n\nimplementation\n\nuses\n  System.Math; // uses 'x;'\n\nconst S = 'uses System.SysUtils;';\n\nend.\n";
+    let source = fixed(unit, diagnostic(14, "E2003", "Undeclared identifier: 'Trim'"));
+    assert!(source.contains("uses\n  System.Math, System.SysUtils; // uses 'x;'\n"), "{source}");
+}
+
+#[test]
+fn finds_declarations_in_project_units() {
+    let dir = tempfile::tempdir().unwrap();
+    let helpers = dir.path().join("App.Helpers.pas");
+    std::fs::write(&helpers, "unit App.Helpers;
+
+interface
+
+uses
+  System.Classes;
+
+type
+  TShapeKind = (skCircle, skSquare);
+  TTimer = class
+  end;
+  TBox<T> = record
+    Value: T;
+  end;
+  THelper = class(TObject)
+    procedure Hidden;
+  end;
+  EHelper = class(Exception);
+
+const
+  DefaultSize = 10;
+
+function Clamp(Value, Low, High: Integer): Integer;
+
+implementation
+
+function Secret: Integer;
+begin
+  Result := 0;
+end;
+
+end.
+").unwrap();
+    std::fs::write(dir.path().join("Other.pas"), "unit Other;\n\ninterface\n\nvar\n  Counter, Clamp: Integer;\n\nimplementation\n\nend.\n").unwrap();
+    let units = vec![helpers, dir.path().join("Other.pas")];
+    let titles = |identifier: &str| -> Vec<String> {
+        let message = format!("Undeclared identifier: '{identifier}'");
+        fixes(UNIT, diagnostic(20, "E2003", &message), &units).into_iter().map(|(title, _)| title).collect()
+    };
+    for identifier in ["skSquare", "TBox", "TShapeKind", "THelper", "EHelper", "DefaultSize"] {
+        assert_eq!(titles(identifier), vec!["Add 'App.Helpers' to the uses clause"], "{identifier}");
+    }
+    assert_eq!(titles("Clamp"), vec!["Add 'App.Helpers' to the uses clause", "Add 'Other' to the uses clause"]);
+    assert_eq!(titles("Counter"), vec!["Add 'Other' to the uses clause"]);
+    // The well-known unit comes first.
+    assert_eq!(titles("TTimer"), vec!["Add 'Vcl.ExtCtrls' to the uses clause", "Add 'App.Helpers' to the uses clause"]);
+    // Members, parameters and implementation-only routines are not visible.
+    for identifier in ["Hidden", "Value", "Low", "Secret"] {
+        assert!(titles(identifier).is_empty(), "{identifier}");
+    }
+
+    let actions = quick_fixes(&uri(), UNIT, &[diagnostic(20, "E2003", "Undeclared identifier: 'Clamp'")], &units);
+    assert!(actions.iter().all(|action| action.is_preferred == Some(false)));
+}
+
+#[test]
+fn adds_unit_of_not_expanded_inline_function() {
+    let (title, source) = fixes(
+        UNIT,
+        diagnostic(20, "H2443", "Inline function 'GetTickCount' has not been expanded because unit 'Winapi.Windows' is not specified in USES list"),
+        &[],
+    )
+    .remove(0);
+    assert_eq!(title, "Add 'Winapi.Windows' to the uses clause");
+    assert!(source.contains("uses\n  System.Math, Winapi.Windows;\n"), "{source}");
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  H2164 – remove an unused variable
+// ═══════════════════════════════════════════════════════════════════════════════
+
+const ROUTINE: &str = "procedure Run;
+var
+  I: Integer;
+  A, B, C: string;
+begin
+  I := 0;
+end;
+";
+
+#[test]
+fn removes_unused_variable_line() {
+    let (title, source) = fixes(ROUTINE, diagnostic(3, "H2164", "Variable 'I' is declared but never used in 'Run'"), &[]).remove(0);
+    assert_eq!(title, "Remove unused variable 'I'");
+    assert_eq!(source, ROUTINE.replace("  I: Integer;\n", ""));
+}
+
+#[test]
+fn removes_unused_variable_from_a_list() {
+    let message = |name: &str| format!("Variable '{name}' is declared but never used in 'Run'");
+    assert!(fixed(ROUTINE, diagnostic(4, "H2164", &message("a"))).contains("\n  B, C: string;\n"));
+    assert!(fixed(ROUTINE, diagnostic(4, "H2164", &message("B"))).contains("\n  A, C: string;\n"));
+    assert!(fixed(ROUTINE, diagnostic(4, "H2164", &message("C"))).contains("\n  A, B: string;\n"));
+    assert!(fixes(ROUTINE, diagnostic(4, "H2164", &message("D")), &[]).is_empty());
+}
+
+#[test]
+fn removes_var_keyword_with_its_only_variable() {
+    let routine = "procedure Run;\nvar\n  I: Integer;\nbegin\nend;\n";
+    let source = fixed(routine, diagnostic(3, "H2164", "Variable 'I' is declared but never used in 'Run'"));
+    assert_eq!(source, "procedure Run;\nbegin\nend;\n");
+
+    let routine = "procedure Run;\nvar I: Integer;\n    J: Integer;\nbegin\nend;\n";
+    let source = fixed(routine, diagnostic(2, "H2164", "Variable 'I' is declared but never used in 'Run'"));
+    assert_eq!(source, "procedure Run;\nvar\n    J: Integer;\nbegin\nend;\n");
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  W1057 – cast explicitly
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn casts_assigned_value() {
+    let source = "begin\n  S := Copy(A, 1, 2) + B; // cast\nend;\n";
+    let (title, fixed) = fixes(source, diagnostic(2, "W1057", "Implicit string cast from 'AnsiString' to 'string'"), &[]).remove(0);
+    assert_eq!(title, "Cast to 'string' explicitly");
+    assert_eq!(fixed, "begin\n  S := string(Copy(A, 1, 2) + B); // cast\nend;\n");
+
+    let source = "begin\n  Label1.Caption := Name\nend;\n";
+    assert_eq!(
+        self::fixed(source, diagnostic(2, "W1057", "Implicit string cast from 'AnsiString' to 'string'")),
+        "begin\n  Label1.Caption := string(Name)\nend;\n",
+    );
+}
+
+#[test]
+fn no_cast_without_assignment_or_when_cast_already() {
+    let message = "Implicit string cast from 'AnsiString' to 'string'";
+    assert!(fixes("begin\n  Foo(A);\nend;\n", diagnostic(2, "W1057", message), &[]).is_empty());
+    assert!(fixes("begin\n  S := string(A);\nend;\n", diagnostic(2, "W1057", message), &[]).is_empty());
+}
+
+#[test]
+fn other_codes_have_no_fixes() {
+    assert!(fixes(UNIT, diagnostic(20, "W1036", "Variable 'I' might not have been initialized"), &[]).is_empty());
+}
+
+#[test]
+fn fix_refers_to_its_diagnostic() {
+    let diagnostic = diagnostic(20, "E2003", "Undeclared identifier: 'IntToStr'");
+    let actions = quick_fixes(&uri(), UNIT, std::slice::from_ref(&diagnostic), &[]);
+    assert_eq!(actions[0].kind, Some(CodeActionKind::QUICKFIX));
+    assert_eq!(actions[0].diagnostics, Some(vec![diagnostic]));
+    assert_eq!(actions[0].is_preferred, Some(true));
+}
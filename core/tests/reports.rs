@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use ddk_core::commands::{CompileOutput, ProjectCompileResult};
+use ddk_core::projects::{CompileDiagnostic, DiagnosticKind};
+use ddk_core::reports::{SARIF_VERSION, relative_to_root, to_junit_xml, to_sarif};
+
+mod common;
+
+fn diagnostic(file: &str, code: &str, severity: DiagnosticKind) -> CompileDiagnostic {
+    CompileDiagnostic { column: Some(3), ..common::diagnostic(file, 12, code, severity, &format!("{code} message")) }
+}
+
+fn output(diagnostics: Vec<CompileDiagnostic>) -> CompileOutput {
+    CompileOutput {
+        project_name: "MyProject".into(),
+        success: true,
+        cancelled: false,
+        code: 0,
+        lines: vec![],
+        compiler_name: "Delphi 12 Athens".into(),
+        projects: vec![ProjectCompileResult::new(1, "MyProject".into(), true, false, 0, &diagnostics)],
+        diagnostics,
+        baseline: None,
+        matrix: Vec::new(),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  relative_to_root
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn relative_to_root_handles_windows_paths_case_insensitively() {
+    assert_eq!(
+        relative_to_root(r"c:\Repo\src\Unit1.pas", Path::new(r"C:\repo")),
+        Some("src/Unit1.pas".to_string())
+    );
+}
+
+#[test]
+fn relative_to_root_rejects_outside_and_sibling_prefix() {
+    assert_eq!(relative_to_root(r"C:\Other\Unit1.pas", Path::new(r"C:\Repo")), None);
+    assert_eq!(relative_to_root(r"C:\Repo2\Unit1.pas", Path::new(r"C:\Repo")), None);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  to_sarif
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn sarif_has_version_tool_and_deduplicated_rules() {
+    let sarif = to_sarif(
+        &output(vec![
+            diagnostic(r"C:\Repo\src\Unit1.pas", "W1000", DiagnosticKind::WARN),
+            diagnostic(r"C:\Repo\src\Unit2.pas", "W1000", DiagnosticKind::WARN),
+            diagnostic(r"C:\Repo\src\Unit2.pas", "H2164", DiagnosticKind::HINT),
+        ]),
+        Some(Path::new(r"C:\Repo")),
+    );
+    assert_eq!(sarif["version"], SARIF_VERSION);
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "Delphi 12 Athens");
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0]["id"], "W1000");
+    assert_eq!(rules[1]["id"], "H2164");
+    assert_eq!(run["results"][1]["ruleIndex"], 0);
+    assert_eq!(run["results"][2]["ruleIndex"], 1);
+    assert_eq!(run["results"][2]["level"], "note");
+}
+
+#[test]
+fn sarif_result_location_is_relative_to_root() {
+    let sarif = to_sarif(
+        &output(vec![diagnostic(r"C:\Repo\src\My Unit.pas", "E2003", DiagnosticKind::ERROR)]),
+        Some(Path::new(r"C:\Repo")),
+    );
+    let run = &sarif["runs"][0];
+    assert_eq!(run["originalUriBaseIds"]["SRCROOT"]["uri"], "file:///C:/Repo/");
+    let result = &run["results"][0];
+    assert_eq!(result["level"], "error");
+    assert_eq!(result["message"]["text"], "E2003 message");
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/My%20Unit.pas");
+    assert_eq!(location["artifactLocation"]["uriBaseId"], "SRCROOT");
+    assert_eq!(location["region"]["startLine"], 12);
+    assert_eq!(location["region"]["startColumn"], 3);
+}
+
+#[test]
+fn sarif_location_outside_root_is_absolute_uri() {
+    let sarif = to_sarif(
+        &output(vec![diagnostic(r"C:\Libs\Vcl.pas", "W1000", DiagnosticKind::WARN)]),
+        Some(Path::new(r"C:\Repo")),
+    );
+    let location = &sarif["runs"][0]["results"][0]["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "file:///C:/Libs/Vcl.pas");
+    assert!(location["artifactLocation"]["uriBaseId"].is_null());
+}
+
+#[test]
+fn sarif_fatal_error_without_position_has_no_region() {
+    let mut fatal = diagnostic(r"C:\Repo\App.dproj", "F1026", DiagnosticKind::FATAL);
+    fatal.line = 0;
+    fatal.column = None;
+    let output = output(vec![fatal]);
+    assert_eq!(output.projects[0].errors, 1);
+    let sarif = to_sarif(&output, Some(Path::new(r"C:\Repo")));
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["level"], "error");
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "App.dproj");
+    assert!(location["region"].is_null());
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  to_junit_xml
+// ═══════════════════════════════════════════════════════════════════════════════
+
+fn project(id: usize, name: &str, success: bool, cancelled: bool, duration_ms: u64) -> ProjectCompileResult {
+    let mut result = ProjectCompileResult::new(id, name.into(), success, cancelled, if success { 0 } else { 1 }, &[]);
+    result.duration_ms = duration_ms;
+    result
+}
+
+fn bulk_output() -> CompileOutput {
+    let mut error = diagnostic(r"C:\Repo\Core\Unit1.pas", "E2003", DiagnosticKind::ERROR);
+    error.project_id = 2;
+    error.message = "Undeclared identifier: 'Foo<T>'".into();
+    let mut warning = diagnostic(r"C:\Repo\Core\Unit1.pas", "W1000", DiagnosticKind::WARN);
+    warning.project_id = 2;
+    CompileOutput {
+        project_name: "Main & Tools".into(),
+        success: false,
+        cancelled: false,
+        code: 1,
+        lines: vec![],
+        compiler_name: "Delphi 12 Athens".into(),
+        diagnostics: vec![error, warning],
+        projects: vec![
+            project(1, "App", true, false, 1500),
+            project(2, "Core", false, false, 250),
+            project(3, "Tools", false, true, 0),
+        ],
+        baseline: None,
+        matrix: Vec::new(),
+    }
+}
+
+#[test]
+fn junit_counts_and_suite_name() {
+    let xml = to_junit_xml(&bulk_output());
+    assert!(xml.starts_with("<?xml"));
+    assert!(xml.contains(
+        r#"<testsuite name="Main &amp; Tools" tests="3" failures="1" errors="0" skipped="1" time="1.750">"#
+    ));
+}
+
+#[test]
+fn junit_testcase_per_project_with_time() {
+    let xml = to_junit_xml(&bulk_output());
+    assert!(xml.contains(r#"<testcase classname="Main &amp; Tools" name="App" time="1.500"/>"#));
+    assert!(xml.contains(r#"name="Tools" time="0.000">"#));
+    assert!(xml.contains("<skipped"));
+}
+
+#[test]
+fn junit_failure_contains_only_error_lines() {
+    let xml = to_junit_xml(&bulk_output());
+    assert!(xml.contains(r#"<failure message="Compilation failed with exit code 1 (1 error(s))""#));
+    assert!(xml.contains("[ERROR][E2003]"));
+    assert!(xml.contains("Undeclared identifier: &apos;Foo&lt;T&gt;&apos;"));
+    assert!(!xml.contains("W1000"));
+}
+
+#[test]
+fn junit_reports_projects_not_compiled_after_a_failure_as_skipped() {
+    let mut output = bulk_output();
+    let mut not_compiled = project(4, "Setup", false, false, 0);
+    not_compiled.skipped = true;
+    output.projects.push(not_compiled);
+    let xml = to_junit_xml(&output);
+    assert!(xml.contains(r#"tests="4" failures="1" errors="0" skipped="2""#));
+    assert!(xml.contains(r#"<skipped message="Not compiled because the compilation stopped before this project."/>"#));
+}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::PathBuf;
+
+use ddk_core::files::sources::{collect_sources, main_source_unit_paths};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ddk_sources_{}_{}", name, std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  collect_sources
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn collects_delphi_sources_and_skips_history() {
+    let dir = scratch_dir("collect");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::create_dir_all(dir.join("__history")).unwrap();
+    fs::write(dir.join("App.dpr"), "program App;").unwrap();
+    fs::write(dir.join("sub").join("Unit1.pas"), "unit Unit1;").unwrap();
+    fs::write(dir.join("sub").join("defs.inc"), "").unwrap();
+    fs::write(dir.join("sub").join("notes.txt"), "").unwrap();
+    fs::write(dir.join("__history").join("Unit1.pas.~1~"), "").unwrap();
+    fs::write(dir.join("__history").join("Old.pas"), "").unwrap();
+
+    let names: Vec<String> = collect_sources(&dir)
+        .unwrap()
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, vec!["App.dpr", "Unit1.pas", "defs.inc"]);
+    _ = fs::remove_dir_all(&dir);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+//  main_source_unit_paths
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn reads_existing_unit_paths_from_uses_clause() {
+    let dir = scratch_dir("uses");
+    fs::create_dir_all(dir.join("app").join("src")).unwrap();
+    fs::create_dir_all(dir.join("shared")).unwrap();
+    fs::write(dir.join("app").join("src").join("Main.pas"), "unit Main;").unwrap();
+    fs::write(dir.join("shared").join("Util.pas"), "unit Util;").unwrap();
+    let dpr = dir.join("app").join("App.dpr");
+    fs::write(
+        &dpr,
+        "program App;\r\nuses\r\n  Forms,\r\n  Main in 'src\\Main.pas' {MainForm},\r\n  Util in '..\\shared\\Util.pas',\r\n  Gone in 'src\\Gone.pas';\r\n",
+    )
+    .unwrap();
+
+    let paths = main_source_unit_paths(&dpr);
+    assert_eq!(paths.len(), 2);
+    assert!(paths[0].ends_with(PathBuf::from("src").join("Main.pas")));
+    assert!(paths[1].ends_with("Util.pas"));
+    _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use ddk_core::projects::Project;
+use ddk_core::projects::up_to_date::{is_up_to_date, source_files};
+
+/// A `.dproj` building `main_source` with `properties` in the base
+/// configuration.
+fn dproj(main_source: &str, properties: &str) -> String {
+    format!(
+        r#"<Project xmlns="http://schemas.microsoft.com/developer/msbuild/2003">
+    <PropertyGroup>
+        <MainSource>{main_source}</MainSource>
+        <Config Condition="'$(Config)'==''">Debug</Config>
+        <Platform Condition="'$(Platform)'==''">Win32</Platform>
+    </PropertyGroup>
+    <PropertyGroup Condition="'$(Base)'!=''">
+        {properties}
+    </PropertyGroup>
+    <ItemGroup>
+        <BuildConfiguration Include="Base">
+            <Key>Base</Key>
+        </BuildConfiguration>
+        <BuildConfiguration Include="Debug">
+            <Key>Cfg_1</Key>
+            <CfgParent>Base</CfgParent>
+        </BuildConfiguration>
+    </ItemGroup>
+</Project>"#
+    )
+}
+
+fn write(path: &Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+/// Set the modification time of `path` to `seconds` after a fixed point.
+fn touch(path: &Path, seconds: u64) {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + seconds);
+    std::fs::File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+}
+
+/// An application with a form unit, a unit found on the search path and an
+/// include file; every file is dated 0 and the output 10.
+fn application(dir: &Path) -> (Project, PathBuf) {
+    write(&dir.join("App.dproj"), &dproj("App.dpr", r"<DCC_ExeOutput>.\bin</DCC_ExeOutput><DCC_UnitSearchPath>..\lib;$(DCC_UnitSearchPath)</DCC_UnitSearchPath>"));
+    write(
+        &dir.join("App.dpr"),
+        "program App;\n\nuses\n  Vcl.Forms,\n  Main in 'forms\\Main.pas' {MainForm};\n\n{$R *.res}\n\nbegin\nend.\n",
+    );
+    write(&dir.join("App.res"), "");
+    write(
+        &dir.join("forms/Main.pas"),
+        "unit Main;\n\ninterface\n\nuses\n  System.SysUtils, Helpers;\n\n{$I Settings.inc}\n\nimplementation\n\n{$R *.dfm}\n{$R-}\n\nend.\n",
+    );
+    write(&dir.join("forms/Main.dfm"), "object MainForm: TMainForm\nend\n");
+    write(&dir.join("forms/Settings.inc"), "{$DEFINE FAST}\n");
+    write(&dir.join("../lib/Helpers.pas"), "unit Helpers;\n\ninterface\n\nuses Tools;\n\nimplementation\n\nend.\n");
+    write(&dir.join("../lib/Tools.pas"), "unit Tools;\n\ninterface\n\nimplementation\n\nend.\n");
+    write(&dir.join("../lib/Unused.pas"), "unit Unused;\n\ninterface\n\nimplementation\n\nend.\n");
+    let output = dir.join("bin/App.exe");
+    write(&output, "");
+    for file in ["App.dproj", "App.dpr", "App.res", "forms/Main.pas", "forms/Main.dfm", "forms/Settings.inc"] {
+        touch(&dir.join(file), 0);
+    }
+    for file in ["Helpers.pas", "Tools.pas", "Unused.pas"] {
+        touch(&dir.join("../lib").join(file), 0);
+    }
+    touch(&output, 10);
+    let project = Project {
+        id: 1,
+        name: "App".into(),
+        directory: dir.to_string_lossy().to_string(),
+        dproj: Some(dir.join("App.dproj").to_string_lossy().to_string()),
+        dpr: Some(dir.join("App.dpr").to_string_lossy().to_string()),
+        ..Default::default()
+    };
+    (project, output)
+}
+
+fn names(files: &[PathBuf]) -> Vec<String> {
+    let mut names: Vec<String> = files.iter().map(|f| f.file_name().unwrap().to_string_lossy().to_string()).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn sources_follow_uses_clauses_and_directives() {
+    let root = tempfile::tempdir().unwrap();
+    let dir = root.path().join("app");
+    let _ = application(&dir);
+    let files = source_files(&dir.join("App.dpr"), &[root.path().join("lib")]);
+    assert_eq!(
+        names(&files),
+        vec!["App.dpr", "App.res", "Helpers.pas", "Main.dfm", "Main.pas", "Settings.inc", "Tools.pas"]
+    );
+    // Without the search path the library units are not found.
+    let files = source_files(&dir.join("App.dpr"), &[]);
+    assert_eq!(names(&files), vec!["App.dpr", "App.res", "Main.dfm", "Main.pas", "Settings.inc"]);
+}
+
+#[test]
+fn project_is_up_to_date_until_a_source_changes() {
+    let root = tempfile::tempdir().unwrap();
+    let dir = root.path().join("app");
+    let (project, output) = application(&dir);
+    let up_to_date = || is_up_to_date(&project, "Debug", "Win32", HashMap::new());
+    assert!(up_to_date());
+
+    for file in ["../lib/Tools.pas", "forms/Main.dfm", "forms/Settings.inc", "App.dproj"] {
+        touch(&dir.join(file), 20);
+        assert!(!up_to_date(), "{file}");
+        touch(&dir.join(file), 0);
+    }
+    // Units that are not used do not matter.
+    touch(&dir.join("../lib/Unused.pas"), 20);
+    assert!(up_to_date());
+
+    std::fs::remove_file(&output).unwrap();
+    assert!(!up_to_date());
+}
+
+#[test]
+fn package_output_is_its_bpl() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    write(&dir.join("Pkg.dpk"), "package Pkg;\n\ncontains\n  PkgUnit in 'PkgUnit.pas';\n\nend.\n");
+    write(&dir.join("PkgUnit.pas"), "unit PkgUnit;\n\ninterface\n\nimplementation\n\nend.\n");
+    let mut project = Project {
+        id: 1,
+        name: "Pkg".into(),
+        dproj: Some(dir.join("Pkg.dproj").to_string_lossy().to_string()),
+        dpk: Some(dir.join("Pkg.dpk").to_string_lossy().to_string()),
+        ..Default::default()
+    };
+    // Without a BPL output directory the output is not known.
+    write(&dir.join("Pkg.dproj"), &dproj("Pkg.dpk", ""));
+    assert!(!is_up_to_date(&project, "Debug", "Win32", HashMap::new()));
+
+    write(&dir.join("Pkg.dproj"), &dproj("Pkg.dpk", r"<DCC_BplOutput>bpl</DCC_BplOutput>"));
+    write(&dir.join("bpl/Pkg.bpl"), "");
+    for file in ["Pkg.dproj", "Pkg.dpk", "PkgUnit.pas"] {
+        touch(&dir.join(file), 0);
+    }
+    touch(&dir.join("bpl/Pkg.bpl"), 10);
+    assert!(is_up_to_date(&project, "Debug", "Win32", HashMap::new()));
+    touch(&dir.join("PkgUnit.pas"), 20);
+    assert!(!is_up_to_date(&project, "Debug", "Win32", HashMap::new()));
+
+    // A project without a .dproj is always built.
+    project.dproj = None;
+    touch(&dir.join("PkgUnit.pas"), 0);
+    assert!(!is_up_to_date(&project, "Debug", "Win32", HashMap::new()));
+}
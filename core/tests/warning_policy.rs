@@ -0,0 +1,70 @@
+use ddk_core::projects::*;
+
+fn policy(errors: &[&str], suppress: &[&str], max_warnings: Option<usize>) -> WarningPolicy {
+    WarningPolicy {
+        errors: errors.iter().map(|code| code.to_string()).collect(),
+        suppress: suppress.iter().map(|code| code.to_string()).collect(),
+        max_warnings,
+    }
+}
+
+mod common;
+
+fn diagnostic(code: &str, severity: DiagnosticKind) -> CompileDiagnostic {
+    common::diagnostic(r"C:\Projects\Unit1.pas", 10, code, severity, "message")
+}
+
+#[test]
+fn severity_follows_the_policy() {
+    let policy = policy(&["W1036", "h2443"], &["H2164", "W1036"], None);
+    assert_eq!(policy.severity("W1036", DiagnosticKind::WARN), Some(DiagnosticKind::ERROR));
+    assert_eq!(policy.severity("H2443", DiagnosticKind::HINT), Some(DiagnosticKind::ERROR));
+    assert_eq!(policy.severity("h2164", DiagnosticKind::HINT), None);
+    assert_eq!(policy.severity("W1000", DiagnosticKind::WARN), Some(DiagnosticKind::WARN));
+    // Errors are never suppressed or changed.
+    let policy = self::policy(&[], &["E2003", "F1026"], None);
+    assert_eq!(policy.severity("E2003", DiagnosticKind::ERROR), Some(DiagnosticKind::ERROR));
+    assert_eq!(policy.severity("F1026", DiagnosticKind::FATAL), Some(DiagnosticKind::FATAL));
+}
+
+#[test]
+fn project_policy_extends_and_overrides_workspace_policy() {
+    let workspace = policy(&["W1036", "W1000"], &["H2164", "H2077"], Some(10));
+    let project = policy(&["H2164"], &["W1000"], None);
+    let merged = workspace.with_project(&project);
+    assert_eq!(merged.severity("W1036", DiagnosticKind::WARN), Some(DiagnosticKind::ERROR));
+    assert_eq!(merged.severity("H2077", DiagnosticKind::HINT), None);
+    assert_eq!(merged.severity("H2164", DiagnosticKind::HINT), Some(DiagnosticKind::ERROR));
+    assert_eq!(merged.severity("W1000", DiagnosticKind::WARN), None);
+    assert_eq!(merged.max_warnings, Some(10));
+    assert_eq!(workspace.with_project(&policy(&[], &[], Some(0))).max_warnings, Some(0));
+    assert_eq!(WarningPolicy::default().with_project(&project), project);
+}
+
+#[test]
+fn violations_report_promoted_codes_and_too_many_warnings() {
+    let policy = policy(&["W1036"], &[], Some(1));
+    let diagnostics = vec![
+        diagnostic("W1036", DiagnosticKind::ERROR),
+        diagnostic("W1036", DiagnosticKind::ERROR),
+        diagnostic("W1000", DiagnosticKind::WARN),
+        diagnostic("W1057", DiagnosticKind::WARN),
+        diagnostic("H2164", DiagnosticKind::HINT),
+    ];
+    assert_eq!(policy.violations(&diagnostics), vec![
+        "2 warning(s)/hint(s) treated as errors: W1036".to_string(),
+        "2 warning(s), more than the 1 allowed".to_string(),
+    ]);
+    assert!(policy.violations(&diagnostics[2..3]).is_empty());
+    assert!(WarningPolicy::default().violations(&diagnostics).is_empty());
+}
+
+#[test]
+fn policy_is_omitted_from_saved_projects_when_empty() {
+    let project = Project { id: 1, name: "App".into(), ..Default::default() };
+    assert!(!ron::to_string(&project).unwrap().contains("warning_policy"));
+    let project = Project { warning_policy: policy(&["W1036"], &[], Some(0)), ..project };
+    let saved = ron::to_string(&project).unwrap();
+    assert_eq!(ron::from_str::<Project>(&saved).unwrap(), project);
+    assert_eq!(project.warning_policy.to_string(), "errors: W1036; at most 0 warning(s)");
+}
@@ -13,7 +13,10 @@ use serde_json::Value;
 use std::sync::Arc;
 
 use ddk_core::commands;
-use ddk_core::commands::CompileFilterOptions;
+use ddk_core::commands::{CompileFilterOptions, CompileTarget};
+use ddk_core::projects::DiagnosticKind;
+use ddk_core::projects::history::HistoryQuery;
+use ddk_core::projects::last_diagnostics::DiagnosticsQuery;
 
 // ---------------------------------------------------------------------------
 // README content embedded at compile time
@@ -78,6 +81,11 @@ pub struct SetGroupProjectsCompilerArgs {
     description = "Compiles a Delphi project. \
         Pass project_id to target a specific project (does not change the active project). \
         Omit project_id to compile the currently active project. \
+        Pass workspace_id or group_project to compile every project of a workspace or of the group project; \
+        with keep_going every project is attempted even after a failure and the result lists each project's outcome; \
+        jobs builds independent projects in parallel (dependencies are built first). \
+        For a single project, with_dependencies first builds the projects it is declared to depend on in its workspace. \
+        When VS Code or another DDK process is building, the compile waits for it unless no_wait is set. \
         Use delphi_list_projects to discover IDs. Always match by project name from the user's request. \
         Returns compiler output with the decorative banner stripped. \
         By default warnings and hints are suppressed to save tokens — \
@@ -100,6 +108,25 @@ pub struct CompileSelectedProjectArgs {
     /// Emit a per-file `<file>: X warn, Y hint` summary for any
     /// warnings/hints that were not shown verbatim. Default: false.
     pub summarize_diagnostics: Option<bool>,
+    /// Fail when a warning or hint is not in the project's committed
+    /// baseline (`<project>.ddk-baseline.json`). Default: false.
+    pub fail_on_new_warnings: Option<bool>,
+    /// Compile every project of this workspace instead of a single project.
+    pub workspace_id: Option<u64>,
+    /// Compile every project of the group project instead of a single project.
+    pub group_project: Option<bool>,
+    /// With workspace_id / group_project: keep compiling the remaining
+    /// projects after one fails. Default: false.
+    pub keep_going: Option<bool>,
+    /// With workspace_id / group_project: number of projects built at the
+    /// same time, 0 for one per CPU core. Default: 1.
+    pub jobs: Option<u64>,
+    /// For a single project: build the projects it depends on in its
+    /// workspace first. Default: false.
+    pub with_dependencies: Option<bool>,
+    /// Fail instead of waiting when another DDK process is building.
+    /// Default: false.
+    pub no_wait: Option<bool>,
 }
 
 #[macros::mcp_tool(
@@ -107,15 +134,70 @@ pub struct CompileSelectedProjectArgs {
     description = "Formats a Delphi source file (.pas / .dpr / .dpk) in-place using the DDK formatter. \
         The file is read from disk, reformatted, and written back to the same path. \
         Requires at least one Delphi compiler installation to be present. \
-        Specify the encoding when the file is not UTF-8, e.g. \"windows-1252\" for ANSI or \"oem\" for the system OEM codepage."
+        The encoding is auto-detected (BOM, UTF-8 validity, ANSI fallback) and the original BOM and line endings are preserved. \
+        Only specify the encoding to override detection, e.g. \"windows-1252\" for ANSI or \"oem\" for the system OEM codepage."
 )]
 #[derive(Debug, Deserialize, Serialize, macros::JsonSchema)]
 pub struct FormatFileArgs {
     /// Absolute or relative path to the Delphi source file to format.
     pub file_path: String,
     /// Encoding of the source file, e.g. "utf-8", "windows-1252", "oem".
-    /// Defaults to "utf-8" when not specified.
+    /// Auto-detected when not specified.
     pub encoding: Option<String>,
+    /// Encoding assumed for files without a BOM that are not valid UTF-8,
+    /// e.g. "windows-1252". Defaults to the system ANSI codepage.
+    pub ansi_fallback: Option<String>,
+}
+
+#[macros::mcp_tool(
+    name = "delphi_get_build_history",
+    description = "Returns recorded Delphi builds of every DDK process (VS Code, this MCP server, the ddk CLI), most recent first: \
+        start and finish time, compiler key and product name, rebuild flag, success / cancelled / exit code, and per project its configuration, platform, status, build time and error / warning / hint counts. \
+        Pass project_id to only get builds of that project (success then refers to that project), e.g. with success=true and limit=1 to find when it last built successfully and with which compiler. \
+        Pass build_id to get a single build."
+)]
+#[derive(Debug, Deserialize, Serialize, macros::JsonSchema)]
+pub struct GetBuildHistoryArgs {
+    /// Only builds that compiled this project.
+    pub project_id: Option<u64>,
+    /// Only builds with this compiler configuration key.
+    pub compiler: Option<String>,
+    /// Only successful (true) or failed / cancelled (false) builds.
+    pub success: Option<bool>,
+    /// Maximum number of builds. Default: 10.
+    pub limit: Option<u64>,
+    /// Return only the build with this ID.
+    pub build_id: Option<u64>,
+}
+
+#[macros::mcp_tool(
+    name = "delphi_get_diagnostics",
+    description = "Returns the errors, warnings and hints of the last build of each Delphi project, without compiling again. \
+        The diagnostics are kept from the last compile of every DDK process (VS Code, this MCP server, the ddk CLI), so use this instead of recompiling to look at warnings again. \
+        Each project comes with the build ID and time the diagnostics are from. \
+        Filter by project_id, severity (\"fatal\", \"error\" (including fatal), \"warning\" or \"hint\"), file (part of the file path, ignoring case) and code (e.g. \"W1036\")."
+)]
+#[derive(Debug, Deserialize, Serialize, macros::JsonSchema)]
+pub struct GetDiagnosticsArgs {
+    /// Only diagnostics of this project.
+    pub project_id: Option<u64>,
+    /// Only diagnostics of this severity: "fatal", "error" (including fatal), "warning" or "hint".
+    pub severity: Option<String>,
+    /// Only diagnostics whose file path contains this text, ignoring case.
+    pub file: Option<String>,
+    /// Only diagnostics with this code, e.g. "W1036".
+    pub code: Option<String>,
+}
+
+#[macros::mcp_tool(
+    name = "delphi_explain_diagnostic",
+    description = "Explains a Delphi compiler message by its code (e.g. \"W1035\", \"E2003\", \"F2613\"): what the message means, its common causes and how to fix it, plus a link to the RAD Studio documentation. \
+        Use it on the codes returned by delphi_compile_project or delphi_get_diagnostics before changing the code."
+)]
+#[derive(Debug, Deserialize, Serialize, macros::JsonSchema)]
+pub struct ExplainDiagnosticArgs {
+    /// The message code, e.g. "W1035".
+    pub code: String,
 }
 
 rust_mcp_sdk::tool_box!(DdkTools, [
@@ -127,6 +209,9 @@ rust_mcp_sdk::tool_box!(DdkTools, [
     SetGroupProjectsCompilerArgs,
     CompileSelectedProjectArgs,
     FormatFileArgs,
+    GetBuildHistoryArgs,
+    GetDiagnosticsArgs,
+    ExplainDiagnosticArgs,
 ]);
 
 // ---------------------------------------------------------------------------
@@ -166,6 +251,9 @@ impl ServerHandler for DdkMcpHandler {
             "delphi_set_group_projects_compiler" => set_group_projects_compiler(&args).await,
             "delphi_compile_project"          => compile_project(&args).await,
             "delphi_format_file"              => format_file(&args).await,
+            "delphi_get_build_history"        => get_build_history(&args).await,
+            "delphi_get_diagnostics"          => get_diagnostics(&args).await,
+            "delphi_explain_diagnostic"       => explain_diagnostic(&args).await,
             _ => format!("Unknown tool: {name}"),
         };
         Ok(CallToolResult::text_content(vec![TextContent::from(result_text)]))
@@ -235,8 +323,23 @@ async fn compile_project(args: &Value) -> String {
             .get("summarize_diagnostics")
             .and_then(|v| v.as_bool())
             .unwrap_or(false),
+        fail_on_new_warnings: args
+            .get("fail_on_new_warnings")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        keep_going: args.get("keep_going").and_then(|v| v.as_bool()).unwrap_or(false),
+        jobs: args.get("jobs").and_then(|v| v.as_u64()).map(|jobs| jobs as usize),
+        with_dependencies: args.get("with_dependencies").and_then(|v| v.as_bool()).unwrap_or(false),
+        no_wait: args.get("no_wait").and_then(|v| v.as_bool()).unwrap_or(false),
+    };
+    let workspace_id = args.get("workspace_id").and_then(|v| v.as_u64()).map(|id| id as usize);
+    let group_project = args.get("group_project").and_then(|v| v.as_bool()).unwrap_or(false);
+    let target = match (workspace_id, group_project) {
+        (Some(id), _) => CompileTarget::Workspace(id),
+        (_, true) => CompileTarget::GroupProject,
+        _ => CompileTarget::Project(project_id),
     };
-    match commands::cmd_compile(rebuild, project_id, filter).await {
+    match commands::cmd_compile_target(target, rebuild, filter, None).await {
         Ok(output) => output.to_string(),
         Err(e) => format!("{e}"),
     }
@@ -250,8 +353,57 @@ async fn format_file(args: &Value) -> String {
     let encoding = args
         .get("encoding")
         .and_then(|v| v.as_str().map(|s| s.to_string()));
-    match commands::cmd_format_file(file_path, encoding).await {
+    let ansi_fallback = args.get("ansi_fallback").and_then(|v| v.as_str());
+    match commands::cmd_format_file(file_path, encoding, ansi_fallback).await {
         Ok(path) => format!("{path}"),
         Err(e) => format!("{e}"),
     }
 }
+
+async fn get_build_history(args: &Value) -> String {
+    if let Some(id) = args.get("build_id").and_then(|v| v.as_u64()) {
+        return match commands::cmd_build_history_show(id).await {
+            Ok(build) => serde_json::to_string_pretty(&build).unwrap_or_default(),
+            Err(e) => format!("{e}"),
+        };
+    }
+    let query = HistoryQuery {
+        project_id: args.get("project_id").and_then(|v| v.as_u64()).map(|id| id as usize),
+        compiler_key: args.get("compiler").and_then(|v| v.as_str()).map(|key| key.to_string()),
+        success: args.get("success").and_then(|v| v.as_bool()),
+        limit: Some(args.get("limit").and_then(|v| v.as_u64()).map_or(10, |limit| limit as usize)),
+    };
+    match commands::cmd_build_history(query).await {
+        Ok(result) => serde_json::to_string_pretty(&result).unwrap_or_default(),
+        Err(e) => format!("Error: {e}"),
+    }
+}
+
+async fn get_diagnostics(args: &Value) -> String {
+    let severity = match args.get("severity").and_then(|v| v.as_str()).map(str::parse::<DiagnosticKind>) {
+        Some(Ok(severity)) => Some(severity),
+        Some(Err(e)) => return format!("{e}"),
+        _ => None,
+    };
+    let query = DiagnosticsQuery {
+        project_id: args.get("project_id").and_then(|v| v.as_u64()).map(|id| id as usize),
+        severity,
+        file: args.get("file").and_then(|v| v.as_str()).map(|file| file.to_string()),
+        code: args.get("code").and_then(|v| v.as_str()).map(|code| code.to_string()),
+    };
+    match commands::cmd_last_diagnostics(query).await {
+        Ok(result) => serde_json::to_string_pretty(&result).unwrap_or_default(),
+        Err(e) => format!("{e}"),
+    }
+}
+
+async fn explain_diagnostic(args: &Value) -> String {
+    let code = match args.get("code").and_then(|v| v.as_str()) {
+        Some(code) => code,
+        _ => return "Missing required parameter: code".to_string(),
+    };
+    match commands::cmd_explain_diagnostic(code).await {
+        Ok(explanation) => serde_json::to_string_pretty(&explanation).unwrap_or_default(),
+        Err(e) => format!("{e}"),
+    }
+}
@@ -1,5 +1,8 @@
 use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::io::{stdin, stdout};
+use tokio::sync::RwLock;
 use tower_lsp::{Client, async_trait, jsonrpc};
 use tower_lsp::{LanguageServer, LspService, Server};
 use tower_lsp::lsp_types::*;
@@ -14,11 +17,13 @@ use ddk_core::try_finish_event;
 #[derive(Debug, Clone)]
 struct DelphiLsp {
     client: Client,
+    /// Text of the open Delphi sources, for quick fixes.
+    documents: Arc<RwLock<HashMap<Url, String>>>,
 }
 
 impl DelphiLsp {
     pub fn new(client: Client) -> Self {
-        return DelphiLsp { client }
+        return DelphiLsp { client, documents: Arc::default() }
     }
 
     async fn projects_compile(
@@ -34,12 +39,39 @@ impl DelphiLsp {
 
     async fn projects_compile_cancel(
         &self,
-        _params: CancelCompilationParams,
+        params: CancelCompilationParams,
     ) -> tower_lsp::jsonrpc::Result<()> {
-        ddk_core::projects::compiler_state::cancel();
+        match params.job_id {
+            Some(job_id) => queue::cancel(job_id)
+                .map_err(|e| jsonrpc::Error::invalid_params(e.to_string()))?,
+            // Nothing runs here: the build may belong to another DDK process.
+            _ => {
+                if queue::cancel_running().is_none() {
+                    let _ = build_lock::request_cancel();
+                }
+            }
+        }
         try_finish_event!(self.client, "compilation cancelled");
     }
 
+    async fn projects_compile_queue(
+        &self,
+        _params: CompileQueueParams,
+    ) -> tower_lsp::jsonrpc::Result<CompileQueueResponse> {
+        Ok(CompileQueueResponse { jobs: queue::all_jobs(), lock_holder: build_lock::current_holder() })
+    }
+
+    async fn projects_build_history(
+        &self,
+        params: BuildHistoryParams,
+    ) -> tower_lsp::jsonrpc::Result<BuildHistoryResponse> {
+        let builds = match params.build_id {
+            Some(id) => history::get(id).into_iter().collect(),
+            _ => history::query(&params.query),
+        };
+        Ok(BuildHistoryResponse { builds })
+    }
+
     async fn configuration_fetch(
         &self,
         _params: serde_json::Value,
@@ -58,6 +90,16 @@ impl DelphiLsp {
             ddk_core::encoding::set_encoding(enc);
             lsp_info!(self.client, "Compiler encoding changed to: {}", enc);
         }
+        if let Some(enc) = params.get("ansiEncoding").and_then(|v| v.as_str()) {
+            match ddk_core::encoding::set_ansi_fallback(enc) {
+                Ok(()) => {
+                    lsp_info!(self.client, "Source ANSI fallback encoding changed to: {}", enc);
+                }
+                Err(e) => {
+                    lsp_error!(self.client, "{}", e);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -163,9 +205,24 @@ impl LanguageServer for DelphiLsp {
             if let Some(enc) = opts.get("encoding").and_then(|v| v.as_str()) {
                 ddk_core::encoding::set_encoding(enc);
             }
+            if let Some(enc) = opts.get("ansiEncoding").and_then(|v| v.as_str())
+                && let Err(e) = ddk_core::encoding::set_ansi_fallback(enc)
+            {
+                lsp_error!(self.client, "{}", e);
+            }
         }
         return Ok(InitializeResult {
-            capabilities: ServerCapabilities::default(), // none
+            capabilities: ServerCapabilities {
+                // Explanations of the last build's diagnostics.
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                // Quick fixes for the last build's diagnostics.
+                code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                    code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                    ..Default::default()
+                })),
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+                ..ServerCapabilities::default()
+            },
             server_info: Some(ServerInfo {
                 name: "DDK - Delphi Server".to_string(),
                 version: Some("0.1.0".to_string()),
@@ -178,10 +235,58 @@ impl LanguageServer for DelphiLsp {
     }
 
     async fn shutdown(&self) -> jsonrpc::Result<()> {
-        ddk_core::projects::compiler_state::cancel();
+        queue::cancel_all();
         return Ok(())
     }
 
+    async fn hover(&self, params: HoverParams) -> jsonrpc::Result<Option<Hover>> {
+        let position = params.text_document_position_params;
+        let Ok(file) = position.text_document.uri.to_file_path() else {
+            return Ok(None);
+        };
+        let mut explanations: Vec<&DiagnosticExplanation> = Vec::new();
+        for diagnostic in last_diagnostics::at(&file, position.position.line + 1) {
+            if let Some(explanation) = DiagnosticExplanation::find(&diagnostic.code)
+                && !explanations.contains(&explanation)
+            {
+                explanations.push(explanation);
+            }
+        }
+        if explanations.is_empty() {
+            return Ok(None);
+        }
+        let value = explanations.iter().map(|explanation| explanation.to_markdown()).collect::<Vec<_>>().join("\n---\n\n");
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+            range: None,
+        }))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> jsonrpc::Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+        let documents = self.documents.read().await;
+        let Some(source) = documents.get(&uri) else {
+            return Ok(None);
+        };
+        let actions = code_actions(&uri, source, &params.context.diagnostics).await;
+        Ok((!actions.is_empty()).then_some(actions))
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        self.documents.write().await.insert(params.text_document.uri, params.text_document.text);
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        // Full sync: the last change holds the whole text.
+        if let Some(change) = params.content_changes.into_iter().last() {
+            self.documents.write().await.insert(params.text_document.uri, change.text);
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.documents.write().await.remove(&params.text_document.uri);
+    }
+
     async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
         let client = self.client.clone();
         let settings = params.settings.clone();
@@ -211,6 +316,8 @@ async fn main() -> Result<()> {
         .custom_method("projects/compile", DelphiLsp::projects_compile)
         .custom_method("configuration/fetch", DelphiLsp::configuration_fetch)
         .custom_method("projects/compile-cancel", DelphiLsp::projects_compile_cancel)
+        .custom_method("projects/compile-queue", DelphiLsp::projects_compile_queue)
+        .custom_method("projects/build-history", DelphiLsp::projects_build_history)
         .custom_method("custom/document/format", DelphiLsp::custom_document_format)
         .custom_method("notifications/settings/encoding", DelphiLsp::settings_encoding)
         .custom_method("dproj/metadata", DelphiLsp::dproj_metadata)
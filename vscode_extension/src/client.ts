@@ -22,6 +22,10 @@ export type Change =
     | { type: 'RemoveWorkspace', workspace_id: number }
     | { type: 'MoveWorkspace', workspace_id: number, drop_target: number }
     | { type: 'UpdateWorkspace', workspace_id: number, data: { name?: string; compiler?: string; } }
+    | { type: 'AddProjectDependency', workspace_id: number, project_id: number, depends_on: number }
+    | { type: 'RemoveProjectDependency', workspace_id: number, project_id: number, depends_on: number }
+    | { type: 'SetWorkspaceWarningPolicy', workspace_id: number, policy: Entities.WarningPolicy }
+    | { type: 'SetProjectWarningPolicy', project_id: number, policy: Entities.WarningPolicy }
     | { type: 'AddCompiler', key: string, config: Entities.CompilerConfiguration }
     | { type: 'RemoveCompiler', compiler: string }
     | { type: 'UpdateCompiler', key: string, data: Partial<Entities.CompilerConfiguration> }
@@ -52,17 +56,25 @@ export function newChanges(changes: Change[], timeout: number = 5000): ChangeSet
 }
 
 export type CompilerProgressParams = {
+    kind: 'Queued',
+    job_id: number,
+    jobs_ahead: number,
+} | {
     kind: 'Start',
+    job_id?: number,
     lines: string[],
 } | {
     kind: 'Stdout' | 'Stderr',
     line: string,
+    project_id?: number,
 } | {
     kind: 'Completed',
+    job_id?: number,
     success: boolean,
     cancelled: boolean,
     code: number,
     lines: string[],
+    projects?: ProjectOutcome[],
 } | {
     kind: 'SingleProjectStarted',
     project_id: number,
@@ -76,6 +88,66 @@ export type CompilerProgressParams = {
     lines: string[],
 } | never;
 
+export interface BuildJobInfo {
+    id: number;
+    description: string;
+    state: 'queued' | 'running' | 'succeeded' | 'failed' | 'cancelled';
+    pid: number;
+    program: string;
+    queued_at: number;
+    started_at?: number;
+    finished_at?: number;
+}
+
+interface DiagnosticCounts {
+    errors: number;
+    warnings: number;
+    hints: number;
+}
+
+interface ProjectOutcome {
+    project_id: number;
+    project_name: string;
+    status: 'succeeded' | 'failed' | 'cancelled' | 'skipped' | 'up_to_date';
+    code: number;
+    duration_ms: number;
+    diagnostics?: DiagnosticCounts;
+    policy_violations?: string[];
+}
+
+export interface BuildRecord {
+    id: number;
+    description: string;
+    started_at: number;
+    finished_at: number;
+    compiler_key: string;
+    compiler_name: string;
+    rebuild: boolean;
+    success: boolean;
+    cancelled: boolean;
+    code: number;
+    program: string;
+    projects: {
+        project_id: number;
+        project_name: string;
+        config: string;
+        platform: string;
+        status: ProjectOutcome['status'];
+        code: number;
+        duration_ms: number;
+        diagnostics: DiagnosticCounts;
+        log?: string;
+    }[];
+}
+
+export interface BuildHistoryQuery {
+    project_id?: number;
+    compiler_key?: string;
+    success?: boolean;
+    limit?: number;
+    build_id?: number;
+}
+
 interface ConfigurationData {
     projects: Entities.ProjectsData;
     compilers: Entities.CompilerConfigurations;
@@ -109,10 +181,12 @@ export class DDK_Client {
         };
         const clientOptions: LanguageClientOptions = {
             initializationOptions: {
-                encoding: workspace.getConfiguration(PROJECTS.SETTINGS.SECTION).get<string>(PROJECTS.SETTINGS.COMPILER_ENCODING, 'oem')
-            }
+                encoding: workspace.getConfiguration(PROJECTS.SETTINGS.SECTION).get<string>(PROJECTS.SETTINGS.COMPILER_ENCODING, 'oem'),
+                ansiEncoding: workspace.getConfiguration(PROJECTS.SETTINGS.SECTION).get<string>(PROJECTS.SETTINGS.COMPILER_ANSI_ENCODING, 'ansi')
+            },
+            // Delphi sources, for the hover and the quick fixes on build diagnostics.
+            documentSelector: [{ scheme: 'file', pattern: '**/*.{pas,dpr,dpk,inc}' }]
         };
-        // we can't set the documentSelector until we implement the actual LSP
         clientOptions.outputChannelName = 'DDK Server';
         this.client = new LanguageClient(
             'ddk_server',
@@ -168,6 +242,11 @@ export class DDK_Client {
                         .get<string>(PROJECTS.SETTINGS.COMPILER_ENCODING, 'oem');
                     this.client.sendNotification('notifications/settings/encoding', { encoding });
                 }
+                if (e.affectsConfiguration(`${PROJECTS.SETTINGS.SECTION}.${PROJECTS.SETTINGS.COMPILER_ANSI_ENCODING}`)) {
+                    const ansiEncoding = workspace.getConfiguration(PROJECTS.SETTINGS.SECTION)
+                        .get<string>(PROJECTS.SETTINGS.COMPILER_ANSI_ENCODING, 'ansi');
+                    this.client.sendNotification('notifications/settings/encoding', { ansiEncoding });
+                }
             })
         );
     }
@@ -210,52 +289,78 @@ export class DDK_Client {
         return await Runtime.waitForEvent(changes.event_id);
     }
 
-    public async compileProject(rebuild: boolean, projectId: number, projectLinkId?: number): Promise<boolean> {
+    public async compileProject(rebuild: boolean, projectId: number, projectLinkId?: number, withDependencies: boolean = false): Promise<boolean> {
         const event = Runtime.addEvent(0);
+        this.parallelCompile = false;
         await this.client.sendRequest('projects/compile', {
             type: 'Project',
             project_id: projectId,
             project_link_id: projectLinkId,
             rebuild: rebuild,
             event_id: event,
+            with_dependencies: withDependencies,
         });
         return await Runtime.waitForEvent(event);
     }
 
-    public async compileAllInWorkspace(rebuild: boolean, workspaceId: number): Promise<boolean> {
+    /** Prefix output lines with their project while projects are built in parallel. */
+    private parallelCompile = false;
+
+    public async compileAllInWorkspace(rebuild: boolean, workspaceId: number, keepGoing: boolean = false, jobs?: number): Promise<boolean> {
         const event = Runtime.addEvent(0);
+        this.parallelCompile = jobs !== undefined && jobs !== 1;
         await this.client.sendRequest('projects/compile', {
             type: 'AllInWorkspace',
             workspace_id: workspaceId,
             rebuild: rebuild,
             event_id: event,
+            keep_going: keepGoing,
+            jobs: jobs,
         });
         return await Runtime.waitForEvent(event);
     }
 
-    public async compileAllInGroupProject(rebuild: boolean): Promise<boolean> {
+    public async compileAllInGroupProject(rebuild: boolean, keepGoing: boolean = false, jobs?: number): Promise<boolean> {
         const event = Runtime.addEvent(0);
+        this.parallelCompile = jobs !== undefined && jobs !== 1;
         await this.client.sendRequest('projects/compile', {
             type: 'AllInGroupProject',
             rebuild: rebuild,
             event_id: event,
+            keep_going: keepGoing,
+            jobs: jobs,
         });
         return await Runtime.waitForEvent(event);
     }
 
-    public async compileFromLink(rebuild: boolean, linkId: number): Promise<boolean> {
+    public async compileFromLink(rebuild: boolean, linkId: number, keepGoing: boolean = false, jobs?: number): Promise<boolean> {
         const event = Runtime.addEvent(0);
+        this.parallelCompile = jobs !== undefined && jobs !== 1;
         await this.client.sendRequest('projects/compile', {
             type: 'FromLink',
             project_link_id: linkId,
             rebuild: rebuild,
-            event_id: event
+            event_id: event,
+            keep_going: keepGoing,
+            jobs: jobs,
         });
         return await Runtime.waitForEvent(event);
     }
 
-    public async cancelCompilation(): Promise<void> {
-        await this.client.sendRequest('projects/compile-cancel', {});
+    /** Cancel build queue job `jobId`, or the running build when omitted. */
+    public async cancelCompilation(jobId?: number): Promise<void> {
+        await this.client.sendRequest('projects/compile-cancel', { job_id: jobId });
+    }
+
+    public async compileQueue(): Promise<BuildJobInfo[]> {
+        const response: { jobs: BuildJobInfo[] } = await this.client.sendRequest('projects/compile-queue', {});
+        return response.jobs;
+    }
+
+    /** Recorded builds matching `query`, most recent first. */
+    public async buildHistory(query: BuildHistoryQuery = {}): Promise<BuildRecord[]> {
+        const response: { builds: BuildRecord[] } = await this.client.sendRequest('projects/build-history', query);
+        return response.builds;
     }
 
     public async dprojMetadata(projectId: number): Promise<DprojMetadata> {
@@ -265,6 +370,9 @@ export class DDK_Client {
     public onCompilerProgress(params: CompilerProgressParams) {
         for (const listener of this.compilerProgressListeners) listener(params);
         switch (params.kind) {
+            case 'Queued':
+                window.setStatusBarMessage(`Build queued behind ${params.jobs_ahead} job(s).`, 5000);
+                break;
             case 'Start':
                 this.compilerLinkProvider.compilerIsActive = true;
                 Runtime.setContext(PROJECTS.CONTEXT.IS_COMPILING, true);
@@ -279,7 +387,10 @@ export class DDK_Client {
                 break;
             case 'Stdout':
             case 'Stderr':
-                Runtime.compilerOutputChannel.appendLine(params.line);
+                if (this.parallelCompile && params.project_id !== undefined)
+                    Runtime.compilerOutputChannel.appendLine(`[${params.project_id}] ${params.line}`);
+                else
+                    Runtime.compilerOutputChannel.appendLine(params.line);
                 break;
             case 'SingleProjectStarted':
                 for (const line of params.lines)
@@ -21,6 +21,7 @@ export namespace PROJECTS {
   export namespace SETTINGS {
     export const SECTION = 'ddk';
     export const COMPILER_ENCODING = 'compiler.encoding';
+    export const COMPILER_ANSI_ENCODING = 'compiler.ansiEncoding';
     export const COMPILER_RESULT_TIMEOUT = 'compiler.resultTimeout';
   }
   export namespace COMMAND {
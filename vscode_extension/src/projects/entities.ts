@@ -17,6 +17,21 @@ export namespace Entities {
     sort_rank: string;
     active_configuration?: Option<string>;
     active_platform?: Option<string>;
+    dependencies?: ProjectDependency[];
+    warning_policy?: WarningPolicy;
+  }
+
+  /** Within its workspace, `project_id` is built after `depends_on`. */
+  export class ProjectDependency {
+    project_id: number;
+    depends_on: number;
+  }
+
+  /** Warnings and hints that fail the build, ones that are ignored, and how many warnings a build may report. */
+  export class WarningPolicy {
+    errors?: string[];
+    suppress?: string[];
+    max_warnings?: Option<number>;
   }
 
   export class GroupProject {
@@ -38,6 +53,7 @@ export namespace Entities {
     ini?: Option<string>;
     active_configuration?: Option<string>;
     active_platform?: Option<string>;
+    warning_policy?: WarningPolicy;
   }
 
   export class ProjectLink {
@@ -54,8 +70,17 @@ export namespace Entities {
     compiler_version: number;
     installation_path: string;
     build_arguments: string[];
+    backend?: BuildBackendKind;
+    msbuild_path?: string;
+    env_script?: string;
+    extra_env?: { [name: string]: string };
+    output_language?: OutputLanguage;
   }
 
+  export type OutputLanguage = 'Auto' | 'English' | 'German' | 'French' | 'Japanese';
+
+  export type BuildBackendKind = 'MsBuild' | 'Dcc' | { Custom: { program: string; args: string[] } };
+
   export type CompilerConfigurations = {
     [compilerId: string]: CompilerConfiguration;
   }